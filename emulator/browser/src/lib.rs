@@ -32,7 +32,7 @@ impl DeviceWrapper {
         let interface_size = FloatVector::from(width, height);
 
         let renderer = WebGLRenderer::new(interface_size)?;
-        let device = Device::new(interface_size - FloatVector::with(20.0)); // why is this - 20.0 needed?
+        let device = Device::new(interface_size - FloatVector::with(20.0)).map_err(|error| JsValue::from_str(&error))?; // why is this - 20.0 needed?
 
         return Ok(DeviceWrapper{
             device: device,
@@ -44,6 +44,13 @@ impl DeviceWrapper {
         self.device.render(&mut self.renderer);
     }
 
+    // elapsed is the time since the last animation frame in milliseconds
+    pub fn update(&mut self, elapsed: f64) {
+        if self.device.update((elapsed / 1000.0) as f32) {
+            self.device.render(&mut self.renderer);
+        }
+    }
+
     pub fn resize(&mut self, width: i32, height: i32) {
         let interface_size = FloatVector::from(width as f32, height as f32);
         self.device.resize(interface_size);
@@ -0,0 +1,266 @@
+use std::fmt::{ Display, Formatter, Result as FormatResult };
+use super::Core;
+use super::core::{ ValueState, LabelSource };
+use super::expression::{ Expression, Value };
+
+#[derive(Clone, Debug)]
+pub enum Condition {
+    Changed(String),
+    Expression(String),
+    Assertion(String),
+    Metastable,
+    Cycles(usize),
+}
+
+impl Condition {
+
+    // anything that is not one of the fixed forms is treated as an expression
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let words: Vec<&str> = source.split_whitespace().collect();
+
+        match words.as_slice() {
+
+            ["metastable"] => return Ok(Condition::Metastable),
+
+            ["cycles", count] => {
+                let count = count.parse().map_err(|_| format!("invalid cycle count {}", count))?;
+                return Ok(Condition::Cycles(count));
+            },
+
+            ["changed", signal] => return Ok(Condition::Changed(String::from(*signal))),
+
+            ["assert", ..] => return Ok(Condition::Assertion(String::from(source.trim()["assert".len()..].trim()))),
+
+            _other => return Ok(Condition::Expression(String::from(source.trim()))),
+        }
+    }
+}
+
+impl Display for Condition {
+
+    fn fmt(&self, formatter: &mut Formatter) -> FormatResult {
+        match self {
+            Condition::Changed(signal) => return write!(formatter, "changed {}", signal),
+            Condition::Expression(source) => return write!(formatter, "{}", source),
+            Condition::Assertion(source) => return write!(formatter, "assert {}", source),
+            Condition::Metastable => return write!(formatter, "metastable"),
+            Condition::Cycles(count) => return write!(formatter, "cycles {}", count),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BreakpointHit {
+    pub breakpoint: usize,
+    pub core: String,
+    pub cycle: usize,
+    pub description: String,
+}
+
+impl Display for BreakpointHit {
+
+    fn fmt(&self, formatter: &mut Formatter) -> FormatResult {
+        return write!(formatter, "breakpoint {} hit in {} at cycle {}: {}", self.breakpoint, self.core, self.cycle, self.description);
+    }
+}
+
+pub struct Breakpoint {
+    pub condition: Condition,
+    target: Option<(usize, usize)>,
+    expression: Option<(usize, Expression)>,
+    previous_value: ValueState,
+    // only the signals the expression refers to
+    previous_values: Vec<(usize, Value)>,
+    previous_result: bool,
+    previous_cycles: Vec<usize>,
+    previous_metastable: Vec<Vec<LabelSource>>,
+}
+
+impl Breakpoint {
+
+    pub fn new(condition: Condition, cores: &[Core]) -> Result<Self, String> {
+
+        let target = match &condition {
+            Condition::Changed(signal) => Some(resolve_signal(signal, cores)?),
+            _other => None,
+        };
+
+        let expression = match &condition {
+            Condition::Expression(source) | Condition::Assertion(source) => Some(compile_expression(source, cores)?),
+            _other => None,
+        };
+
+        let mut breakpoint = Self {
+            condition: condition,
+            target: target,
+            expression: expression,
+            previous_value: ValueState::Floating,
+            previous_values: Vec::new(),
+            previous_result: false,
+            previous_cycles: Vec::new(),
+            previous_metastable: Vec::new(),
+        };
+
+        breakpoint.synchronize(cores);
+        return Ok(breakpoint);
+    }
+
+    // remember the current state so only changes from here on are reported
+    pub fn synchronize(&mut self, cores: &[Core]) {
+
+        if let Some((core, signal)) = self.target {
+            self.previous_value = cores[core].signal_state(signal);
+        }
+
+        if let Some((core, expression)) = &self.expression {
+            self.previous_result = self.evaluate_expression(*core, expression, cores).is_true();
+
+            let mut signals = expression.signals();
+            signals.sort_unstable();
+            signals.dedup();
+            self.previous_values = signals.into_iter().map(|signal| (signal, cores[*core].signal_value(signal))).collect();
+        }
+
+        match &self.condition {
+            Condition::Cycles(..) => self.previous_cycles = cores.iter().map(|core| core.cycle_count()).collect(),
+            Condition::Metastable => self.previous_metastable = cores.iter().map(|core| core.metastable_sources()).collect(),
+            _other => { },
+        }
+    }
+
+    // returns the index of the core that triggered the breakpoint and a description of what happened
+    pub fn check(&mut self, cores: &[Core]) -> Option<(usize, String)> {
+        let result = self.evaluate(cores);
+        self.synchronize(cores);
+        return result;
+    }
+
+    fn evaluate_expression(&self, core: usize, expression: &Expression, cores: &[Core]) -> Value {
+        let current = |index: usize| cores[core].signal_value(index);
+        let previous = |index: usize| match self.previous_values.iter().find(|(signal, _value)| *signal == index) {
+            Some((_signal, value)) => *value,
+            None => cores[core].signal_value(index),
+        };
+        return expression.evaluate(&current, &previous);
+    }
+
+    fn evaluate(&self, cores: &[Core]) -> Option<(usize, String)> {
+        match &self.condition {
+
+            Condition::Changed(..) => {
+                let (core, signal) = self.target.unwrap();
+                let state = cores[core].signal_state(signal);
+
+                if state != self.previous_value {
+                    let name = &cores[core].signals()[signal].name;
+                    return Some((core, format!("{} changed from {} to {}", name, value_text(&self.previous_value), value_text(&state))));
+                }
+            },
+
+            // expressions only trigger when they become true, so a run can continue past them
+            Condition::Expression(source) => {
+                let (core, expression) = self.expression.as_ref().unwrap();
+
+                if self.evaluate_expression(*core, expression, cores).is_true() && !self.previous_result {
+                    return Some((*core, format!("{} became true", source)));
+                }
+            },
+
+            Condition::Assertion(source) => {
+                let (core, expression) = self.expression.as_ref().unwrap();
+                let result = self.evaluate_expression(*core, expression, cores);
+
+                if !result.is_true() {
+                    return Some((*core, format!("assertion {} failed with {}", source, value_text(&result.to_number()))));
+                }
+            },
+
+            Condition::Metastable => {
+                for (index, core) in cores.iter().enumerate() {
+                    let previous = self.previous_metastable.get(index);
+
+                    for source in core.metastable_sources() {
+                        if !previous.map(|previous| previous.contains(&source)).unwrap_or(false) {
+                            return Some((index, format!("{} went metastable", core.describe_source(source))));
+                        }
+                    }
+                }
+            },
+
+            Condition::Cycles(count) => {
+                for (index, core) in cores.iter().enumerate() {
+                    let previous = self.previous_cycles.get(index).cloned().unwrap_or(0);
+
+                    if previous < *count && core.cycle_count() >= *count {
+                        return Some((index, format!("{} cycles elapsed", count)));
+                    }
+                }
+            },
+        }
+
+        return None;
+    }
+}
+
+// signals may be prefixed with the name of a core and a colon
+fn resolve_signal(name: &str, cores: &[Core]) -> Result<(usize, usize), String> {
+
+    if let Some(separator) = name.find(':') {
+        let (core_name, signal_name) = (&name[..separator], &name[separator + 1..]);
+
+        return match cores.iter().position(|core| core.name() == core_name) {
+            Some(core) => cores[core].find_signal(signal_name).map(|signal| (core, signal)),
+            None => Err(format!("no core named {}", core_name)),
+        };
+    }
+
+    let mut found = None;
+    let mut last_error = format!("no signal named {}", name);
+
+    for (index, core) in cores.iter().enumerate() {
+        match core.find_signal(name) {
+
+            Ok(signal) => {
+                if found.is_some() {
+                    return Err(format!("signal {} exists in multiple cores, prefix it with the core name", name));
+                }
+                found = Some((index, signal));
+            },
+
+            Err(message) => last_error = message,
+        }
+    }
+
+    return found.ok_or(last_error);
+}
+
+// all signals of an expression have to belong to the same core
+pub(super) fn compile_expression(source: &str, cores: &[Core]) -> Result<(usize, Expression), String> {
+
+    if cores.is_empty() {
+        return Err(String::from("expressions need at least one core"));
+    }
+
+    let mut target_core = None;
+    let mut resolve = |name: &str| {
+        let (core, signal) = resolve_signal(name, cores)?;
+
+        match target_core {
+            Some(target_core) if target_core != core => return Err(String::from("expression refers to signals of multiple cores")),
+            _other => target_core = Some(core),
+        }
+
+        return Ok(signal);
+    };
+
+    let expression = Expression::parse(source, &mut resolve)?;
+    return Ok((target_core.unwrap_or(0), expression));
+}
+
+fn value_text(state: &ValueState) -> String {
+    match state {
+        ValueState::Stable(value) => return value.to_string(),
+        ValueState::Metastable => return String::from("metastable"),
+        ValueState::Floating => return String::from("floating"),
+    }
+}
@@ -0,0 +1,108 @@
+// cycles per second that can be selected while the clock is running
+const SPEEDS: [f32; 10] = [1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 1000.0, 10000.0];
+
+// running as fast as possible starts with this many half cycles per update and adapts to the time a frame takes
+const UNLIMITED_STEPS: usize = 16;
+
+// frames taking longer than this get fewer half cycles, shorter ones get more
+const UNLIMITED_FRAME_TIME: f32 = 1.0 / 30.0;
+
+// a single slow frame should never stall the interface, so anything beyond this is dropped
+const MAXIMUM_STEPS: usize = 20000;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Speed {
+    CyclesPerSecond(f32),
+    Unlimited,
+}
+
+pub struct Clock {
+    running: bool,
+    speed: Speed,
+    pending: f32,
+    unlimited_steps: usize,
+}
+
+impl Clock {
+
+    pub fn new() -> Self {
+        return Self {
+            running: false,
+            speed: Speed::CyclesPerSecond(SPEEDS[3]),
+            pending: 0.0,
+            unlimited_steps: UNLIMITED_STEPS,
+        };
+    }
+
+    pub fn is_running(&self) -> bool {
+        return self.running;
+    }
+
+    pub fn set_running(&mut self, running: bool) {
+        self.running = running;
+        self.pending = 0.0;
+    }
+
+    pub fn speed(&self) -> Speed {
+        return self.speed;
+    }
+
+    pub fn set_speed(&mut self, speed: Speed) {
+        self.speed = speed;
+        self.pending = 0.0;
+        self.unlimited_steps = UNLIMITED_STEPS;
+    }
+
+    pub fn faster(&mut self) {
+        if let Speed::CyclesPerSecond(current) = self.speed {
+            match SPEEDS.iter().find(|speed| **speed > current) {
+                Some(speed) => self.set_speed(Speed::CyclesPerSecond(*speed)),
+                None => self.set_speed(Speed::Unlimited),
+            }
+        }
+    }
+
+    pub fn slower(&mut self) {
+        match self.speed {
+            Speed::Unlimited => self.set_speed(Speed::CyclesPerSecond(SPEEDS[SPEEDS.len() - 1])),
+            Speed::CyclesPerSecond(current) => {
+                if let Some(speed) = SPEEDS.iter().rev().find(|speed| **speed < current) {
+                    self.set_speed(Speed::CyclesPerSecond(*speed));
+                }
+            },
+        }
+    }
+
+    // number of half cycles that should be simulated after elapsed seconds
+    pub fn steps(&mut self, elapsed: f32) -> usize {
+
+        if !self.running {
+            return 0;
+        }
+
+        match self.speed {
+
+            // the elapsed time includes simulating the previous update, so it tells how much a frame can take
+            Speed::Unlimited => {
+                match elapsed > UNLIMITED_FRAME_TIME {
+                    true => self.unlimited_steps = (self.unlimited_steps / 2).max(1),
+                    false => self.unlimited_steps = (self.unlimited_steps + self.unlimited_steps / 4 + 1).min(MAXIMUM_STEPS),
+                }
+                return self.unlimited_steps;
+            },
+
+            Speed::CyclesPerSecond(speed) => {
+                self.pending += elapsed * speed * 2.0;
+                let steps = self.pending.floor() as usize;
+
+                if steps >= MAXIMUM_STEPS {
+                    self.pending = 0.0;
+                    return MAXIMUM_STEPS;
+                }
+
+                self.pending -= steps as f32;
+                return steps;
+            },
+        }
+    }
+}
@@ -0,0 +1,7 @@
+// colors shared by the top bar and the device overview
+use crate::types::Color;
+
+pub const TEXT_COLOR: Color = Color::from(160, 160, 160);
+pub const RUNNING_COLOR: Color = Color::from(100, 150, 100);
+pub const PAUSED_COLOR: Color = Color::from(100, 100, 150);
+pub const BREAKPOINT_COLOR: Color = Color::from(250, 100, 100);
@@ -0,0 +1,266 @@
+use super::{ Core, LogicState, LabelSource };
+use super::logic::{ Register, Gate };
+use std::fmt;
+
+// transitions only count between high and low, so a floating or metastable state in between is skipped over
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Toggles {
+    pub rises: usize,
+    pub falls: usize,
+    pub floating: usize,
+    pub metastable: usize,
+    last: Option<bool>,
+}
+
+impl Toggles {
+
+    // returns true if the state toggled
+    fn sample(&mut self, state: LogicState) -> bool {
+
+        let value = match state {
+            LogicState::High => true,
+            LogicState::Low => false,
+            LogicState::Floating => {
+                self.floating += 1;
+                return false;
+            },
+            LogicState::Metastable => {
+                self.metastable += 1;
+                return false;
+            },
+        };
+
+        let toggled = match (self.last, value) {
+            (Some(false), true) => {
+                self.rises += 1;
+                true
+            },
+            (Some(true), false) => {
+                self.falls += 1;
+                true
+            },
+            _ => false,
+        };
+
+        self.last = Some(value);
+        return toggled;
+    }
+
+    pub fn toggles(&self) -> usize {
+        return self.rises + self.falls;
+    }
+
+    pub fn toggled(&self) -> bool {
+        return self.rises > 0 && self.falls > 0;
+    }
+
+    pub fn never_toggled(&self) -> bool {
+        return self.rises == 0 && self.falls == 0;
+    }
+}
+
+// statistics of every register and of both inputs and the output of every gate, sampled after each tick
+#[derive(Clone, Debug)]
+pub struct Activity {
+    pub ticks: usize,
+    pub registers: Vec<Toggles>,
+    pub gates: Vec<[Toggles; 3]>,
+    // the edge of every tick and how many and, or and xor gate outputs and registers toggled in it
+    pub history: Vec<(bool, [usize; 4])>,
+}
+
+// the counters of an activity at one point, the history past its ticks is kept until the next sample replaces it
+#[derive(Clone, Debug)]
+pub struct ActivitySnapshot {
+    ticks: usize,
+    registers: Vec<Toggles>,
+    gates: Vec<[Toggles; 3]>,
+}
+
+impl Activity {
+
+    pub fn new(registers: usize, gates: usize) -> Self {
+        return Self {
+            ticks: 0,
+            registers: vec![Toggles::default(); registers],
+            gates: vec![[Toggles::default(); 3]; gates],
+            history: Vec::new(),
+        };
+    }
+
+    pub fn snapshot(&self) -> ActivitySnapshot {
+        return ActivitySnapshot {
+            ticks: self.ticks,
+            registers: self.registers.clone(),
+            gates: self.gates.clone(),
+        };
+    }
+
+    // no snapshot means the state was recorded before the statistics were collected
+    pub fn restore(&mut self, snapshot: Option<&ActivitySnapshot>) {
+        match snapshot {
+            Some(snapshot) => {
+                self.ticks = snapshot.ticks;
+                self.registers = snapshot.registers.clone();
+                self.gates = snapshot.gates.clone();
+            },
+            None => *self = Self::new(self.registers.len(), self.gates.len()),
+        }
+    }
+
+    // the ticks that were sampled up to the restored state
+    pub fn recorded(&self) -> &[(bool, [usize; 4])] {
+        return &self.history[..self.ticks];
+    }
+
+    pub fn sample(&mut self, rising: bool, registers: &[Register], gates: &[Gate]) {
+
+        // sampling after going back in time replaces the future
+        self.history.truncate(self.ticks);
+        let mut counts = [0; 4];
+
+        for (toggles, register) in self.registers.iter_mut().zip(registers.iter()) {
+            if toggles.sample(register.state) {
+                counts[3] += 1;
+            }
+        }
+
+        for (toggles, gate) in self.gates.iter_mut().zip(gates.iter()) {
+            toggles[0].sample(gate.left_input.state);
+            toggles[1].sample(gate.right_input.state);
+            if toggles[2].sample(gate.state) {
+                counts[gate.operator as usize] += 1;
+            }
+        }
+
+        self.history.push((rising, counts));
+        self.ticks += 1;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ActivityReport {
+    pub ticks: usize,
+    pub entries: Vec<(String, Toggles)>,
+}
+
+impl ActivityReport {
+
+    pub fn never_toggled(&self) -> Vec<&str> {
+        return self.entries.iter().filter(|(_name, toggles)| toggles.never_toggled()).map(|(name, _toggles)| name.as_str()).collect();
+    }
+
+    // the percentage of entries that rose and fell at least once
+    pub fn coverage(&self) -> f64 {
+        match self.entries.len() {
+            0 => return 100.0,
+            count => return self.entries.iter().filter(|(_name, toggles)| toggles.toggled()).count() as f64 * 100.0 / count as f64,
+        }
+    }
+}
+
+impl fmt::Display for ActivityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        let toggled = self.entries.iter().filter(|(_name, toggles)| toggles.toggled()).count();
+        write!(f, "{} of {} toggled both ways in {} ticks, {:.1}% toggle coverage", toggled, self.entries.len(), self.ticks, self.coverage())?;
+
+        let width = self.entries.iter().map(|(name, _toggles)| name.len()).max().unwrap_or(0);
+        write!(f, "\n  {:width$}  {:>8}  {:>8}  {:>8}  {:>10}", "", "rises", "falls", "floating", "metastable", width = width)?;
+        for (name, toggles) in &self.entries {
+            write!(f, "\n  {:width$}  {:>8}  {:>8}  {:>8}  {:>10}", name, toggles.rises, toggles.falls, toggles.floating, toggles.metastable, width = width)?;
+        }
+
+        for name in self.never_toggled() {
+            write!(f, "\n  never toggled: {}", name)?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl Core {
+
+    // statistics are only collected from this point on
+    pub fn track_activity(&mut self) {
+        self.activity = Some(Activity::new(self.registers.len(), self.gates.len()));
+    }
+
+    pub fn activity(&self) -> Option<ActivityReport> {
+
+        let activity = self.activity.as_ref()?;
+        let mut entries = Vec::new();
+
+        for (index, toggles) in activity.registers.iter().enumerate() {
+            entries.push((self.describe_source(LabelSource::Register(index)), *toggles));
+        }
+
+        for (index, toggles) in activity.gates.iter().enumerate() {
+            entries.push((self.describe_source(LabelSource::Gate(index, false)), toggles[0]));
+            entries.push((self.describe_source(LabelSource::Gate(index, true)), toggles[1]));
+            entries.push((format!("output of gate {}", index), toggles[2]));
+        }
+
+        return Some(ActivityReport {
+            ticks: activity.ticks,
+            entries: entries,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::{ Core, Netlist, LogicState };
+    use crate::types::FloatVector;
+
+    const TOGGLE: &str = "
+        module toggle(input clk, input en, output q);
+            reg master = 1'b0;
+            reg q = 1'b0;
+            always @(negedge clk) master <= q ^ en;
+            always @(posedge clk) q <= master;
+        endmodule
+    ";
+
+    fn counts(core: &Core) -> Vec<(usize, usize)> {
+        return core.activity().unwrap().entries.iter().map(|(_name, toggles)| (toggles.rises, toggles.falls)).collect();
+    }
+
+    #[test]
+    fn restoring_rewinds_the_statistics() {
+
+        let netlist = Netlist::from_verilog(TOGGLE).unwrap();
+        let mut core = Core::from_netlist(String::from("toggle"), &netlist, FloatVector::new(), true).unwrap();
+        let initial = core.state();
+        core.track_activity();
+        core.set_port_state(core.find_port("en").unwrap(), LogicState::High);
+
+        let run = |core: &mut Core, cycles: usize| for _cycle in 0..cycles {
+            core.tick(true);
+            core.tick(false);
+        };
+
+        run(&mut core, 2);
+        let state = core.state();
+        let samples = core.samples();
+        let expected = counts(&core);
+
+        run(&mut core, 3);
+        assert_eq!(core.activity().unwrap().ticks, 10);
+
+        core.restore_state(&state, samples);
+        assert_eq!(core.activity().unwrap().ticks, 4);
+        assert_eq!(counts(&core), expected);
+
+        // the future is replaced once the simulation continues
+        run(&mut core, 1);
+        assert_eq!(core.activity().unwrap().ticks, 6);
+        assert_eq!(core.activity.as_ref().unwrap().history.len(), 6);
+
+        // a state from before the statistics were collected has none
+        core.restore_state(&initial, 0);
+        assert_eq!(core.activity().unwrap().ticks, 0);
+        assert!(counts(&core).iter().all(|count| *count == (0, 0)));
+    }
+}
@@ -0,0 +1,532 @@
+use super::{ Core, LogicState, Operator };
+use super::logic::{ Input, Output, Gate, Register, Constant, Port };
+use super::inspector::{ InspectorItem, Group, Label, Formatting, LogicTracker, ValueTracker, LabelSource };
+use super::netlist::LoweredNetlist;
+use crate::types::FloatVector;
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GateHandle(usize);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RegisterHandle(usize);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ConstantHandle(usize);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PortHandle(usize);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GroupHandle(usize);
+
+impl GateHandle {
+
+    pub fn index(&self) -> usize {
+        return self.0;
+    }
+
+    pub fn left(&self) -> Pin {
+        return Pin::Gate(*self, false);
+    }
+
+    pub fn right(&self) -> Pin {
+        return Pin::Gate(*self, true);
+    }
+}
+
+impl RegisterHandle {
+
+    pub fn index(&self) -> usize {
+        return self.0;
+    }
+}
+
+impl ConstantHandle {
+
+    pub fn index(&self) -> usize {
+        return self.0;
+    }
+}
+
+// the index is the one expected by the port functions of the core
+impl PortHandle {
+
+    pub fn index(&self) -> usize {
+        return self.0;
+    }
+}
+
+// anything that drives a state
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Source {
+    Gate(GateHandle),
+    Register(RegisterHandle),
+    Constant(ConstantHandle),
+    Port(PortHandle),
+}
+
+impl From<GateHandle> for Source {
+    fn from(gate: GateHandle) -> Self {
+        return Source::Gate(gate);
+    }
+}
+
+impl From<RegisterHandle> for Source {
+    fn from(register: RegisterHandle) -> Self {
+        return Source::Register(register);
+    }
+}
+
+impl From<ConstantHandle> for Source {
+    fn from(constant: ConstantHandle) -> Self {
+        return Source::Constant(constant);
+    }
+}
+
+impl From<PortHandle> for Source {
+    fn from(port: PortHandle) -> Self {
+        return Source::Port(port);
+    }
+}
+
+// anything a state arrives at, which is also where the inspector observes it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Pin {
+    Gate(GateHandle, bool),
+    Register(RegisterHandle),
+    Port(PortHandle),
+}
+
+impl From<RegisterHandle> for Pin {
+    fn from(register: RegisterHandle) -> Self {
+        return Pin::Register(register);
+    }
+}
+
+impl From<PortHandle> for Pin {
+    fn from(port: PortHandle) -> Self {
+        return Pin::Port(port);
+    }
+}
+
+impl Pin {
+
+    fn label_source(&self) -> LabelSource {
+        match self {
+            Pin::Gate(gate, right) => return LabelSource::Gate(gate.0, *right),
+            Pin::Register(register) => return LabelSource::Register(register.0),
+            Pin::Port(port) => return LabelSource::Port(port.0),
+        }
+    }
+
+    fn output(&self) -> Output {
+        match self {
+            Pin::Gate(gate, right) => return Output::Gate(gate.0, *right),
+            Pin::Register(register) => return Output::Register(register.0),
+            Pin::Port(port) => return Output::Port(port.0),
+        }
+    }
+}
+
+impl fmt::Display for Pin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.label_source().describe());
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Gate(gate) => return write!(f, "gate {}", gate.0),
+            Source::Register(register) => return write!(f, "register {}", register.0),
+            Source::Constant(constant) => return write!(f, "constant {}", constant.0),
+            Source::Port(port) => return write!(f, "port {}", port.0),
+        }
+    }
+}
+
+enum Child {
+    Group(usize),
+    Label(String, Pin),
+}
+
+struct BuilderGroup {
+    name: String,
+    // the bits of a bus starting with the most significant one
+    bus: Option<(Vec<Pin>, Formatting)>,
+}
+
+// builds a core from handles instead of indices, nothing is checked until the core is built
+pub struct CoreBuilder {
+    name: String,
+    operators: Vec<Operator>,
+    registers: Vec<(bool, LogicState)>,
+    constants: Vec<LogicState>,
+    ports: Vec<(String, bool)>,
+    connections: Vec<(Source, Pin)>,
+    // the first group is the root group, named after the core
+    groups: Vec<BuilderGroup>,
+    // groups and labels with the group they belong to, in the order they were added
+    children: Vec<(usize, Child)>,
+}
+
+impl CoreBuilder {
+
+    pub fn new(name: &str) -> Self {
+        return Self {
+            name: String::from(name),
+            operators: Vec::new(),
+            registers: Vec::new(),
+            constants: Vec::new(),
+            ports: Vec::new(),
+            connections: Vec::new(),
+            groups: vec![BuilderGroup { name: String::from(name), bus: None }],
+            children: Vec::new(),
+        };
+    }
+
+    pub fn gate(&mut self, operator: Operator) -> GateHandle {
+        self.operators.push(operator);
+        return GateHandle(self.operators.len() - 1);
+    }
+
+    pub fn register(&mut self, rising: bool, initial: LogicState) -> RegisterHandle {
+        self.registers.push((rising, initial));
+        return RegisterHandle(self.registers.len() - 1);
+    }
+
+    pub fn constant(&mut self, state: LogicState) -> ConstantHandle {
+        self.constants.push(state);
+        return ConstantHandle(self.constants.len() - 1);
+    }
+
+    // input ports float until they are driven
+    pub fn input(&mut self, name: &str) -> PortHandle {
+        self.ports.push((String::from(name), true));
+        return PortHandle(self.ports.len() - 1);
+    }
+
+    pub fn output(&mut self, name: &str) -> PortHandle {
+        self.ports.push((String::from(name), false));
+        return PortHandle(self.ports.len() - 1);
+    }
+
+    // a source can drive any number of pins, but every pin only has one source
+    pub fn connect<S: Into<Source>, P: Into<Pin>>(&mut self, source: S, pin: P) {
+        self.connections.push((source.into(), pin.into()));
+    }
+
+    // groups and labels without a parent go into the root group
+    pub fn group(&mut self, parent: Option<GroupHandle>, name: &str) -> GroupHandle {
+        return self.add_group(parent, name, None);
+    }
+
+    pub fn bus(&mut self, parent: Option<GroupHandle>, name: &str, pins: Vec<Pin>, formatting: Formatting) -> GroupHandle {
+        return self.add_group(parent, name, Some((pins, formatting)));
+    }
+
+    fn add_group(&mut self, parent: Option<GroupHandle>, name: &str, bus: Option<(Vec<Pin>, Formatting)>) -> GroupHandle {
+        let index = self.groups.len();
+        self.groups.push(BuilderGroup { name: String::from(name), bus: bus });
+        self.children.push((parent.map_or(0, |parent| parent.0), Child::Group(index)));
+        return GroupHandle(index);
+    }
+
+    pub fn label<P: Into<Pin>>(&mut self, parent: Option<GroupHandle>, name: &str, pin: P) {
+        self.children.push((parent.map_or(0, |parent| parent.0), Child::Label(String::from(name), pin.into())));
+    }
+
+    // ports are named by their name rather than their index
+    fn pin_name(&self, pin: Pin) -> String {
+        match pin {
+            Pin::Port(port) if port.0 < self.ports.len() => return format!("port {}", self.ports[port.0].0),
+            _other => return pin.to_string(),
+        }
+    }
+
+    fn source_name(&self, source: Source) -> String {
+        match source {
+            Source::Port(port) if port.0 < self.ports.len() => return format!("port {}", self.ports[port.0].0),
+            _other => return source.to_string(),
+        }
+    }
+
+    fn validate_pin(&self, pin: Pin) -> Result<(), String> {
+        let exists = match pin {
+            Pin::Gate(gate, _right) => gate.0 < self.operators.len(),
+            Pin::Register(register) => register.0 < self.registers.len(),
+            Pin::Port(port) => port.0 < self.ports.len(),
+        };
+
+        match exists {
+            true => return Ok(()),
+            false => return Err(format!("{} does not exist in {}", pin, self.name)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+
+        for (index, (name, _input)) in self.ports.iter().enumerate() {
+            if self.ports[..index].iter().any(|(other, _input)| other == name) {
+                return Err(format!("{} has more than one port named {}", self.name, name));
+            }
+        }
+
+        let mut drivers: Vec<(Pin, Source)> = Vec::new();
+        for (source, pin) in &self.connections {
+
+            let exists = match source {
+                Source::Gate(gate) => gate.0 < self.operators.len(),
+                Source::Register(register) => register.0 < self.registers.len(),
+                Source::Constant(constant) => constant.0 < self.constants.len(),
+                Source::Port(port) => port.0 < self.ports.len(),
+            };
+
+            if !exists {
+                return Err(format!("{} does not exist in {}", source, self.name));
+            }
+
+            self.validate_pin(*pin)?;
+
+            if let Source::Port(port) = source {
+                if !self.ports[port.0].1 {
+                    return Err(format!("port {} is an output and can not drive {}", self.ports[port.0].0, self.pin_name(*pin)));
+                }
+            }
+
+            if let Pin::Port(port) = pin {
+                if self.ports[port.0].1 {
+                    return Err(format!("port {} is an input and can not be driven by {}", self.ports[port.0].0, self.source_name(*source)));
+                }
+            }
+
+            if let Some((_pin, other)) = drivers.iter().find(|(other, _source)| other == pin) {
+                return Err(format!("{} is driven by both {} and {}", self.pin_name(*pin), self.source_name(*other), self.source_name(*source)));
+            }
+
+            drivers.push((*pin, *source));
+        }
+
+        // a gate only fires once both of its inputs arrived, so a missing input is always a mistake
+        for index in 0..self.operators.len() {
+            for pin in &[GateHandle(index).left(), GateHandle(index).right()] {
+                if !drivers.iter().any(|(other, _source)| other == pin) {
+                    return Err(format!("{} is not connected", pin));
+                }
+            }
+        }
+
+        for group in &self.groups {
+            for pin in group.bus.iter().flat_map(|(pins, _formatting)| pins.iter()) {
+                self.validate_pin(*pin)?;
+            }
+        }
+
+        // a group can only be added to one that existed before it, which keeps them a tree
+        for (parent, child) in &self.children {
+            match child {
+                Child::Group(index) if parent >= index => return Err(format!("group {} can not be added to group {}, which does not exist yet", self.groups[*index].name, parent)),
+                Child::Label(name, _pin) if *parent >= self.groups.len() => return Err(format!("label {} can not be added to group {}, which does not exist", name, parent)),
+                Child::Label(_name, pin) => self.validate_pin(*pin)?,
+                _valid => (),
+            }
+        }
+
+        return Ok(());
+    }
+
+    // fan-out is expressed as a chain of splitters, in the order the pins were connected
+    fn output_tree(&self, source: Source) -> Output {
+        let pins: Vec<Pin> = self.connections.iter().filter(|(other, _pin)| *other == source).map(|(_source, pin)| *pin).collect();
+        return pins.iter().rev().fold(None, |rest: Option<Output>, pin| match rest {
+            None => Some(pin.output()),
+            Some(rest) => Some(Output::Splitter(Box::new(pin.output()), Box::new(rest))),
+        }).unwrap_or(Output::Disconnected);
+    }
+
+    // every pin gets a single tracker, no matter how many labels or buses show it
+    fn tracker(pin: Pin, logic_trackers: &mut Vec<LogicTracker>) -> usize {
+        let source = pin.label_source();
+        match logic_trackers.iter().position(|tracker| tracker.source == Some(source)) {
+            Some(index) => return index,
+            None => {
+                logic_trackers.push(LogicTracker::new(source));
+                return logic_trackers.len() - 1;
+            },
+        }
+    }
+
+    fn build_item(&self, index: usize, logic_trackers: &mut Vec<LogicTracker>, value_trackers: &mut Vec<ValueTracker>) -> InspectorItem {
+
+        let group = &self.groups[index];
+        let mut items = Vec::new();
+
+        // the bits of a bus are listed before anything added to it
+        let tracker = group.bus.as_ref().map(|(pins, formatting)| {
+            let trackers: Vec<usize> = pins.iter().map(|pin| Self::tracker(*pin, logic_trackers)).collect();
+            for (offset, tracker) in trackers.iter().enumerate() {
+                items.push(InspectorItem::Label(Label::new(&format!("{}[{}]", group.name, trackers.len() - offset - 1), *tracker)));
+            }
+
+            value_trackers.push(ValueTracker::new(trackers));
+            return (value_trackers.len() - 1, *formatting);
+        });
+
+        for (_parent, child) in self.children.iter().filter(|(parent, _child)| *parent == index) {
+            match child {
+                Child::Group(child) => items.push(self.build_item(*child, logic_trackers, value_trackers)),
+                Child::Label(name, pin) => items.push(InspectorItem::Label(Label::new(name, Self::tracker(*pin, logic_trackers)))),
+            }
+        }
+
+        return InspectorItem::Group(Group::new(&group.name, tracker, items));
+    }
+
+    pub fn build(self, interface_size: FloatVector, debugging: bool) -> Result<Core, String> {
+
+        self.validate()?;
+
+        let gates = (0..self.operators.len()).map(|index| Gate::new(self.operators[index], self.output_tree(Source::Gate(GateHandle(index))))).collect();
+        let registers = (0..self.registers.len()).map(|index| {
+            let (rising, initial) = self.registers[index];
+            return Register::new(Input::new(initial), self.output_tree(Source::Register(RegisterHandle(index))), rising);
+        }).collect();
+        let constants = (0..self.constants.len()).map(|index| Constant::new(self.constants[index], self.output_tree(Source::Constant(ConstantHandle(index))))).collect();
+        let ports = (0..self.ports.len()).map(|index| match &self.ports[index] {
+            (name, true) => Port::input(name, LogicState::Floating, self.output_tree(Source::Port(PortHandle(index)))),
+            (name, false) => Port::output(name),
+        }).collect();
+
+        let mut logic_trackers = Vec::new();
+        let mut value_trackers = Vec::new();
+        let root_item = self.build_item(0, &mut logic_trackers, &mut value_trackers);
+
+        let lowered = LoweredNetlist {
+            gates: gates,
+            registers: registers,
+            constants: constants,
+            ports: ports,
+            root_item: root_item,
+            logic_trackers: logic_trackers,
+            value_trackers: value_trackers,
+        };
+
+        return Ok(Core::from_lowered(self.name, lowered, interface_size, debugging));
+    }
+}
+
+impl Core {
+
+    // a small circuit used when no netlist is loaded: two three bit registers, the first anded and xored with
+    // the second into a buffer that feeds back into the first, while the second is kept high by or gates
+    pub fn sample(name: String, interface_size: FloatVector, debugging: bool) -> Result<Self, String> {
+
+        let mut builder = CoreBuilder::new(&name);
+        let first: Vec<RegisterHandle> = [LogicState::High, LogicState::Low, LogicState::High].iter().map(|initial| builder.register(true, *initial)).collect();
+        let second: Vec<RegisterHandle> = (0..3).map(|_index| builder.register(true, LogicState::High)).collect();
+        let buffer: Vec<RegisterHandle> = (0..3).map(|_index| builder.register(false, LogicState::Floating)).collect();
+        let combined: Vec<GateHandle> = [Operator::And, Operator::Xor, Operator::Xor].iter().map(|operator| builder.gate(*operator)).collect();
+        let inverters: Vec<GateHandle> = (0..3).map(|_index| builder.gate(Operator::Or)).collect();
+        let _carry_in = builder.input("carry_in");
+        let carry_out = builder.output("carry_out");
+
+        for bit in 0..3 {
+            builder.connect(first[bit], combined[bit].left());
+            builder.connect(second[bit], combined[bit].right());
+            builder.connect(combined[bit], buffer[bit]);
+            builder.connect(buffer[bit], first[bit]);
+            builder.connect(buffer[bit], inverters[bit].left());
+            let high = builder.constant(LogicState::High);
+            builder.connect(high, inverters[bit].right());
+            builder.connect(inverters[bit], second[bit]);
+        }
+        builder.connect(combined[0], carry_out);
+
+        // buses list their bits starting with the most significant one
+        builder.bus(None, "input 0", combined.iter().rev().map(|gate| gate.left()).collect(), Formatting::Binary);
+        builder.bus(None, "input 1", combined.iter().rev().map(|gate| gate.right()).collect(), Formatting::Binary);
+
+        let groups = [("gates", &buffer, ["AND gate", "XOR gate", "XOR gate"]), ("buffer", &first, ["register 0", "register 1", "register 2"]), ("inverter", &second, ["output 0", "output 1", "output 2"])];
+        for (group, registers, names) in groups.iter() {
+            let group = builder.group(None, group);
+            for (register, name) in registers.iter().zip(names.iter()) {
+                builder.label(Some(group), name, *register);
+            }
+        }
+
+        return builder.build(interface_size, debugging);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ CoreBuilder, GateHandle };
+    use super::super::{ LogicState, Operator };
+    use crate::types::FloatVector;
+
+    // two inputs anded into an output
+    fn and_gate() -> (CoreBuilder, GateHandle) {
+        let mut builder = CoreBuilder::new("and");
+        let (left, right) = (builder.input("left"), builder.input("right"));
+        let gate = builder.gate(Operator::And);
+        let output = builder.output("out");
+        builder.connect(left, gate.left());
+        builder.connect(right, gate.right());
+        builder.connect(gate, output);
+        return (builder, gate);
+    }
+
+    #[test]
+    fn built_cores_simulate() {
+
+        let (builder, _gate) = and_gate();
+        let mut core = builder.build(FloatVector::new(), true).unwrap();
+        let (left, right, output) = (core.find_port("left").unwrap(), core.find_port("right").unwrap(), core.find_port("out").unwrap());
+
+        for (first, second) in [(false, false), (true, false), (true, true), (false, true)].iter() {
+            core.set_port_state(left, LogicState::from_boolean(*first));
+            core.set_port_state(right, LogicState::from_boolean(*second));
+            core.tick(true);
+            assert_eq!(core.ports[output].state, LogicState::from_boolean(*first && *second));
+            core.tick(false);
+        }
+    }
+
+    #[test]
+    fn mistakes_are_reported() {
+
+        let (mut builder, gate) = and_gate();
+        let other = builder.gate(Operator::Or);
+        builder.connect(gate, other.left());
+        builder.connect(gate, other.right());
+        let output = builder.output("other");
+        builder.connect(other, output);
+        builder.connect(gate, output);
+        assert_eq!(builder.build(FloatVector::new(), false).err().unwrap(), "port other is driven by both gate 1 and gate 0");
+
+        let (mut builder, _gate) = and_gate();
+        let unconnected = builder.gate(Operator::Xor);
+        builder.connect(unconnected, unconnected.left());
+        assert_eq!(builder.build(FloatVector::new(), false).err().unwrap(), format!("{} is not connected", unconnected.right()));
+
+        let (mut builder, gate) = and_gate();
+        let output = builder.output("another");
+        let source = builder.output("source");
+        builder.connect(source, output);
+        builder.connect(gate, source);
+        assert_eq!(builder.build(FloatVector::new(), false).err().unwrap(), "port source is an output and can not drive port another");
+
+        // handles of another builder refer to groups that do not exist here
+        let mut other = CoreBuilder::new("other");
+        let (first, second) = (other.group(None, "first"), other.group(None, "second"));
+        let (mut builder, _gate) = and_gate();
+        builder.group(Some(first), "late");
+        assert_eq!(builder.build(FloatVector::new(), false).err().unwrap(), "group late can not be added to group 1, which does not exist yet");
+
+        let (mut builder, gate) = and_gate();
+        builder.label(Some(second), "missing", gate.left());
+        assert_eq!(builder.build(FloatVector::new(), false).err().unwrap(), "label missing can not be added to group 2, which does not exist");
+    }
+}
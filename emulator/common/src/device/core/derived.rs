@@ -0,0 +1,119 @@
+use super::Core;
+use super::LogicState;
+use super::super::expression::{ Expression, Value };
+
+// a signal computed from an expression over the other signals of a core, numbers are high when they are not zero
+pub struct DerivedSignal {
+    expression: Expression,
+    tracker: usize,
+    // only the signals the expression refers to, as they were in the previous half cycle
+    previous_values: Vec<(usize, Value)>,
+}
+
+impl DerivedSignal {
+
+    fn evaluate(&self, core: &Core) -> LogicState {
+        let current = |index: usize| core.signal_value(index);
+        let previous = |index: usize| match self.previous_values.iter().find(|(signal, _value)| *signal == index) {
+            Some((_signal, value)) => *value,
+            None => core.signal_value(index),
+        };
+        return self.expression.evaluate(&current, &previous).to_logic();
+    }
+
+    fn synchronize(&mut self, core: &Core) {
+        let mut signals = self.expression.signals();
+        signals.sort_unstable();
+        signals.dedup();
+        self.previous_values = signals.into_iter().map(|signal| (signal, core.signal_value(signal))).collect();
+    }
+}
+
+impl Core {
+
+    // the expression has to refer to signals of this core, derived signals are only recorded while debugging
+    pub fn add_derived_signal(&mut self, name: &str, expression: Expression) -> Result<(), String> {
+
+        let tracker = match &mut self.inspector {
+            Some(inspector) => inspector.add_derived(name)?,
+            None => return Err(String::from("derived signals need the inspector")),
+        };
+
+        let mut signal = DerivedSignal {
+            expression: expression,
+            tracker: tracker,
+            previous_values: Vec::new(),
+        };
+
+        signal.synchronize(self);
+        self.derived.push(signal);
+        return Ok(());
+    }
+
+    pub(super) fn sample_derived(&mut self) {
+        let states: Vec<(usize, LogicState)> = self.derived.iter().map(|signal| (signal.tracker, signal.evaluate(self))).collect();
+
+        if let Some(inspector) = &mut self.inspector {
+            states.into_iter().for_each(|(tracker, state)| inspector.push_derived(tracker, state));
+        }
+
+        self.synchronize_derived();
+    }
+
+    // after a restore the previous values no longer belong to the previous half cycle
+    pub(super) fn synchronize_derived(&mut self) {
+        let mut derived = std::mem::take(&mut self.derived);
+        derived.iter_mut().for_each(|signal| signal.synchronize(self));
+        self.derived = derived;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::{ Core, Netlist, LogicState };
+    use super::super::super::expression::Expression;
+    use crate::types::FloatVector;
+
+    // a master slave flip flop, so the input is sampled on the falling edge
+    const TOGGLE: &str = "
+        module toggle(input clk, input en, output q);
+            reg master = 1'b0;
+            reg q = 1'b0;
+            always @(negedge clk) master <= q ^ en;
+            always @(posedge clk) q <= master;
+        endmodule
+    ";
+
+    fn add(core: &mut Core, name: &str, source: &str) -> Result<(), String> {
+        let mut resolve = |name: &str| core.find_signal(name);
+        let expression = Expression::parse(source, &mut resolve)?;
+        return core.add_derived_signal(name, expression);
+    }
+
+    #[test]
+    fn derived_signals_are_recorded() {
+
+        let netlist = Netlist::from_verilog(TOGGLE).unwrap();
+        let mut core = Core::from_netlist(String::from("toggle"), &netlist, FloatVector::new(), true).unwrap();
+
+        add(&mut core, "both", "q & en").unwrap();
+        add(&mut core, "rising", "rose(q)").unwrap();
+        add(&mut core, "differs", "q ^ en").unwrap();
+        assert_eq!(add(&mut core, "both", "q").unwrap_err(), "derived signal both is already defined");
+
+        for enable in [true, true, false, true].iter() {
+            core.set_port_state(core.find_port("en").unwrap(), LogicState::from_boolean(*enable));
+            core.tick(true);
+            core.tick(false);
+        }
+
+        let trace = core.trace().unwrap();
+        let line = |name: &str| trace.lines().find(|line| line.starts_with(name)).map(String::from);
+        assert_eq!(line("toggle/en:"), Some(String::from("toggle/en: 11110011")));
+        assert_eq!(line("toggle/q:"), Some(String::from("toggle/q: 00110000")));
+        assert_eq!(line("toggle/derived/both:"), Some(String::from("toggle/derived/both: 00110000")));
+        assert_eq!(line("toggle/derived/rising:"), Some(String::from("toggle/derived/rising: 00100000")));
+        assert_eq!(line("toggle/derived/differs:"), Some(String::from("toggle/derived/differs: 11000011")));
+    }
+}
@@ -0,0 +1,129 @@
+use super::{ Core, LogicState, LabelSource, Operator };
+use super::logic::Output;
+use super::optimize::output_sinks;
+
+fn fill_color(state: LogicState) -> &'static str {
+    match state {
+        LogicState::High => return "palegreen",
+        LogicState::Low => return "lightgray",
+        LogicState::Floating => return "lightblue",
+        LogicState::Metastable => return "salmon",
+    }
+}
+
+fn edge_color(state: LogicState) -> &'static str {
+    match state {
+        LogicState::High => return "forestgreen",
+        LogicState::Low => return "gray30",
+        LogicState::Floating => return "steelblue",
+        LogicState::Metastable => return "red",
+    }
+}
+
+fn state_name(state: LogicState) -> &'static str {
+    match state {
+        LogicState::High => return "1",
+        LogicState::Low => return "0",
+        LogicState::Floating => return "z",
+        LogicState::Metastable => return "x",
+    }
+}
+
+fn escape(text: &str) -> String {
+    return text.replace('\\', "\\\\").replace('"', "\\\"");
+}
+
+impl Core {
+
+    pub fn dot_port_node(prefix: &str, index: usize) -> String {
+        return format!("{}port_{}", prefix, index);
+    }
+
+    // for names written into quoted strings by anything that wraps cores in a larger graph
+    pub fn dot_escape(text: &str) -> String {
+        return escape(text);
+    }
+
+    // nodes are prefixed so the cores of a device can share one graph
+    pub fn write_dot(&self, prefix: &str, colored: bool) -> Vec<String> {
+
+        let mut lines = Vec::new();
+        let style = |state: LogicState| match colored {
+            true => format!(", style=filled, fillcolor={}", fill_color(state)),
+            false => String::new(),
+        };
+
+        for (index, gate) in self.gates.iter().enumerate() {
+            let operator = match gate.operator {
+                Operator::And => "AND",
+                Operator::Or => "OR",
+                Operator::Xor => "XOR",
+            };
+            lines.push(format!("{}gate_{} [shape=invtrapezium, label=\"{}\\n{}\"{}];", prefix, index, operator, index, style(gate.state)));
+        }
+
+        for (index, register) in self.registers.iter().enumerate() {
+            let edge = match register.rising {
+                true => "rising",
+                false => "falling",
+            };
+            lines.push(format!("{}register_{} [shape=box, label=\"register {}\\n{}\"{}];", prefix, index, index, edge, style(register.state)));
+        }
+
+        for (index, constant) in self.constants.iter().enumerate() {
+            lines.push(format!("{}constant_{} [shape=circle, label=\"{}\"{}];", prefix, index, state_name(constant.state), style(constant.state)));
+        }
+
+        for (index, port) in self.ports.iter().enumerate() {
+            let shape = match port.is_input() {
+                true => "invhouse",
+                false => "house",
+            };
+            lines.push(format!("{} [shape={}, label=\"{}\"{}];", Self::dot_port_node(prefix, index), shape, escape(&port.name), style(port.state)));
+        }
+
+        let mut drivers: Vec<(String, LogicState, &Output)> = Vec::new();
+        drivers.extend(self.gates.iter().enumerate().map(|(index, gate)| (format!("{}gate_{}", prefix, index), gate.state, &gate.output)));
+        drivers.extend(self.registers.iter().enumerate().map(|(index, register)| (format!("{}register_{}", prefix, index), register.state, &register.output)));
+        drivers.extend(self.constants.iter().enumerate().map(|(index, constant)| (format!("{}constant_{}", prefix, index), constant.state, &constant.output)));
+        drivers.extend(self.ports.iter().enumerate().filter_map(|(index, port)| port.output.as_ref().map(|output| (Self::dot_port_node(prefix, index), port.state, output))));
+
+        for (node, state, output) in drivers {
+
+            for sink in output_sinks(output) {
+                let (target, pin) = match sink {
+                    LabelSource::Register(index) => (format!("{}register_{}", prefix, index), None),
+                    LabelSource::Gate(index, false) => (format!("{}gate_{}", prefix, index), Some("L")),
+                    LabelSource::Gate(index, true) => (format!("{}gate_{}", prefix, index), Some("R")),
+                    LabelSource::Port(index) => (Self::dot_port_node(prefix, index), None),
+                };
+
+                // edges are labeled with the signal observed at their sink, if there is one
+                let mut attributes = Vec::new();
+                if let Some(signal) = self.signals.iter().find(|signal| signal.sources == [sink]) {
+                    attributes.push(format!("label=\"{}\"", escape(signal.name.rsplit('/').next().unwrap())));
+                }
+                if let Some(pin) = pin {
+                    attributes.push(format!("headlabel=\"{}\"", pin));
+                }
+                if colored {
+                    attributes.push(format!("color={}", edge_color(state)));
+                }
+
+                match attributes.is_empty() {
+                    true => lines.push(format!("{} -> {};", node, target)),
+                    false => lines.push(format!("{} -> {} [{}];", node, target, attributes.join(", "))),
+                }
+            }
+        }
+
+        return lines;
+    }
+
+    pub fn to_dot(&self, colored: bool) -> String {
+        let mut dot = format!("digraph \"{}\" {{\n    rankdir=LR;\n    label=\"{} cycle {}\";\n", escape(&self.name), escape(&self.name), self.cycle_count);
+        self.write_dot("", colored).iter().for_each(|line| dot.push_str(&format!("    {}\n", line)));
+        dot.push_str("}\n");
+        return dot;
+    }
+}
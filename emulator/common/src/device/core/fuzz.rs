@@ -0,0 +1,496 @@
+use super::{ Core, LogicState, Operator };
+use super::netlist::{ Netlist, NetlistGate, Net, PortDirection, Random };
+use crate::types::FloatVector;
+use std::fmt;
+
+const MAX_INPUTS: usize = 4;
+const MAX_CONSTANTS: usize = 2;
+const MAX_REGISTERS: usize = 6;
+const MAX_GATES: usize = 20;
+const MAX_OUTPUTS: usize = 4;
+const MAX_CYCLES: usize = 24;
+
+impl Random {
+
+    // mostly low and high, with the occasional floating or metastable state
+    fn state(&mut self) -> LogicState {
+        match self.below(10) {
+            0 => return LogicState::Floating,
+            1 => return LogicState::Metastable,
+            value => return LogicState::from_boolean(value % 2 == 1),
+        }
+    }
+
+    fn operator(&mut self) -> Operator {
+        match self.below(3) {
+            0 => return Operator::And,
+            1 => return Operator::Or,
+            _ => return Operator::Xor,
+        }
+    }
+}
+
+// a circuit together with the state of every port in every cycle, the states of output ports are ignored
+#[derive(Clone, Debug)]
+pub(super) struct Case {
+    pub(super) netlist: Netlist,
+    pub(super) stimulus: Vec<Vec<LogicState>>,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Element {
+    Gate(usize),
+    Register(usize),
+    Constant(usize),
+    Port(usize),
+}
+
+impl Case {
+
+    // gates only read nets that exist before them, so there are no combinational loops,
+    // but their indices are shuffled so that they are not stored in the order they fire in
+    pub(super) fn generate(random: &mut Random) -> Self {
+
+        let mut netlist = Netlist::new("fuzz");
+        let mut nets = Vec::new();
+
+        for index in 0..random.below(MAX_INPUTS) + 1 {
+            let net = netlist.add_net();
+            netlist.add_port(&format!("in{}", index), PortDirection::Input, net);
+            nets.push(net);
+        }
+
+        for _index in 0..random.below(MAX_CONSTANTS + 1) {
+            let state = random.state();
+            nets.push(netlist.add_constant(state));
+        }
+
+        // registers are connected once every net exists, so they can feed back
+        let register_outputs: Vec<Net> = (0..random.below(MAX_REGISTERS + 1)).map(|_index| netlist.add_net()).collect();
+        nets.extend_from_slice(&register_outputs);
+
+        let mut gates = Vec::new();
+        for _index in 0..random.below(MAX_GATES) + 1 {
+            let operator = random.operator();
+            let left = nets[random.below(nets.len())];
+            let right = nets[random.below(nets.len())];
+            let output = netlist.add_net();
+            gates.push(NetlistGate { operator: operator, left: left, right: right, output: output });
+            nets.push(output);
+        }
+
+        while !gates.is_empty() {
+            let gate = gates.swap_remove(random.below(gates.len()));
+            netlist.add_gate(gate.operator, gate.left, gate.right, gate.output);
+        }
+
+        for output in register_outputs {
+            let rising = random.below(2) == 0;
+            let initial = random.state();
+            netlist.add_register(rising, initial, nets[random.below(nets.len())], output);
+        }
+
+        for index in 0..random.below(MAX_OUTPUTS) + 1 {
+            netlist.add_port(&format!("out{}", index), PortDirection::Output, nets[random.below(nets.len())]);
+        }
+
+        let stimulus = (0..random.below(MAX_CYCLES) + 1).map(|_cycle| netlist.ports.iter().map(|port| match port.direction {
+            PortDirection::Input => random.state(),
+            PortDirection::Output => LogicState::Floating,
+        }).collect()).collect();
+
+        return Self {
+            netlist: netlist,
+            stimulus: stimulus,
+        };
+    }
+
+    fn elements(&self) -> Vec<Element> {
+        let mut elements: Vec<Element> = (0..self.netlist.gates.len()).map(Element::Gate).collect();
+        elements.extend((0..self.netlist.registers.len()).map(Element::Register));
+        elements.extend((0..self.netlist.constants.len()).map(Element::Constant));
+        elements.extend((0..self.netlist.ports.len()).map(Element::Port));
+        return elements;
+    }
+
+    // the nets an element drove are left floating
+    fn without(&self, element: Element) -> Self {
+        let mut case = self.clone();
+        match element {
+            Element::Gate(index) => { case.netlist.gates.remove(index); },
+            Element::Register(index) => { case.netlist.registers.remove(index); },
+            Element::Constant(index) => { case.netlist.constants.remove(index); },
+            Element::Port(index) => {
+                case.netlist.ports.remove(index);
+                case.stimulus.iter_mut().for_each(|states| { states.remove(index); });
+            },
+        }
+        return case;
+    }
+
+    // renumbers the nets so that only the ones still connected remain
+    fn compact(&mut self) {
+
+        let netlist = &mut self.netlist;
+        let mut numbers: Vec<Option<Net>> = vec![None; netlist.net_count];
+        let mut count = 0;
+        let mut renumber = |net: &mut Net| {
+            *net = *numbers[*net].get_or_insert_with(|| {
+                count += 1;
+                count - 1
+            });
+        };
+
+        netlist.ports.iter_mut().for_each(|port| renumber(&mut port.net));
+        netlist.constants.iter_mut().for_each(|constant| renumber(&mut constant.output));
+        for register in &mut netlist.registers {
+            renumber(&mut register.input);
+            renumber(&mut register.output);
+        }
+        for gate in &mut netlist.gates {
+            renumber(&mut gate.left);
+            renumber(&mut gate.right);
+            renumber(&mut gate.output);
+        }
+
+        netlist.net_count = count;
+    }
+
+    fn stimulus_text(&self) -> String {
+        let inputs: Vec<usize> = (0..self.netlist.ports.len()).filter(|index| self.netlist.ports[*index].direction == PortDirection::Input).collect();
+        if inputs.is_empty() {
+            return format!("{} cycles without inputs", self.stimulus.len());
+        }
+
+        let mut text = inputs.iter().map(|index| self.netlist.ports[*index].name.clone()).collect::<Vec<String>>().join(" ");
+
+        for states in &self.stimulus {
+            text.push('\n');
+            text.push_str(&inputs.iter().map(|index| match states[*index] {
+                LogicState::Low => "0",
+                LogicState::High => "1",
+                LogicState::Floating => "z",
+                LogicState::Metastable => "x",
+            }).collect::<Vec<&str>>().join(" "));
+        }
+
+        return text;
+    }
+}
+
+// the semantics of a tick written down as plainly as possible without sharing any code with the emulator:
+// constants drive every tick, input ports on the rising one and registers on their own edge, a gate fires once
+// both of its inputs were driven, and a register written on its own edge turns metastable.
+// this is not a settle to a fixed point: a gate whose inputs are driven on different edges never fires and stays
+// floating, the reference copies that rule on purpose so that only the order of propagation is checked
+struct Reference<'a> {
+    netlist: &'a Netlist,
+    register_states: Vec<LogicState>,
+    register_inputs: Vec<LogicState>,
+    gate_states: Vec<LogicState>,
+    port_states: Vec<LogicState>,
+}
+
+impl<'a> Reference<'a> {
+
+    fn new(netlist: &'a Netlist) -> Self {
+        let initial: Vec<LogicState> = netlist.registers.iter().map(|register| register.initial).collect();
+        return Self {
+            netlist: netlist,
+            register_states: initial.clone(),
+            register_inputs: initial,
+            gate_states: vec![LogicState::Floating; netlist.gates.len()],
+            port_states: vec![LogicState::Floating; netlist.ports.len()],
+        };
+    }
+
+    fn evaluate(operator: Operator, left: LogicState, right: LogicState) -> LogicState {
+        match (left, right) {
+            (LogicState::Floating, _) | (_, LogicState::Floating) => return LogicState::Floating,
+            (LogicState::Metastable, _) | (_, LogicState::Metastable) => return LogicState::Metastable,
+            _known => (),
+        }
+
+        let (left, right) = (left == LogicState::High, right == LogicState::High);
+        match operator {
+            Operator::And => return LogicState::from_boolean(left && right),
+            Operator::Or => return LogicState::from_boolean(left || right),
+            Operator::Xor => return LogicState::from_boolean(left != right),
+        }
+    }
+
+    fn tick(&mut self, rising: bool) {
+
+        let netlist = self.netlist;
+        self.register_states = self.register_inputs.clone();
+
+        let mut driven: Vec<Option<LogicState>> = vec![None; netlist.net_count];
+        for constant in &netlist.constants {
+            driven[constant.output] = Some(constant.state);
+        }
+
+        for (index, port) in netlist.ports.iter().enumerate() {
+            if rising && port.direction == PortDirection::Input {
+                driven[port.net] = Some(self.port_states[index]);
+            }
+        }
+
+        for (index, register) in netlist.registers.iter().enumerate() {
+            if register.rising == rising {
+                driven[register.output] = Some(self.register_states[index]);
+            }
+        }
+
+        // sweeps over all gates until none can fire anymore, so the order they are stored in does not matter
+        let mut fired = vec![false; netlist.gates.len()];
+        loop {
+            let mut changed = false;
+            for (index, gate) in netlist.gates.iter().enumerate() {
+                if let (false, Some(left), Some(right)) = (fired[index], driven[gate.left], driven[gate.right]) {
+                    let state = Self::evaluate(gate.operator, left, right);
+                    self.gate_states[index] = state;
+                    driven[gate.output] = Some(state);
+                    fired[index] = true;
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        for (index, register) in netlist.registers.iter().enumerate() {
+            if let Some(state) = driven[register.input] {
+                self.register_inputs[index] = match register.rising == rising {
+                    true => LogicState::Metastable,
+                    false => state,
+                };
+            }
+        }
+
+        for (index, port) in netlist.ports.iter().enumerate() {
+            if let (PortDirection::Output, Some(state)) = (port.direction, driven[port.net]) {
+                self.port_states[index] = state;
+            }
+        }
+    }
+}
+
+fn state_name(state: LogicState) -> String {
+    return format!("{:?}", state).to_lowercase();
+}
+
+impl Core {
+
+    // the first difference between the emulator and the reference
+    fn compare_reference(&self, reference: &Reference) -> Option<String> {
+
+        let differs = |what: String, actual: LogicState, expected: LogicState| match actual == expected {
+            true => None,
+            false => Some(format!("{} is {} but should be {}", what, state_name(actual), state_name(expected))),
+        };
+
+        // elements are named after the nets they drive, which the verilog of the circuit names n<net>
+        let netlist = reference.netlist;
+        for (index, register) in self.registers.iter().enumerate() {
+            let output = netlist.registers[index].output;
+            let difference = differs(format!("register {} driving net {}", index, output), register.state, reference.register_states[index])
+                .or_else(|| differs(format!("the input of register {} driving net {}", index, output), register.input.state, reference.register_inputs[index]));
+            if difference.is_some() {
+                return difference;
+            }
+        }
+
+        for (index, gate) in self.gates.iter().enumerate() {
+            if let Some(difference) = differs(format!("gate {} driving net {}", index, netlist.gates[index].output), gate.state, reference.gate_states[index]) {
+                return Some(difference);
+            }
+        }
+
+        for (index, port) in self.ports.iter().enumerate().filter(|(_index, port)| !port.is_input()) {
+            if let Some(difference) = differs(format!("port {}", port.name), port.state, reference.port_states[index]) {
+                return Some(difference);
+            }
+        }
+
+        return None;
+    }
+}
+
+// runs the case on both and returns the first tick in which they differ
+fn diverge(case: &Case) -> Result<Option<(usize, String)>, String> {
+
+    let mut core = Core::from_netlist(String::from("fuzz"), &case.netlist, FloatVector::new(), false)?;
+    let mut reference = Reference::new(&case.netlist);
+
+    for (cycle, states) in case.stimulus.iter().enumerate() {
+
+        for (index, state) in states.iter().enumerate() {
+            if core.ports[index].is_input() {
+                core.set_port_state(index, *state);
+                reference.port_states[index] = *state;
+            }
+        }
+
+        for (offset, rising) in [true, false].iter().enumerate() {
+            core.tick(*rising);
+            reference.tick(*rising);
+
+            if let Some(difference) = core.compare_reference(&reference) {
+                return Ok(Some((cycle * 2 + offset, difference)));
+            }
+        }
+    }
+
+    return Ok(None);
+}
+
+// removes elements one at a time for as long as the difference remains
+fn minimize(mut case: Case) -> Result<(Case, usize, String), String> {
+
+    loop {
+        let (tick, _difference) = diverge(&case)?.unwrap();
+
+        // nothing after the tick with the difference matters
+        case.stimulus.truncate(tick / 2 + 1);
+
+        let mut reduced = None;
+        for element in case.elements() {
+            let candidate = case.without(element);
+            if candidate.netlist.validate().is_ok() && diverge(&candidate)?.is_some() {
+                reduced = Some(candidate);
+                break;
+            }
+        }
+
+        match reduced {
+            Some(candidate) => case = candidate,
+            None => {
+                // the difference is found again so that it refers to the renumbered nets
+                case.compact();
+                let (tick, difference) = diverge(&case)?.unwrap();
+                return Ok((case, tick, difference));
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Divergence {
+    // the seed that generates the case on its own
+    pub seed: u64,
+    pub tick: usize,
+    pub difference: String,
+    pub verilog: String,
+    pub stimulus: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct FuzzReport {
+    pub cases: usize,
+    pub divergence: Option<Divergence>,
+}
+
+impl fmt::Display for FuzzReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        let divergence = match &self.divergence {
+            Some(divergence) => divergence,
+            None => return write!(f, "{} random circuits behave like the reference", self.cases),
+        };
+
+        let edge = match divergence.tick % 2 {
+            0 => "rising",
+            _ => "falling",
+        };
+
+        write!(f, "case {} with seed {} diverges from the reference on the {} edge of cycle {}: {}", self.cases, divergence.seed, edge, divergence.tick / 2, divergence.difference)?;
+        write!(f, "\n  minimized circuit:\n    {}", divergence.verilog.trim_end().replace("\n", "\n    "))?;
+        write!(f, "\n  stimulus:\n    {}", divergence.stimulus.replace("\n", "\n    "))?;
+        return Ok(());
+    }
+}
+
+// every case is generated from its own seed, so a divergence can be reproduced with a single case
+pub fn fuzz(cases: usize, seed: u64) -> Result<FuzzReport, String> {
+
+    for index in 0..cases {
+
+        let case_seed = seed.wrapping_add(index as u64);
+        let case = Case::generate(&mut Random::new(case_seed));
+
+        if diverge(&case)?.is_some() {
+            let (case, tick, difference) = minimize(case)?;
+            return Ok(FuzzReport {
+                cases: index + 1,
+                divergence: Some(Divergence {
+                    seed: case_seed,
+                    tick: tick,
+                    difference: difference,
+                    verilog: case.netlist.to_verilog()?,
+                    stimulus: case.stimulus_text(),
+                }),
+            });
+        }
+    }
+
+    return Ok(FuzzReport {
+        cases: cases,
+        divergence: None,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ fuzz, diverge, Case, Reference };
+    use super::super::{ Core, LogicState, Operator };
+    use super::super::netlist::{ Netlist, PortDirection };
+    use crate::types::FloatVector;
+
+    const CASES: usize = 2000;
+    const SEED: u64 = 0x5eed;
+
+    // catches changes to the propagation order that the emulator and the reference no longer agree on
+    #[test]
+    fn engine_matches_reference() {
+        let report = fuzz(CASES, SEED).unwrap();
+        assert!(report.divergence.is_none(), "{}", report);
+    }
+
+    // a register on each edge with nothing driving it, so both keep their high state, anded together
+    fn mixed_edges() -> Netlist {
+        let mut netlist = Netlist::new("mixed");
+        let (rising, falling) = (netlist.add_net(), netlist.add_net());
+        let (unused_rising, unused_falling) = (netlist.add_net(), netlist.add_net());
+        netlist.add_register(true, LogicState::High, unused_rising, rising);
+        netlist.add_register(false, LogicState::High, unused_falling, falling);
+        let output = netlist.add_gate_net(Operator::And, rising, falling);
+        netlist.add_port("out", PortDirection::Output, output);
+        return netlist;
+    }
+
+    // the quirk both models share, a settle to a fixed point of the same circuit would drive the output high
+    #[test]
+    fn gates_fed_on_different_edges_never_fire() {
+
+        let netlist = mixed_edges();
+        let settled = Reference::evaluate(Operator::And, netlist.registers[0].initial, netlist.registers[1].initial);
+        assert_eq!(settled, LogicState::High);
+
+        let mut core = Core::from_netlist(String::from("mixed"), &netlist, FloatVector::new(), false).unwrap();
+        for rising in [true, false, true, false].iter() {
+            core.tick(*rising);
+            assert_eq!(core.registers.iter().map(|register| register.state).collect::<Vec<LogicState>>(), vec![LogicState::High; 2]);
+            assert_eq!(core.gates[0].state, LogicState::Floating);
+            assert_eq!(core.ports[0].state, LogicState::Floating);
+        }
+
+        let case = Case {
+            netlist: netlist,
+            stimulus: vec![vec![LogicState::Floating]; 2],
+        };
+        assert!(diverge(&case).unwrap().is_none());
+    }
+}
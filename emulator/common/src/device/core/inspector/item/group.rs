@@ -2,7 +2,7 @@ use super::{ InspectorItem, Formatting };
 
 #[derive(Clone, Debug)]
 pub struct Group {
-    pub identifier: &'static str,
+    pub identifier: String,
     pub tracker: Option<(usize, Formatting)>,
     pub items: Vec<InspectorItem>,
     pub expanded: bool,
@@ -10,9 +10,9 @@ pub struct Group {
 
 impl Group {
 
-    pub fn new(identifier: &'static str, tracker: Option<(usize, Formatting)>, items: Vec<InspectorItem>) -> Self {
+    pub fn new(identifier: &str, tracker: Option<(usize, Formatting)>, items: Vec<InspectorItem>) -> Self {
         return Self {
-            identifier: identifier,
+            identifier: String::from(identifier),
             tracker: tracker,
             items: items,
             expanded: true, // TODO: false
@@ -1,14 +1,14 @@
 #[derive(Clone, Debug)]
 pub struct Label {
-    pub identifier: &'static str,
+    pub identifier: String,
     pub tracker: usize,
 }
 
 impl Label {
 
-    pub fn new(identifier: &'static str, tracker: usize) -> Self {
+    pub fn new(identifier: &str, tracker: usize) -> Self {
         return Self {
-            identifier: identifier,
+            identifier: String::from(identifier),
             tracker: tracker,
         }
     }
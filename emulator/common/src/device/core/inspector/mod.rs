@@ -1,10 +1,12 @@
 mod tracker;
 mod item;
+mod vcd;
 
 pub use self::tracker::*;
 pub use self::item::*;
+pub use self::vcd::{ VcdWriter, VcdReader };
 
-use super::{ LogicState, ValueState, Register, Gate };
+use super::{ LogicState, ValueState, Register, Gate, Port, Signal };
 use crate::types::*;
 
 const LABEL_HEIGHT: f32 = 20.0;
@@ -27,15 +29,30 @@ const STABLE_COLOR: Color = Color::from(100, 150, 100);
 const METASTABLE_COLOR: Color = Color::from(250, 100, 100);
 const FLOATING_COLOR: Color = Color::from(150, 100, 100);
 const VALUE_COLOR: Color = Color::from(100, 100, 150);
+const IDLE_LABEL_COLOR: Color = Color::from(85, 45, 45);
+const PARTIAL_LABEL_COLOR: Color = Color::from(80, 70, 40);
+const ANALOG_COLOR: Color = Color::from(150, 130, 80);
+
+#[derive(Copy, Clone, Debug)]
+enum TrackerReference {
+    Logic(usize),
+    Value(usize, Formatting),
+}
 
 pub struct Inspector {
     interface_size: FloatVector,
     label_width: f32,
     show_trackers: bool,
+    show_activity: bool,
     root_item: InspectorItem,
     logic_trackers: Vec<LogicTracker>,
+    // trackers added by track_all and add_derived come after these and are not part of snapshots
+    snapshot_trackers: usize,
+    tracking_all: bool,
     value_trackers: Vec<ValueTracker>,
+    analog_trackers: Vec<AnalogTracker>,
     step_size: f32,
+    cursor: Option<usize>,
 }
 
 impl Inspector {
@@ -49,29 +66,135 @@ impl Inspector {
             interface_size: interface_size,
             label_width: label_width,
             show_trackers: true,
+            show_activity: false,
             root_item: root_item,
+            snapshot_trackers: logic_trackers.len(),
+            tracking_all: false,
             logic_trackers: logic_trackers,
             value_trackers: value_trackers,
+            analog_trackers: Vec::new(),
             step_size: TRACKER_STEP,
+            cursor: None,
         };
     }
 
+    pub fn from_vcd(name: &str, interface_size: FloatVector, source: &str) -> Result<Self, String> {
+        let (root_item, logic_trackers, value_trackers) = VcdReader::read(name, source)?;
+        return Ok(Self::new(interface_size, logic_trackers, value_trackers, root_item));
+    }
+
     pub fn handle_key_input(&mut self, key: Key) {
         if key == Key::T {
             self.show_trackers = !self.show_trackers;
         }
+
+        if key == Key::A {
+            self.show_activity = !self.show_activity;
+        }
     }
 
     pub fn resize(&mut self, interface_size: FloatVector) {
         self.interface_size = interface_size;
     }
 
-    pub fn update(&mut self, registers: &Vec<Register>, gates: &Vec<Gate>) {
+    pub fn track_all(&mut self, registers: &[Register], gates: &[Gate]) {
+
+        if self.tracking_all {
+            return;
+        }
+        self.tracking_all = true;
+
+        // pad new trackers so that they line up with the ones that already recorded states
+        let recorded = self.samples();
+
+        let mut register_items = Vec::new();
+        for index in 0..registers.len() {
+            register_items.push(InspectorItem::Label(Label::new(&format!("register {}", index), self.logic_trackers.len())));
+            self.logic_trackers.push(LogicTracker::new(LabelSource::Register(index)));
+            self.logic_trackers.last_mut().unwrap().states.resize(recorded, LogicState::Floating);
+        }
+
+        let mut gate_items = Vec::new();
+        for index in 0..gates.len() {
+            let mut pin_items = Vec::new();
+
+            for (identifier, right) in &[("left", false), ("right", true)] {
+                pin_items.push(InspectorItem::Label(Label::new(identifier, self.logic_trackers.len())));
+                self.logic_trackers.push(LogicTracker::new(LabelSource::Gate(index, *right)));
+                self.logic_trackers.last_mut().unwrap().states.resize(recorded, LogicState::Floating);
+            }
+
+            gate_items.push(InspectorItem::Group(Group::new(&format!("gate {}", index), None, pin_items)));
+        }
+
+        let internal_items = vec![
+            InspectorItem::Group(Group::new("registers", None, register_items)),
+            InspectorItem::Group(Group::new("gates", None, gate_items)),
+        ];
+        let internal_item = InspectorItem::Group(Group::new("internal", None, internal_items));
+
+        // shown like any other item at the end of the core, whose root item is always a group
+        if let InspectorItem::Group(group) = &mut self.root_item {
+            group.items.push(internal_item);
+        }
+    }
+
+    // derived signals are computed by the core, so their trackers have no source and are filled by push_derived
+    pub fn add_derived(&mut self, identifier: &str) -> Result<usize, String> {
+
+        let tracker = self.logic_trackers.len();
+        let recorded = self.samples();
+
+        let root_group = match &mut self.root_item {
+            InspectorItem::Group(group) => group,
+            InspectorItem::Label(..) => return Err(String::from("inspector has no root group")),
+        };
+
+        if !root_group.items.iter().any(|item| matches!(item, InspectorItem::Group(group) if group.identifier == "derived")) {
+            root_group.items.push(InspectorItem::Group(Group::new("derived", None, Vec::new())));
+        }
+
+        for item in &mut root_group.items {
+            if let InspectorItem::Group(group) = item {
+                if group.identifier == "derived" {
+
+                    if group.items.iter().any(|item| matches!(item, InspectorItem::Label(label) if label.identifier == identifier)) {
+                        return Err(format!("derived signal {} is already defined", identifier));
+                    }
+
+                    group.items.push(InspectorItem::Label(Label::new(identifier, tracker)));
+                }
+            }
+        }
+
+        // padded so that it lines up with the trackers that already recorded states
+        self.logic_trackers.push(LogicTracker::recorded(vec![LogicState::Floating; recorded]));
+        return Ok(tracker);
+    }
+
+    // called after update, so the new state belongs to the latest sample
+    pub fn push_derived(&mut self, tracker: usize, state: LogicState) {
+        self.logic_trackers[tracker].states.push(state);
+    }
+
+    pub fn sources(&self) -> Vec<LabelSource> {
+        return self.logic_trackers.iter().filter_map(|tracker| tracker.source).collect();
+    }
+
+    // used when the circuit is optimized and gates move to a different index
+    pub fn remap_sources(&mut self, remap: &dyn Fn(LabelSource) -> LabelSource) {
+        for tracker in &mut self.logic_trackers {
+            tracker.source = tracker.source.map(remap);
+        }
+    }
+
+    pub fn update(&mut self, registers: &[Register], gates: &[Gate], ports: &[Port]) {
 
         for index in 0..self.logic_trackers.len() {
-            let source = self.logic_trackers[index].source.clone();
-            let state = source.get_state(registers, gates);
-            self.logic_trackers[index].states.push(state);
+            if let Some(source) = self.logic_trackers[index].source {
+                let state = source.get_state(registers, gates, ports);
+                self.logic_trackers[index].states.push(state);
+            }
         }
 
         for index in 0..self.value_trackers.len() {
@@ -80,30 +203,257 @@ impl Inspector {
         }
     }
 
+    // called after update, so the new value belongs to the latest sample
+    pub fn push_analog(&mut self, identifier: &str, value: f64) {
+
+        let samples = self.samples();
+        let index = match self.analog_trackers.iter().position(|tracker| tracker.identifier == identifier) {
+            Some(index) => index,
+            None => {
+                self.analog_trackers.push(AnalogTracker::new(identifier));
+                self.analog_trackers.len() - 1
+            },
+        };
+
+        let tracker = &mut self.analog_trackers[index];
+        tracker.values.resize(samples.saturating_sub(1), 0.0);
+        tracker.values.push(value);
+    }
+
+    // recordings may only contain value trackers
+    pub fn samples(&self) -> usize {
+        let logic_samples = self.logic_trackers.first().map(|tracker| tracker.states.len());
+        let value_samples = self.value_trackers.first().map(|tracker| tracker.states.len());
+        return logic_samples.max(value_samples).unwrap_or(0);
+    }
+
+    // only the first samples are shown until the cursor is cleared again
+    pub fn set_cursor(&mut self, samples: usize) {
+        self.cursor = (samples < self.samples()).then_some(samples);
+    }
+
+    pub fn discard_future(&mut self) {
+        if let Some(cursor) = self.cursor.take() {
+            self.logic_trackers.iter_mut().for_each(|tracker| tracker.states.truncate(cursor));
+            self.value_trackers.iter_mut().for_each(|tracker| tracker.states.truncate(cursor));
+            self.analog_trackers.iter_mut().for_each(|tracker| tracker.values.truncate(cursor));
+        }
+    }
+
+    fn visible<'a, S>(&self, states: &'a [S]) -> &'a [S] {
+        match self.cursor {
+            Some(cursor) => return &states[..cursor.min(states.len())],
+            None => return states,
+        }
+    }
+
+    pub fn tracker_states(&self) -> (Vec<Vec<LogicState>>, Vec<Vec<ValueState>>) {
+        let logic_states = self.logic_trackers[..self.snapshot_trackers].iter().map(|tracker| tracker.states.clone()).collect();
+        let value_states = self.value_trackers.iter().map(|tracker| tracker.states.clone()).collect();
+        return (logic_states, value_states);
+    }
+
+    pub fn validate_tracker_states(&self, logic_states: &[Vec<LogicState>], value_states: &[Vec<ValueState>]) -> Result<(), String> {
+
+        if logic_states.len() != self.snapshot_trackers || value_states.len() != self.value_trackers.len() {
+            return Err(String::from("snapshot was taken with a different set of trackers"));
+        }
+
+        return Ok(());
+    }
+
+    pub fn restore_tracker_states(&mut self, logic_states: &[Vec<LogicState>], value_states: &[Vec<ValueState>]) {
+        self.logic_trackers.iter_mut().zip(logic_states.iter()).for_each(|(tracker, states)| tracker.states = states.clone());
+        self.value_trackers.iter_mut().zip(value_states.iter()).for_each(|(tracker, states)| tracker.states = states.clone());
+        self.cursor = None;
+
+        // analog values and trackers added by track_all are not part of snapshots, so they are cut or padded to the restored samples
+        let samples = logic_states.first().map(Vec::len).max(value_states.first().map(Vec::len)).unwrap_or(0);
+        self.analog_trackers.iter_mut().for_each(|tracker| tracker.values.truncate(samples));
+        self.logic_trackers[self.snapshot_trackers..].iter_mut().for_each(|tracker| tracker.states.resize(samples, LogicState::Floating));
+    }
+
     fn get_value_state(&self, index: usize) -> ValueState {
-        let mut value = 0;
+        let states = self.value_trackers[index].trackers.iter().map(|index| *self.logic_trackers[*index].states.last().unwrap());
+        return ValueState::from_logic_states(states);
+    }
+
+    pub fn states(&self) -> Vec<(String, String)> {
+        let mut states = Vec::new();
+
+        for (path, reference) in self.get_trackers() {
+            let text = match reference {
+
+                TrackerReference::Logic(index) => {
+                    match self.visible(&self.logic_trackers[index].states).last() {
+                        Some(state) => String::from(Self::get_state_text(state)),
+                        None => String::from("-"),
+                    }
+                },
+
+                TrackerReference::Value(index, formatting) => {
+                    let length = self.value_trackers[index].trackers.len();
+                    match self.visible(&self.value_trackers[index].states).last() {
+                        Some(value) => Self::get_value_text(value, formatting, length),
+                        None => String::from("-"),
+                    }
+                },
+            };
+
+            states.push((path, text));
+        }
+
+        return states;
+    }
+
+    pub fn trace(&self) -> String {
+        let mut trace = String::new();
+
+        for (path, reference) in self.get_trackers() {
+            trace.push_str(&path);
+            trace.push(':');
 
-        for index in &self.value_trackers[index].trackers {
-            match self.logic_trackers[*index].states.last().unwrap() {
+            match reference {
 
-                LogicState::High => {
-                    value = value << 1;
-                    value = value | 1;
+                TrackerReference::Logic(index) => {
+                    trace.push(' ');
+                    self.logic_trackers[index].states.iter().for_each(|state| trace.push(Self::get_state_character(state)));
                 },
 
-                LogicState::Low => value = value << 1,
+                TrackerReference::Value(index, formatting) => {
+                    let length = self.value_trackers[index].trackers.len();
+                    for value in &self.value_trackers[index].states {
+                        trace.push(' ');
+                        trace.push_str(&Self::get_value_text(value, formatting, length));
+                    }
+                },
+            }
+
+            trace.push('\n');
+        }
+
+        return trace;
+    }
+
+    pub fn write_vcd(&self, writer: &mut VcdWriter) {
+        self.write_vcd_item(writer, &self.root_item);
+    }
+
+    fn write_vcd_item(&self, writer: &mut VcdWriter, item: &InspectorItem) {
+        match item {
+
+            InspectorItem::Group(group) => {
+
+                if let Some((index, _formatting)) = group.tracker {
+                    let tracker = &self.value_trackers[index];
+                    writer.add_value(&group.identifier, tracker.trackers.len(), &tracker.states);
+                }
+
+                writer.begin_scope(&group.identifier);
+                group.items.iter().for_each(|item| self.write_vcd_item(writer, item));
+                writer.end_scope();
+            },
+
+            InspectorItem::Label(label) => writer.add_logic(&label.identifier, &self.logic_trackers[label.tracker].states),
+        }
+    }
+
+    // every labeled tracker and every group with a value tracker becomes a named signal
+    pub fn collect_signals(root_item: &InspectorItem, logic_trackers: &[LogicTracker], value_trackers: &[ValueTracker]) -> Vec<Signal> {
+        let mut trackers = Vec::new();
+        Self::collect_trackers(root_item, "", &mut trackers);
+
+        let logic_source = |index: usize| logic_trackers[index].source;
+        let mut signals = Vec::new();
 
-                LogicState::Metastable => return ValueState::Metastable,
+        for (path, reference) in trackers {
+            let sources: Vec<LabelSource> = match reference {
+                TrackerReference::Logic(index) => logic_source(index).into_iter().collect(),
+                TrackerReference::Value(index, _formatting) => value_trackers[index].trackers.iter().filter_map(|index| logic_source(*index)).collect(),
+            };
 
-                LogicState::Floating => return ValueState::Floating,
+            if !sources.is_empty() {
+                signals.push(Signal::new(path, sources));
             }
         }
 
-        return ValueState::Stable(value);
+        return signals;
+    }
+
+    fn get_trackers(&self) -> Vec<(String, TrackerReference)> {
+        let mut trackers = Vec::new();
+        Self::collect_trackers(&self.root_item, "", &mut trackers);
+        return trackers;
+    }
+
+    fn collect_trackers(item: &InspectorItem, prefix: &str, trackers: &mut Vec<(String, TrackerReference)>) {
+        match item {
+
+            InspectorItem::Group(group) => {
+                let path = format!("{}{}", prefix, Self::sanitize(&group.identifier));
+
+                if let Some((index, formatting)) = group.tracker {
+                    trackers.push((path.clone(), TrackerReference::Value(index, formatting)));
+                }
+
+                let prefix = format!("{}/", path);
+                group.items.iter().for_each(|item| Self::collect_trackers(item, &prefix, trackers));
+            },
+
+            InspectorItem::Label(label) => {
+                let path = format!("{}{}", prefix, Self::sanitize(&label.identifier));
+                trackers.push((path, TrackerReference::Logic(label.tracker)));
+            },
+        }
     }
 
     pub fn draw<T: Renderer>(&self, renderer: &mut T, mut position: FloatVector) {
         self.draw_item(renderer, &self.root_item, &mut position, 0.0);
+        self.analog_trackers.iter().for_each(|tracker| self.draw_analog(renderer, tracker, &mut position));
+    }
+
+    // drawn as a line scaled to the largest visible value
+    fn draw_analog<T: Renderer>(&self, renderer: &mut T, tracker: &AnalogTracker, position: &mut FloatVector) {
+
+        self.draw_common(renderer, &format!("  {}", tracker.identifier), *position, 0.0, LABEL_COLOR);
+
+        let values = self.visible(&tracker.values);
+        if let Some(value) = values.last() {
+
+            self.draw_state_text(renderer, *position, ANALOG_COLOR, &format!("{:.3}", value));
+
+            if self.show_trackers {
+
+                let maximum = values.iter().cloned().fold(0.0, f64::max);
+                let scale = match maximum > 0.0 {
+                    true => (LABEL_HEIGHT - TRACKER_MARGIN * 2.0) as f64 / maximum,
+                    false => 0.0,
+                };
+
+                let mut step_offset = 0.0;
+                let mut vertices = Vec::new();
+                let max_width = self.interface_size.x - self.label_width - TRACKER_OFFSET;
+
+                for value in values {
+
+                    let height = LABEL_HEIGHT - TRACKER_MARGIN - (value * scale) as f32;
+                    vertices.push(Vertex::new(FloatVector::from(step_offset, height), ANALOG_COLOR));
+                    step_offset += self.step_size;
+
+                    if step_offset > max_width {
+                        vertices.push(Vertex::new(FloatVector::from(max_width, height), ANALOG_COLOR));
+                        break;
+                    } else {
+                        vertices.push(Vertex::new(FloatVector::from(step_offset, height), ANALOG_COLOR));
+                    }
+                }
+
+                let graph_position = *position + FloatVector::with_x(self.label_width + TRACKER_OFFSET);
+                renderer.draw_line_segment(graph_position, &vertices);
+            }
+        }
+
+        position.y += LABEL_HEIGHT + ITEM_GAP;
     }
 
     fn draw_state_text<T: Renderer>(&self, renderer: &mut T, position: FloatVector, color: Color, source: &str) {
@@ -111,9 +461,9 @@ impl Inspector {
         renderer.draw_text_right(source, right_position, color, TEXT_SIZE);
     }
 
-    fn draw_common<T: Renderer>(&self, renderer: &mut T, text: &str, position: FloatVector, level: f32) {
+    fn draw_common<T: Renderer>(&self, renderer: &mut T, text: &str, position: FloatVector, level: f32, color: Color) {
 
-        renderer.draw_rectangle(position, FloatVector::from(self.label_width, LABEL_HEIGHT), LABEL_COLOR);
+        renderer.draw_rectangle(position, FloatVector::from(self.label_width, LABEL_HEIGHT), color);
         renderer.draw_text(text, position + FloatVector::from(level + IDENTIFIER_OFFSET, TEXT_PADDING), TEXT_COLOR, TEXT_SIZE);
 
         if self.show_trackers {
@@ -146,10 +496,10 @@ impl Inspector {
                     false => format!("+ {}", group.identifier),
                 };
 
-                self.draw_common(renderer, &text, *position, level);
+                self.draw_common(renderer, &text, *position, level, LABEL_COLOR);
 
                 if let Some((index, formatting)) = group.tracker {
-                    if let Some(value) = self.value_trackers[index].states.last() {
+                    if let Some(value) = self.visible(&self.value_trackers[index].states).last() {
 
                         let color = Self::get_value_color(value);
                         let length = self.value_trackers[index].trackers.len();
@@ -162,7 +512,7 @@ impl Inspector {
                             let mut vertices = Vec::new();
                             let mut previous_state = ValueState::Stable(0);
 
-                            for state in &self.value_trackers[index].states {
+                            for state in self.visible(&self.value_trackers[index].states) {
 
                                 let color = Self::get_value_color(state);
                                 let height = LABEL_HEIGHT / 2.0;
@@ -202,9 +552,9 @@ impl Inspector {
 
             InspectorItem::Label(label) => {
 
-                self.draw_common(renderer, &format!("  {}", label.identifier), *position, level);
+                self.draw_common(renderer, &format!("  {}", label.identifier), *position, level, self.get_label_color(label.tracker));
 
-                if let Some(state) = self.logic_trackers[label.tracker].states.last() {
+                if let Some(state) = self.visible(&self.logic_trackers[label.tracker].states).last() {
 
                     let color = Self::get_state_color(state);
                     let text = Self::get_state_text(state);
@@ -215,7 +565,7 @@ impl Inspector {
                         let mut step_offset = 0.0;
                         let mut vertices = Vec::new();
 
-                        for state in &self.logic_trackers[label.tracker].states {
+                        for state in self.visible(&self.logic_trackers[label.tracker].states) {
 
                             let color = Self::get_state_color(state);
                             let height = Self::get_state_height(state);
@@ -243,6 +593,38 @@ impl Inspector {
         }
     }
 
+    // paths are used to refer to signals, so they should not contain any whitespace
+    fn sanitize(identifier: &str) -> String {
+        return identifier.split_whitespace().collect::<Vec<&str>>().join("_");
+    }
+
+    // labels that never toggled stand out when activity is shown, ones that only rose or fell less so
+    fn get_label_color(&self, tracker: usize) -> Color {
+
+        if !self.show_activity {
+            return LABEL_COLOR;
+        }
+
+        let mut last = None;
+        let (mut rose, mut fell) = (false, false);
+        for state in &self.logic_trackers[tracker].states {
+            let value = match state {
+                LogicState::High => true,
+                LogicState::Low => false,
+                _other => continue,
+            };
+            rose |= last == Some(false) && value;
+            fell |= last == Some(true) && !value;
+            last = Some(value);
+        }
+
+        match (rose, fell) {
+            (true, true) => return LABEL_COLOR,
+            (false, false) => return IDLE_LABEL_COLOR,
+            _partial => return PARTIAL_LABEL_COLOR,
+        }
+    }
+
     fn get_value_color(value: &ValueState) -> Color {
         match value {
             ValueState::Stable(..) => return VALUE_COLOR,
@@ -287,6 +669,15 @@ impl Inspector {
         }
     }
 
+    fn get_state_character(state: &LogicState) -> char {
+        match state {
+            LogicState::High => return '1',
+            LogicState::Low => return '0',
+            LogicState::Metastable => return 'x',
+            LogicState::Floating => return 'z',
+        }
+    }
+
     fn get_state_height(state: &LogicState) -> f32 {
         match state {
             LogicState::High => return TRACKER_MARGIN,
@@ -0,0 +1,15 @@
+// a quantity that is computed every tick rather than read from the circuit, like the estimated power
+pub struct AnalogTracker {
+    pub identifier: String,
+    pub values: Vec<f64>,
+}
+
+impl AnalogTracker {
+
+    pub fn new(identifier: &str) -> Self {
+        return Self {
+            identifier: String::from(identifier),
+            values: Vec::new(),
+        };
+    }
+}
@@ -2,7 +2,7 @@ use super::LabelSource;
 use crate::device::core::LogicState;
 
 pub struct LogicTracker {
-    pub source: LabelSource,
+    pub source: Option<LabelSource>,
     pub states: Vec<LogicState>,
 }
 
@@ -10,8 +10,15 @@ impl LogicTracker {
 
     pub fn new(source: LabelSource) -> Self {
         return Self {
-            source: source,
+            source: Some(source),
             states: Vec::new(),
         };
     }
+
+    pub fn recorded(states: Vec<LogicState>) -> Self {
+        return Self {
+            source: None,
+            states: states,
+        };
+    }
 }
@@ -1,7 +1,9 @@
 mod source;
 mod logic;
 mod value;
+mod analog;
 
 pub use self::source::LabelSource;
 pub use self::logic::LogicTracker;
 pub use self::value::ValueTracker;
+pub use self::analog::AnalogTracker;
@@ -1,14 +1,24 @@
-use crate::device::core::{ LogicState, Register, Gate };
+use crate::device::core::{ LogicState, Register, Gate, Port };
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum LabelSource {
     Register(usize),
     Gate(usize, bool),
+    Port(usize),
 }
 
 impl LabelSource {
 
-    pub fn get_state(&self, registers: &Vec<Register>, gates: &Vec<Gate>) -> LogicState {
+    pub fn describe(&self) -> String {
+        match self {
+            LabelSource::Register(index) => return format!("register {}", index),
+            LabelSource::Gate(index, false) => return format!("left input of gate {}", index),
+            LabelSource::Gate(index, true) => return format!("right input of gate {}", index),
+            LabelSource::Port(index) => return format!("port {}", index),
+        }
+    }
+
+    pub fn get_state(&self, registers: &[Register], gates: &[Gate], ports: &[Port]) -> LogicState {
         match self {
 
             LabelSource::Register(index) => return registers[*index].input.state,
@@ -20,6 +30,8 @@ impl LabelSource {
                 }
 
             },
+
+            LabelSource::Port(index) => return ports[*index].state,
         }
     }
 }
@@ -0,0 +1,108 @@
+mod writer;
+mod reader;
+
+pub use self::writer::VcdWriter;
+pub use self::reader::VcdReader;
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ VcdWriter, VcdReader };
+    use super::super::InspectorItem;
+    use crate::device::core::{ LogicState, ValueState };
+
+    // every label and every group with a value tracker by its path
+    fn paths(item: &InspectorItem, prefix: &str, paths: &mut Vec<String>) {
+        match item {
+            InspectorItem::Group(group) => {
+                let path = format!("{}{}", prefix, group.identifier);
+                if group.tracker.is_some() {
+                    paths.push(path.clone());
+                }
+                group.items.iter().for_each(|item| self::paths(item, &format!("{}/", path), paths));
+            },
+            InspectorItem::Label(label) => paths.push(format!("{}{}", prefix, label.identifier)),
+        }
+    }
+
+    fn outline(item: &InspectorItem) -> Vec<String> {
+        let mut outline = Vec::new();
+        paths(item, "", &mut outline);
+        return outline;
+    }
+
+    #[test]
+    fn written_files_read_back() {
+
+        let clock = vec![LogicState::Low, LogicState::High, LogicState::Floating, LogicState::Metastable, LogicState::High, LogicState::High];
+        let bus = vec![ValueState::Stable(5), ValueState::Floating, ValueState::Metastable, ValueState::Stable(2), ValueState::Stable(2), ValueState::Stable(0)];
+
+        let mut writer = VcdWriter::new();
+        writer.begin_scope("top");
+        writer.add_logic("clock", &clock);
+        writer.begin_scope("inner");
+        writer.add_value("bus", 3, &bus);
+        writer.end_scope();
+        writer.end_scope();
+        let source = writer.finish();
+
+        let (root_item, logic_trackers, value_trackers) = VcdReader::read("recording", &source).unwrap();
+        assert_eq!(outline(&root_item), vec!["recording/top/clock", "recording/top/inner/bus", "recording/top/inner/bus/bus[2]", "recording/top/inner/bus/bus[1]", "recording/top/inner/bus/bus[0]"]);
+        assert_eq!(logic_trackers[0].states, clock);
+        assert_eq!(value_trackers[0].states, bus);
+        assert_eq!(logic_trackers[1].states, vec![LogicState::High, LogicState::Floating, LogicState::Metastable, LogicState::Low, LogicState::Low, LogicState::Low]);
+    }
+
+    // as written by other simulators, with a finer timescale and values that do not change every step
+    const FOREIGN: &str = "
+        $date today $end
+        $version some simulator $end
+        $timescale 1ps $end
+        $scope module testbench $end
+        $var wire 1 ! clk $end
+        $var real 64 \" temperature $end
+        $scope module dut $end
+        $var reg 4 # count [3:0] $end
+        $var wire 1 $ ready $end
+        $upscope $end
+        $upscope $end
+        $enddefinitions $end
+        #0
+        $dumpvars
+        0!
+        r21.5 \"
+        bx #
+        z$
+        $end
+        #10
+        1!
+        b1 #
+        #20
+        0!
+        b1z10 #
+        1$
+        r22 \"
+        #30
+        1!
+        bX #
+        #40
+    ";
+
+    #[test]
+    fn foreign_files_are_read() {
+
+        let (root_item, logic_trackers, value_trackers) = VcdReader::read("foreign", FOREIGN).unwrap();
+        assert_eq!(outline(&root_item), vec![
+            "foreign/testbench/clk", "foreign/testbench/dut/count", "foreign/testbench/dut/count/count[3]", "foreign/testbench/dut/count/count[2]",
+            "foreign/testbench/dut/count/count[1]", "foreign/testbench/dut/count/count[0]", "foreign/testbench/dut/ready",
+        ]);
+
+        // the real variable is left out, values are left extended with zeros unless they start with x or z
+        assert_eq!(logic_trackers.len(), 6);
+        assert_eq!(logic_trackers[0].states, vec![LogicState::Low, LogicState::High, LogicState::Low, LogicState::High]);
+        assert_eq!(value_trackers[0].states, vec![ValueState::Metastable, ValueState::Stable(1), ValueState::Floating, ValueState::Metastable]);
+        assert_eq!(logic_trackers[1..5].iter().map(|tracker| tracker.states[2]).collect::<Vec<LogicState>>(), vec![LogicState::High, LogicState::Floating, LogicState::High, LogicState::Low]);
+        assert_eq!(logic_trackers[1..5].iter().map(|tracker| tracker.states[3]).collect::<Vec<LogicState>>(), vec![LogicState::Metastable; 4]);
+        assert_eq!(logic_trackers[5].states, vec![LogicState::Floating, LogicState::Floating, LogicState::High, LogicState::High]);
+    }
+}
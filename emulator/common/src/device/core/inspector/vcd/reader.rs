@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use crate::device::core::{ LogicState, ValueState };
+use super::super::{ InspectorItem, Group, Label, Formatting, LogicTracker, ValueTracker };
+
+// files with a very fine timescale would otherwise produce gigantic trackers
+const MAXIMUM_SAMPLES: u64 = 1_000_000;
+
+struct VcdVariable {
+    width: usize,
+    trackers: Vec<usize>,
+    value_tracker: Option<usize>,
+    changes: Vec<(u64, String)>,
+}
+
+pub struct VcdReader<'a> {
+    tokens: Vec<(usize, &'a str)>,
+    position: usize,
+    scopes: Vec<(String, Vec<InspectorItem>)>,
+    variables: Vec<VcdVariable>,
+    identifiers: HashMap<String, usize>,
+    timestamps: Vec<u64>,
+    logic_tracker_count: usize,
+    value_tracker_count: usize,
+}
+
+impl<'a> VcdReader<'a> {
+
+    pub fn read(name: &str, source: &'a str) -> Result<(InspectorItem, Vec<LogicTracker>, Vec<ValueTracker>), String> {
+
+        let tokens = source.lines().enumerate().flat_map(|(index, line)| line.split_whitespace().map(move |token| (index + 1, token))).collect();
+
+        let mut reader = Self {
+            tokens: tokens,
+            position: 0,
+            scopes: vec![(String::from(name), Vec::new())],
+            variables: Vec::new(),
+            identifiers: HashMap::new(),
+            timestamps: Vec::new(),
+            logic_tracker_count: 0,
+            value_tracker_count: 0,
+        };
+
+        reader.parse()?;
+
+        if reader.scopes.len() != 1 {
+            return Err(format!("scope {} is never closed", reader.scopes.last().unwrap().0));
+        }
+
+        let (name, items) = reader.scopes.pop().unwrap();
+        let root_item = InspectorItem::Group(Group::new(&name, None, items));
+        let (logic_trackers, value_trackers) = reader.sample();
+        return Ok((root_item, logic_trackers, value_trackers));
+    }
+
+    fn next(&mut self) -> Option<(usize, &'a str)> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        return token;
+    }
+
+    fn expect(&mut self, context: &str) -> Result<&'a str, String> {
+        match self.next() {
+            Some((_line, token)) => return Ok(token),
+            None => return Err(format!("unexpected end of file in {}", context)),
+        }
+    }
+
+    fn expect_end(&mut self, context: &str) -> Result<(), String> {
+        match self.next() {
+            Some((_line, "$end")) => return Ok(()),
+            Some((line, token)) => return Err(format!("line {}: expected $end after {}, found {}", line, context, token)),
+            None => return Err(format!("unexpected end of file in {}", context)),
+        }
+    }
+
+    fn skip_section(&mut self, context: &str) -> Result<(), String> {
+        loop {
+            if self.expect(context)? == "$end" {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse(&mut self) -> Result<(), String> {
+        while let Some((line, token)) = self.next() {
+            match token {
+
+                "$scope" => {
+                    let _kind = self.expect("$scope")?;
+                    let name = self.expect("$scope")?;
+                    self.expect_end("$scope")?;
+                    self.scopes.push((String::from(name), Vec::new()));
+                },
+
+                "$upscope" => {
+                    self.expect_end("$upscope")?;
+
+                    if self.scopes.len() == 1 {
+                        return Err(format!("line {}: $upscope without matching $scope", line));
+                    }
+
+                    let (name, items) = self.scopes.pop().unwrap();
+                    self.scopes.last_mut().unwrap().1.push(InspectorItem::Group(Group::new(&name, None, items)));
+                },
+
+                "$var" => self.parse_variable(line)?,
+
+                "$comment" | "$date" | "$version" | "$timescale" => self.skip_section(token)?,
+
+                // value changes in these sections are handled like any other ones
+                "$enddefinitions" | "$dumpvars" | "$dumpall" | "$dumpon" | "$dumpoff" | "$end" => { },
+
+                token if token.starts_with('#') => {
+                    let time = token[1..].parse().map_err(|_| format!("line {}: invalid timestamp {}", line, token))?;
+
+                    if let Some(previous) = self.timestamps.last() {
+                        if time < *previous {
+                            return Err(format!("line {}: timestamp {} is before {}", line, time, previous));
+                        }
+                    }
+
+                    self.timestamps.push(time);
+                },
+
+                token if token.starts_with('b') || token.starts_with('B') => {
+                    let identifier = self.expect("vector value change")?;
+                    self.add_change(line, identifier, &token[1..])?;
+                },
+
+                // real values can not be represented
+                token if token.starts_with('r') || token.starts_with('R') => {
+                    self.expect("real value change")?;
+                },
+
+                token if token.starts_with(|character| "01xXzZ".contains(character)) => {
+                    self.add_change(line, &token[1..], &token[..1])?;
+                },
+
+                other => return Err(format!("line {}: unexpected token {}", line, other)),
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn parse_variable(&mut self, line: usize) -> Result<(), String> {
+
+        let kind = self.expect("$var")?;
+
+        // real values can not be represented, so they are left out entirely
+        if kind == "real" || kind == "realtime" {
+            return self.skip_section("$var");
+        }
+
+        let width = self.expect("$var")?;
+        let width: usize = width.parse().map_err(|_| format!("line {}: invalid variable width {}", line, width))?;
+        let identifier = self.expect("$var")?;
+        let name = self.expect("$var")?;
+
+        // an optional bit range may follow the name
+        self.skip_section("$var")?;
+
+        if width == 0 {
+            return Err(format!("line {}: variable {} has a width of zero", line, name));
+        }
+
+        let index = match self.identifiers.get(identifier) {
+
+            Some(index) => {
+                if self.variables[*index].width != width {
+                    return Err(format!("line {}: variable {} is declared with different widths", line, identifier));
+                }
+                *index
+            },
+
+            None => {
+                let trackers = (self.logic_tracker_count..self.logic_tracker_count + width).collect();
+                self.logic_tracker_count += width;
+
+                let value_tracker = (width > 1).then_some(self.value_tracker_count);
+                if width > 1 {
+                    self.value_tracker_count += 1;
+                }
+
+                self.identifiers.insert(String::from(identifier), self.variables.len());
+                self.variables.push(VcdVariable {
+                    width: width,
+                    trackers: trackers,
+                    value_tracker: value_tracker,
+                    changes: Vec::new(),
+                });
+                self.variables.len() - 1
+            },
+        };
+
+        let variable = &self.variables[index];
+        let item = match variable.value_tracker {
+
+            Some(value_tracker) => {
+                let mut bit_items = Vec::new();
+
+                for (offset, tracker) in variable.trackers.iter().enumerate() {
+                    let bit_name = format!("{}[{}]", name, width - offset - 1);
+                    bit_items.push(InspectorItem::Label(Label::new(&bit_name, *tracker)));
+                }
+
+                InspectorItem::Group(Group::new(name, Some((value_tracker, Formatting::Binary)), bit_items))
+            },
+
+            None => InspectorItem::Label(Label::new(name, variable.trackers[0])),
+        };
+
+        self.scopes.last_mut().unwrap().1.push(item);
+        return Ok(());
+    }
+
+    fn add_change(&mut self, line: usize, identifier: &str, value: &str) -> Result<(), String> {
+        let time = self.timestamps.last().cloned().unwrap_or(0);
+
+        match self.identifiers.get(identifier) {
+            Some(index) => self.variables[*index].changes.push((time, String::from(value))),
+            None => return Err(format!("line {}: value change for undeclared variable {}", line, identifier)),
+        }
+
+        return Ok(());
+    }
+
+    fn sample_times(&self) -> Vec<u64> {
+
+        let end = self.timestamps.last().cloned().unwrap_or(0);
+        let changed_at_end = self.variables.iter().any(|variable| variable.changes.last().map(|(time, _value)| *time == end).unwrap_or(false));
+        let step = self.timestamps.iter().fold(0, |step, time| greatest_common_divisor(step, *time));
+
+        if step == 0 {
+            return match changed_at_end {
+                true => vec![0],
+                false => Vec::new(),
+            };
+        }
+
+        let count = end / step + changed_at_end as u64;
+        if count <= MAXIMUM_SAMPLES {
+            return (0..count).map(|sample| sample * step).collect();
+        }
+
+        // fall back to one sample per timestamp
+        let mut times = self.timestamps.clone();
+        times.dedup();
+        if !changed_at_end {
+            times.pop();
+        }
+        return times;
+    }
+
+    fn sample(&self) -> (Vec<LogicTracker>, Vec<ValueTracker>) {
+
+        let sample_times = self.sample_times();
+        let mut logic_states = vec![Vec::with_capacity(sample_times.len()); self.logic_tracker_count];
+        let mut value_trackers = Vec::new();
+
+        for variable in &self.variables {
+
+            let mut bits = vec![LogicState::Metastable; variable.width];
+            let mut changes = variable.changes.iter().peekable();
+            let mut values = Vec::new();
+
+            for time in &sample_times {
+                while let Some((_time, value)) = changes.next_if(|(change_time, _value)| change_time <= time) {
+                    bits = expand_value(value, variable.width);
+                }
+
+                for (offset, tracker) in variable.trackers.iter().enumerate() {
+                    logic_states[*tracker].push(bits[offset]);
+                }
+
+                if variable.value_tracker.is_some() {
+                    values.push(ValueState::from_logic_states(bits.iter().cloned()));
+                }
+            }
+
+            if variable.value_tracker.is_some() {
+                let mut value_tracker = ValueTracker::new(variable.trackers.clone());
+                value_tracker.states = values;
+                value_trackers.push(value_tracker);
+            }
+        }
+
+        let logic_trackers = logic_states.into_iter().map(LogicTracker::recorded).collect();
+        return (logic_trackers, value_trackers);
+    }
+}
+
+fn greatest_common_divisor(left: u64, right: u64) -> u64 {
+    match right {
+        0 => return left,
+        right => return greatest_common_divisor(right, left % right),
+    }
+}
+
+// vcd values are left extended with zeros, unless the first bit is unknown or floating
+fn expand_value(value: &str, width: usize) -> Vec<LogicState> {
+
+    let states: Vec<LogicState> = value.chars().map(character_state).collect();
+    let padding = match states.first() {
+        Some(LogicState::High) | None => LogicState::Low,
+        Some(state) => *state,
+    };
+
+    let mut expanded = vec![padding; width.saturating_sub(states.len())];
+    expanded.extend_from_slice(&states[states.len().saturating_sub(width)..]);
+    return expanded;
+}
+
+fn character_state(character: char) -> LogicState {
+    match character {
+        '1' | 'h' | 'H' => return LogicState::High,
+        '0' | 'l' | 'L' => return LogicState::Low,
+        'z' | 'Z' => return LogicState::Floating,
+        _other => return LogicState::Metastable,
+    }
+}
@@ -0,0 +1,138 @@
+use crate::device::core::{ LogicState, ValueState };
+
+const TIMESCALE: &str = "1ns";
+const HALF_CYCLE_TIME: usize = 5;
+const FIRST_IDENTIFIER: u8 = b'!';
+const IDENTIFIER_RANGE: usize = (b'~' - b'!' + 1) as usize;
+
+enum VcdSignal {
+    Logic(Vec<LogicState>),
+    Value(Vec<ValueState>),
+}
+
+pub struct VcdWriter {
+    declarations: String,
+    signals: Vec<(String, VcdSignal)>,
+}
+
+impl VcdWriter {
+
+    pub fn new() -> Self {
+        return Self {
+            declarations: String::new(),
+            signals: Vec::new(),
+        };
+    }
+
+    pub fn begin_scope(&mut self, name: &str) {
+        self.declarations.push_str(&format!("$scope module {} $end\n", Self::sanitize(name)));
+    }
+
+    pub fn end_scope(&mut self) {
+        self.declarations.push_str("$upscope $end\n");
+    }
+
+    pub fn add_logic(&mut self, name: &str, states: &[LogicState]) {
+        let identifier = self.next_identifier();
+        self.declarations.push_str(&format!("$var wire 1 {} {} $end\n", identifier, Self::sanitize(name)));
+        self.signals.push((identifier, VcdSignal::Logic(states.to_vec())));
+    }
+
+    pub fn add_value(&mut self, name: &str, width: usize, states: &[ValueState]) {
+        let identifier = self.next_identifier();
+        self.declarations.push_str(&format!("$var wire {} {} {} [{}:0] $end\n", width, identifier, Self::sanitize(name), width.saturating_sub(1)));
+        self.signals.push((identifier, VcdSignal::Value(states.to_vec())));
+    }
+
+    pub fn finish(self) -> String {
+
+        let mut output = String::from("$version mimicry emulator $end\n");
+        output.push_str(&format!("$timescale {} $end\n", TIMESCALE));
+        output.push_str(&self.declarations);
+        output.push_str("$enddefinitions $end\n");
+
+        let length = self.signals.iter().map(|(_identifier, signal)| Self::length(signal)).max().unwrap_or(0);
+
+        for tick in 0..length {
+            let mut changes = String::new();
+
+            for (identifier, signal) in &self.signals {
+                match signal {
+
+                    VcdSignal::Logic(states) => {
+                        if let Some(state) = states.get(tick) {
+                            if tick == 0 || states[tick - 1] != *state {
+                                changes.push_str(&format!("{}{}\n", Self::logic_text(state), identifier));
+                            }
+                        }
+                    },
+
+                    VcdSignal::Value(states) => {
+                        if let Some(state) = states.get(tick) {
+                            if tick == 0 || states[tick - 1] != *state {
+                                changes.push_str(&format!("b{} {}\n", Self::value_text(state), identifier));
+                            }
+                        }
+                    },
+                }
+            }
+
+            if tick == 0 {
+                output.push_str("#0\n$dumpvars\n");
+                output.push_str(&changes);
+                output.push_str("$end\n");
+            } else if !changes.is_empty() {
+                output.push_str(&format!("#{}\n", tick * HALF_CYCLE_TIME));
+                output.push_str(&changes);
+            }
+        }
+
+        output.push_str(&format!("#{}\n", length * HALF_CYCLE_TIME));
+        return output;
+    }
+
+    fn next_identifier(&self) -> String {
+        let mut index = self.signals.len();
+        let mut identifier = String::new();
+
+        loop {
+            identifier.push((FIRST_IDENTIFIER + (index % IDENTIFIER_RANGE) as u8) as char);
+            index /= IDENTIFIER_RANGE;
+
+            if index == 0 {
+                return identifier;
+            }
+
+            index -= 1;
+        }
+    }
+
+    fn length(signal: &VcdSignal) -> usize {
+        match signal {
+            VcdSignal::Logic(states) => return states.len(),
+            VcdSignal::Value(states) => return states.len(),
+        }
+    }
+
+    fn logic_text(state: &LogicState) -> char {
+        match state {
+            LogicState::High => return '1',
+            LogicState::Low => return '0',
+            LogicState::Floating => return 'z',
+            LogicState::Metastable => return 'x',
+        }
+    }
+
+    fn value_text(state: &ValueState) -> String {
+        match state {
+            ValueState::Stable(value) => return format!("{:b}", value),
+            ValueState::Floating => return String::from("z"),
+            ValueState::Metastable => return String::from("x"),
+        }
+    }
+
+    // whitespace is not allowed in names and most viewers use dots as the hierarchy separator
+    fn sanitize(name: &str) -> String {
+        return name.chars().map(|character| if character.is_whitespace() || character == '.' { '_' } else { character }).collect();
+    }
+}
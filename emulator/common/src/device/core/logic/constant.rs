@@ -1,8 +1,8 @@
-use super::{ LogicState, Output, Register, Gate };
+use super::{ LogicState, Output, Register, Gate, Port };
 
 pub struct Constant {
-    output: Output,
-    state: LogicState,
+    pub output: Output,
+    pub state: LogicState,
 }
 
 impl Constant {
@@ -14,7 +14,7 @@ impl Constant {
         };
     }
 
-    pub fn update(&self, registers: &mut Vec<Register>, gates: &mut Vec<Gate>, rising: bool) {
-        self.output.update(registers, gates, rising, self.state);
+    pub fn update(&self, registers: &mut Vec<Register>, gates: &mut Vec<Gate>, ports: &mut Vec<Port>, rising: bool) {
+        self.output.update(registers, gates, ports, rising, self.state);
     }
 }
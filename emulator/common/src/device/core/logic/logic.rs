@@ -1,6 +1,6 @@
 use super::Operator;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum LogicState {
     High,
     Low,
@@ -6,6 +6,7 @@ mod output;
 mod constant;
 mod gate;
 mod register;
+mod port;
 
 pub use self::logic::LogicState;
 pub use self::value::ValueState;
@@ -15,3 +16,4 @@ pub use self::output::Output;
 pub use self::constant::Constant;
 pub use self::gate::Gate;
 pub use self::register::Register;
+pub use self::port::Port;
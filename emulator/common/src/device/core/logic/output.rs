@@ -1,15 +1,17 @@
-use super::{ LogicState, Register, Gate };
+use super::{ LogicState, Register, Gate, Port };
 
 #[derive(Clone, Debug)]
 pub enum Output {
     Register(usize),
     Gate(usize, bool),
     Splitter(Box<Output>, Box<Output>),
+    Port(usize),
+    Disconnected,
 }
 
 impl Output {
 
-    pub fn update(&self, registers: &mut Vec<Register>, gates: &mut Vec<Gate>, rising: bool, state: LogicState) {
+    pub fn update(&self, registers: &mut Vec<Register>, gates: &mut Vec<Gate>, ports: &mut Vec<Port>, rising: bool, state: LogicState) {
         match self {
 
             Output::Register(index) => {
@@ -30,14 +32,18 @@ impl Output {
                 if gate.left_input.updated && gate.right_input.updated {
                     let state = gate.left_input.state.operator(gate.right_input.state, gate.operator);
                     gates[*index].state = state;
-                    gate.output.update(registers, gates, rising, state);
+                    gate.output.update(registers, gates, ports, rising, state);
                 }
             },
 
             Output::Splitter(left_output, right_output) => {
-                left_output.update(registers, gates, rising, state);
-                right_output.update(registers, gates, rising, state);
+                left_output.update(registers, gates, ports, rising, state);
+                right_output.update(registers, gates, ports, rising, state);
             },
+
+            Output::Port(index) => ports[*index].state = state,
+
+            Output::Disconnected => { },
         }
     }
 }
@@ -0,0 +1,38 @@
+use super::{ LogicState, Output, Register, Gate };
+
+// input ports drive the circuit like constants, output ports hold the last state that reached them
+#[derive(Clone, Debug)]
+pub struct Port {
+    pub name: String,
+    pub state: LogicState,
+    pub output: Option<Output>,
+}
+
+impl Port {
+
+    pub fn input(name: &str, state: LogicState, output: Output) -> Self {
+        return Self {
+            name: String::from(name),
+            state: state,
+            output: Some(output),
+        };
+    }
+
+    pub fn output(name: &str) -> Self {
+        return Self {
+            name: String::from(name),
+            state: LogicState::Floating,
+            output: None,
+        };
+    }
+
+    pub fn is_input(&self) -> bool {
+        return self.output.is_some();
+    }
+
+    pub fn update(&self, registers: &mut Vec<Register>, gates: &mut Vec<Gate>, ports: &mut Vec<Port>, rising: bool) {
+        if let Some(output) = &self.output {
+            output.update(registers, gates, ports, rising, self.state);
+        }
+    }
+}
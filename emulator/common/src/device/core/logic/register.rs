@@ -1,4 +1,4 @@
-use super::{ LogicState, Input, Output, Gate };
+use super::{ LogicState, Input, Output, Gate, Port };
 
 #[derive(Clone, Debug)]
 pub struct Register {
@@ -19,8 +19,8 @@ impl Register {
         };
     }
 
-    pub fn update(&self, registers: &mut Vec<Register>, gates: &mut Vec<Gate>, rising: bool) {
-        self.output.update(registers, gates, rising, self.state);
+    pub fn update(&self, registers: &mut Vec<Register>, gates: &mut Vec<Gate>, ports: &mut Vec<Port>, rising: bool) {
+        self.output.update(registers, gates, ports, rising, self.state);
     }
 
     pub fn reset(&mut self) {
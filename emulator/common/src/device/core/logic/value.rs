@@ -1,6 +1,34 @@
+use super::LogicState;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ValueState {
     Stable(u64),
     Metastable,
     Floating,
 }
+
+impl ValueState {
+
+    // combines individual bits into a value, starting with the most significant one
+    pub fn from_logic_states<T: Iterator<Item = LogicState>>(states: T) -> Self {
+        let mut value = 0;
+
+        for state in states {
+            match state {
+
+                LogicState::High => {
+                    value <<= 1;
+                    value |= 1;
+                },
+
+                LogicState::Low => value <<= 1,
+
+                LogicState::Metastable => return ValueState::Metastable,
+
+                LogicState::Floating => return ValueState::Floating,
+            }
+        }
+
+        return ValueState::Stable(value);
+    }
+}
@@ -1,129 +1,334 @@
 mod logic;
 mod inspector;
+mod signal;
+mod netlist;
+mod dot;
+mod optimize;
+mod activity;
+mod power;
+mod fuzz;
+mod builder;
+mod derived;
 
 use crate::types::*;
+use super::CoreSnapshot;
+use super::expression::Value;
+use std::path::Path;
+use std::fs::read_to_string;
 use self::logic::*;
-use self::inspector::*;
-
-const INTERFACE_BORDER: f32 = 10.0;
+use self::netlist::LoweredNetlist;
+use self::activity::Activity;
+use self::derived::DerivedSignal;
+
+pub use self::logic::{ LogicState, ValueState, Operator, Port };
+pub use self::inspector::{ Inspector, VcdWriter, LabelSource, Formatting };
+pub use self::signal::Signal;
+pub use self::optimize::{ Pass, PassReport };
+pub use self::activity::{ Toggles, ActivityReport, ActivitySnapshot };
+pub use self::power::{ Technology, PowerReport };
+pub use self::fuzz::{ fuzz, FuzzReport, Divergence };
+pub use self::builder::{ CoreBuilder, GateHandle, RegisterHandle, ConstantHandle, PortHandle, GroupHandle, Source, Pin };
+pub use self::netlist::{ Netlist, Net, PortDirection, NetlistGate, NetlistRegister, NetlistConstant, NetlistPort, NetlistSignal, EquivalenceReport, Comparison, Counterexample, Method, Delays, TimingPath, TimingReport, Stimulus, Fault, FaultSite, FaultReport, TruthTable, TableFormat };
+
+pub const INTERFACE_BORDER: f32 = 10.0;
 
 pub struct Core {
+    name: String,
     cycle_count: usize,
     gates: Vec<Gate>,
     registers: Vec<Register>,
     constants: Vec<Constant>,
+    ports: Vec<Port>,
+    signals: Vec<Signal>,
     inspector: Option<Inspector>,
+    derived: Vec<DerivedSignal>,
+    activity: Option<Activity>,
+    technology: Option<Technology>,
 }
 
 impl Core {
 
-    pub fn load(_filename: String, interface_size: FloatVector, debugging: bool) -> Self {
+    // the netlist format is picked by the file extension
+    pub fn load(filename: String, interface_size: FloatVector, debugging: bool) -> Result<Self, String> {
 
-        let mut gates = Vec::new();
-        let mut registers = Vec::new();
-        let mut constants = Vec::new();
-        let mut core_group = Vec::new();
+        // only the file name is kept so snapshots can be shared between machines
+        let name = Path::new(&filename).file_name().and_then(|name| name.to_str()).map(String::from).unwrap_or(filename.clone());
+        let extension = Path::new(&filename).extension().and_then(|extension| extension.to_str()).unwrap_or("");
 
-        let mut logic_trackers = Vec::new();
-        let mut value_trackers = Vec::new();
+        let netlist = match extension {
+            "json" => Netlist::from_yosys_json(&read_source(&filename)?),
+            "blif" => Netlist::from_blif(&read_source(&filename)?),
+            "v" => Netlist::from_verilog(&read_source(&filename)?),
+            _other => return Err(format!("{}: unsupported circuit format", filename)),
+        };
 
-        // TEMP
+        let netlist = netlist.map_err(|error| format!("{}: {}", filename, error))?;
+        return Self::from_netlist(name, &netlist, interface_size, debugging).map_err(|error| format!("{}: {}", filename, error));
+    }
 
-        logic_trackers.push(LogicTracker::new(LabelSource::Gate(0, false)));
-        logic_trackers.push(LogicTracker::new(LabelSource::Gate(1, false)));
-        logic_trackers.push(LogicTracker::new(LabelSource::Gate(2, false)));
-        logic_trackers.push(LogicTracker::new(LabelSource::Gate(0, true)));
-        logic_trackers.push(LogicTracker::new(LabelSource::Gate(1, true)));
-        logic_trackers.push(LogicTracker::new(LabelSource::Gate(2, true)));
+    pub fn from_netlist(name: String, netlist: &Netlist, interface_size: FloatVector, debugging: bool) -> Result<Self, String> {
+        let lowered = netlist.lower()?;
+        return Ok(Self::from_lowered(name, lowered, interface_size, debugging));
+    }
 
-        logic_trackers.push(LogicTracker::new(LabelSource::Register(6)));
-        logic_trackers.push(LogicTracker::new(LabelSource::Register(7)));
-        logic_trackers.push(LogicTracker::new(LabelSource::Register(8)));
+    fn from_lowered(name: String, lowered: LoweredNetlist, interface_size: FloatVector, debugging: bool) -> Self {
 
-        logic_trackers.push(LogicTracker::new(LabelSource::Register(0)));
-        logic_trackers.push(LogicTracker::new(LabelSource::Register(1)));
-        logic_trackers.push(LogicTracker::new(LabelSource::Register(2)));
+        let LoweredNetlist { gates, registers, constants, ports, root_item, logic_trackers, value_trackers } = lowered;
+        let signals = Inspector::collect_signals(&root_item, &logic_trackers, &value_trackers);
+        let inspector = debugging.then(|| Inspector::new(interface_size, logic_trackers, value_trackers, root_item));
 
-        logic_trackers.push(LogicTracker::new(LabelSource::Register(3)));
-        logic_trackers.push(LogicTracker::new(LabelSource::Register(4)));
-        logic_trackers.push(LogicTracker::new(LabelSource::Register(5)));
+        return Self {
+            name: name,
+            cycle_count: 0,
+            gates: gates,
+            registers: registers,
+            constants: constants,
+            ports: ports,
+            signals: signals,
+            inspector: inspector,
+            derived: Vec::new(),
+            activity: None,
+            technology: None,
+        };
+    }
 
-        value_trackers.push(ValueTracker::new(vec![2, 1, 0]));
-        value_trackers.push(ValueTracker::new(vec![5, 4, 3]));
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
 
-        let mut input0_group = Vec::new();
-        input0_group.push(InspectorItem::Label(Label::new("register 0", 0)));
-        input0_group.push(InspectorItem::Label(Label::new("register 1", 1)));
-        input0_group.push(InspectorItem::Label(Label::new("register 2", 2)));
-        core_group.push(InspectorItem::Group(Group::new("input 0", Some((0, Formatting::Binary)), input0_group)));
+    // cores loaded as part of a device are named in the device description
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
 
-        let mut input1_group = Vec::new();
-        input1_group.push(InspectorItem::Label(Label::new("register 0", 3)));
-        input1_group.push(InspectorItem::Label(Label::new("register 1", 4)));
-        input1_group.push(InspectorItem::Label(Label::new("register 2", 5)));
-        core_group.push(InspectorItem::Group(Group::new("input 1", Some((1, Formatting::Binary)), input1_group)));
+    pub fn cycle_count(&self) -> usize {
+        return self.cycle_count;
+    }
 
-        let mut gates_group = Vec::new();
-        gates_group.push(InspectorItem::Label(Label::new("AND gate", 6)));
-        gates_group.push(InspectorItem::Label(Label::new("XOR gate", 7)));
-        gates_group.push(InspectorItem::Label(Label::new("XOR gate", 8)));
-        core_group.push(InspectorItem::Group(Group::new("gates", None, gates_group)));
+    pub fn signals(&self) -> &Vec<Signal> {
+        return &self.signals;
+    }
 
-        let mut buffer_group = Vec::new();
-        buffer_group.push(InspectorItem::Label(Label::new("register 0", 9)));
-        buffer_group.push(InspectorItem::Label(Label::new( "register 1", 1)));
-        buffer_group.push(InspectorItem::Label(Label::new( "register 2", 1)));
-        core_group.push(InspectorItem::Group(Group::new("buffer", None, buffer_group)));
+    // the module is named after the file the core was loaded from
+    pub fn netlist(&self) -> Netlist {
+        let name = Path::new(&self.name).file_stem().and_then(|name| name.to_str()).unwrap_or(&self.name);
+        return netlist::extract(name, &self.gates, &self.registers, &self.constants, &self.ports, &self.signals);
+    }
 
-        let mut gates_group = Vec::new();
-        gates_group.push(InspectorItem::Label(Label::new( "output 0", 1)));
-        gates_group.push(InspectorItem::Label(Label::new( "output 1", 1)));
-        gates_group.push(InspectorItem::Label(Label::new( "output 2", 1)));
-        core_group.push(InspectorItem::Group(Group::new("inverter", None, gates_group)));
+    pub fn check_equivalence(&self, other: &Core) -> Result<EquivalenceReport, String> {
+        return self.netlist().check_equivalence(&other.netlist());
+    }
 
-        let root_item = InspectorItem::Group(Group::new("core", None, core_group));
+    pub fn timing(&self, delays: &Delays, clock_period: Option<f64>) -> Result<TimingReport, String> {
+        return self.netlist().timing(delays, clock_period);
+    }
 
-        registers.push(Register::new(Input::new(LogicState::High), Output::Gate(0, false), true));
-        registers.push(Register::new(Input::new(LogicState::Low), Output::Gate(1, false), true));
-        registers.push(Register::new(Input::new(LogicState::High), Output::Gate(2, false), true));
+    // runs every stuck at fault of the core against the stimulus
+    pub fn simulate_faults(&self, stimulus: &Stimulus) -> Result<FaultReport, String> {
+        let netlist = self.netlist();
+        return netlist.simulate_faults(netlist.faults(), stimulus);
+    }
 
-        registers.push(Register::new(Input::new(LogicState::High), Output::Gate(0, true), true));
-        registers.push(Register::new(Input::new(LogicState::High), Output::Gate(1, true), true));
-        registers.push(Register::new(Input::new(LogicState::High), Output::Gate(2, true), true));
+    // inputs and outputs are named relative to the core, no selection means all input or output ports
+    pub fn truth_table(&self, inputs: &[String], outputs: &[String], unknown: bool) -> Result<TruthTable, String> {
+        return self.netlist().truth_table(inputs, outputs, unknown);
+    }
 
-        gates.push(Gate::new(Operator::And, Output::Register(6)));
-        gates.push(Gate::new(Operator::Xor, Output::Register(7)));
-        gates.push(Gate::new(Operator::Xor, Output::Register(8)));
+    // signals can be referred to by their full path or by any unique trailing part of it
+    pub fn find_signal(&self, name: &str) -> Result<usize, String> {
 
-        let splitter0 = Output::Splitter(Box::new(Output::Register(0)), Box::new(Output::Gate(3, false)));
-        let splitter1 = Output::Splitter(Box::new(Output::Register(1)), Box::new(Output::Gate(4, false)));
-        let splitter2 = Output::Splitter(Box::new(Output::Register(2)), Box::new(Output::Gate(5, false)));
+        if let Some(index) = self.signals.iter().position(|signal| signal.name == name) {
+            return Ok(index);
+        }
 
-        registers.push(Register::new(Input::new(LogicState::Floating), splitter0, false));
-        registers.push(Register::new(Input::new(LogicState::Floating), splitter1, false));
-        registers.push(Register::new(Input::new(LogicState::Floating), splitter2, false));
+        let suffix = format!("/{}", name);
+        let matches: Vec<usize> = (0..self.signals.len()).filter(|index| self.signals[*index].name.ends_with(&suffix)).collect();
 
-        constants.push(Constant::new(LogicState::High, Output::Gate(3, true)));
-        constants.push(Constant::new(LogicState::High, Output::Gate(4, true)));
-        constants.push(Constant::new(LogicState::High, Output::Gate(5, true)));
+        match matches.len() {
+            0 => return Err(format!("{} has no signal named {}", self.name, name)),
+            1 => return Ok(matches[0]),
+            _other => return Err(format!("signal name {} is ambiguous in {}", name, self.name)),
+        }
+    }
 
-        gates.push(Gate::new(Operator::Or, Output::Register(3)));
-        gates.push(Gate::new(Operator::Or, Output::Register(4)));
-        gates.push(Gate::new(Operator::Or, Output::Register(5)));
+    pub fn ports(&self) -> &Vec<Port> {
+        return &self.ports;
+    }
 
-        // TEMP
+    pub fn find_port(&self, name: &str) -> Result<usize, String> {
+        return self.ports.iter().position(|port| port.name == name).ok_or(format!("{} has no port named {}", self.name, name));
+    }
 
-        let inspector = debugging.then(|| Inspector::new(interface_size, logic_trackers, value_trackers, root_item));
+    pub fn port_state(&self, index: usize) -> LogicState {
+        return self.ports[index].state;
+    }
 
-        return Self {
-            cycle_count: 0,
-            gates: gates,
-            registers: registers,
-            constants: constants,
-            inspector: inspector,
+    // the state of an input port is pushed into the circuit on the next tick
+    pub fn set_port_state(&mut self, index: usize, state: LogicState) {
+        self.ports[index].state = state;
+    }
+
+    pub fn signal_state(&self, index: usize) -> ValueState {
+        return self.signals[index].value_state(&self.registers, &self.gates, &self.ports);
+    }
+
+    // single bit signals are logic states and buses are numbers
+    pub fn signal_value(&self, index: usize) -> Value {
+        return Value::from_signal(self.signal_state(index), self.signals[index].is_bus());
+    }
+
+    pub fn metastable_sources(&self) -> Vec<LabelSource> {
+        let mut sources = Vec::new();
+
+        for index in 0..self.registers.len() {
+            if self.registers[index].input.state.is_metastable() {
+                sources.push(LabelSource::Register(index));
+            }
+        }
+
+        for index in 0..self.gates.len() {
+            if self.gates[index].left_input.state.is_metastable() {
+                sources.push(LabelSource::Gate(index, false));
+            }
+
+            if self.gates[index].right_input.state.is_metastable() {
+                sources.push(LabelSource::Gate(index, true));
+            }
+        }
+
+        return sources;
+    }
+
+    pub fn describe_source(&self, source: LabelSource) -> String {
+        match self.signals.iter().find(|signal| signal.sources == [source]) {
+            Some(signal) => return signal.name.clone(),
+            None => return source.describe(),
+        }
+    }
+
+    pub fn states(&self) -> Option<Vec<(String, String)>> {
+        return self.inspector.as_ref().map(|inspector| inspector.states());
+    }
+
+    pub fn trace(&self) -> Option<String> {
+        return self.inspector.as_ref().map(|inspector| inspector.trace());
+    }
+
+    pub fn track_all(&mut self) {
+        if let Some(inspector) = &mut self.inspector {
+            inspector.track_all(&self.registers, &self.gates);
+        }
+    }
+
+    pub fn write_vcd(&self, writer: &mut VcdWriter) {
+        if let Some(inspector) = &self.inspector {
+            writer.begin_scope(&self.name);
+            inspector.write_vcd(writer);
+            writer.end_scope();
+        }
+    }
+
+    pub fn snapshot(&self) -> CoreSnapshot {
+        let mut snapshot = self.state();
+
+        if let Some(inspector) = &self.inspector {
+            let (logic_trackers, value_trackers) = inspector.tracker_states();
+            snapshot.logic_trackers = logic_trackers;
+            snapshot.value_trackers = value_trackers;
+        }
+
+        return snapshot;
+    }
+
+    // same as a snapshot but without any tracker history
+    pub fn state(&self) -> CoreSnapshot {
+        return CoreSnapshot {
+            name: self.name.clone(),
+            cycle_count: self.cycle_count,
+            registers: self.registers.iter().map(|register| (register.input.state, register.state)).collect(),
+            gates: self.gates.iter().map(|gate| (gate.left_input.state, gate.right_input.state, gate.state)).collect(),
+            ports: self.ports.iter().map(|port| port.state).collect(),
+            logic_trackers: Vec::new(),
+            value_trackers: Vec::new(),
+            activity: self.activity.as_ref().map(|activity| activity.snapshot()),
         };
     }
 
+    pub fn samples(&self) -> usize {
+        return self.inspector.as_ref().map(|inspector| inspector.samples()).unwrap_or(0);
+    }
+
+    pub fn validate_snapshot(&self, snapshot: &CoreSnapshot) -> Result<(), String> {
+
+        if snapshot.registers.len() != self.registers.len() || snapshot.gates.len() != self.gates.len() || snapshot.ports.len() != self.ports.len() {
+            return Err(format!("snapshot of {} does not match the loaded circuit", snapshot.name));
+        }
+
+        if let Some(inspector) = &self.inspector {
+            inspector.validate_tracker_states(&snapshot.logic_trackers, &snapshot.value_trackers)?;
+        }
+
+        return Ok(());
+    }
+
+    pub fn restore(&mut self, snapshot: &CoreSnapshot) -> Result<(), String> {
+
+        self.validate_snapshot(snapshot)?;
+        self.restore_logic(snapshot);
+
+        if let Some(inspector) = &mut self.inspector {
+            inspector.restore_tracker_states(&snapshot.logic_trackers, &snapshot.value_trackers);
+        }
+
+        // snapshot files carry no statistics, so they start over from the restored state
+        if let Some(activity) = &mut self.activity {
+            activity.restore(None);
+        }
+
+        self.synchronize_derived();
+        return Ok(());
+    }
+
+    // restores the logic state and lets the inspector show only the first samples
+    pub fn restore_state(&mut self, state: &CoreSnapshot, samples: usize) {
+        self.restore_logic(state);
+
+        if let Some(inspector) = &mut self.inspector {
+            inspector.set_cursor(samples);
+        }
+
+        if let Some(activity) = &mut self.activity {
+            activity.restore(state.activity.as_ref());
+        }
+
+        self.synchronize_derived();
+    }
+
+    pub fn discard_future(&mut self) {
+        if let Some(inspector) = &mut self.inspector {
+            inspector.discard_future();
+        }
+    }
+
+    fn restore_logic(&mut self, snapshot: &CoreSnapshot) {
+        self.cycle_count = snapshot.cycle_count;
+
+        for (register, (input, state)) in self.registers.iter_mut().zip(snapshot.registers.iter()) {
+            register.input = Input::new(*input);
+            register.state = *state;
+        }
+
+        for (gate, (left_input, right_input, state)) in self.gates.iter_mut().zip(snapshot.gates.iter()) {
+            gate.left_input = Input::new(*left_input);
+            gate.right_input = Input::new(*right_input);
+            gate.state = *state;
+        }
+
+        for (port, state) in self.ports.iter_mut().zip(snapshot.ports.iter()) {
+            port.state = *state;
+        }
+    }
+
     pub fn handle_key_input(&mut self, key: Key) {
         if let Some(inspector) = &mut self.inspector {
             inspector.handle_key_input(key);
@@ -142,20 +347,37 @@ impl Core {
         self.gates.iter_mut().for_each(|gate| gate.reset());
 
         for index in 0..self.constants.len() {
-            self.constants[index].update(&mut self.registers, &mut self.gates, rising);
+            self.constants[index].update(&mut self.registers, &mut self.gates, &mut self.ports, rising);
+        }
+
+        // input ports are driven on the rising edge, so a register on the rising edge reading one directly turns metastable,
+        // only master slave flip flops whose first register is on the falling edge can sample them safely
+        if rising {
+            for index in 0..self.ports.len() {
+                let port = self.ports[index].clone();
+                port.update(&mut self.registers, &mut self.gates, &mut self.ports, rising);
+            }
         }
 
         for index in 0..self.registers.len() {
             if self.registers[index].rising == rising {
                 let register = self.registers[index].clone();
-                register.update(&mut self.registers, &mut self.gates, rising);
+                register.update(&mut self.registers, &mut self.gates, &mut self.ports, rising);
             }
         }
 
         if let Some(inspector) = &mut self.inspector {
-            inspector.update(&self.registers, &self.gates);
+            inspector.update(&self.registers, &self.gates, &self.ports);
+        }
+
+        self.sample_derived();
+
+        if let Some(activity) = &mut self.activity {
+            activity.sample(rising, &self.registers, &self.gates);
         }
 
+        self.sample_power();
+
         if rising {
             self.cycle_count += 1;
         }
@@ -167,3 +389,7 @@ impl Core {
         }
     }
 }
+
+fn read_source(filename: &str) -> Result<String, String> {
+    return read_to_string(filename).map_err(|error| format!("failed to read {}: {}", filename, error));
+}
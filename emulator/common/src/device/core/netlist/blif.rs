@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use super::super::{ LogicState, Operator };
+use super::{ Netlist, Net, PortDirection };
+
+// a logical line of the file, continued lines are already joined
+struct BlifLine {
+    number: usize,
+    words: Vec<String>,
+}
+
+struct Cover {
+    line: usize,
+    inputs: Vec<String>,
+    output: String,
+    rows: Vec<(String, char)>,
+}
+
+struct Latch {
+    line: usize,
+    input: String,
+    output: String,
+    rising: bool,
+    initial: LogicState,
+}
+
+struct BlifImporter {
+    netlist: Netlist,
+    nets: HashMap<String, Net>,
+    inverted: HashMap<Net, Net>,
+}
+
+fn lines(source: &str) -> Vec<BlifLine> {
+
+    let mut lines = Vec::new();
+    let mut pending: Option<BlifLine> = None;
+
+    for (index, line) in source.lines().enumerate() {
+
+        let line = line.split('#').next().unwrap();
+        let (line, continued) = match line.trim_end().strip_suffix('\\') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+
+        let mut current = pending.take().unwrap_or(BlifLine {
+            number: index + 1,
+            words: Vec::new(),
+        });
+
+        current.words.extend(line.split_whitespace().map(String::from));
+
+        if continued {
+            pending = Some(current);
+        } else if !current.words.is_empty() {
+            lines.push(current);
+        }
+    }
+
+    lines.extend(pending);
+    return lines;
+}
+
+fn parse_latch(line: &BlifLine) -> Result<(Latch, Option<String>), String> {
+
+    let words = &line.words[1..];
+    let (input, output) = match words {
+        [input, output, ..] => (input.clone(), output.clone()),
+        _other => return Err(format!("line {}: .latch needs an input and an output", line.number)),
+    };
+
+    let (kind, control, initial) = match &words[2..] {
+        [] => (None, None, None),
+        [initial] => (None, None, Some(initial)),
+        [kind, control] => (Some(kind), Some(control), None),
+        [kind, control, initial] => (Some(kind), Some(control), Some(initial)),
+        _other => return Err(format!("line {}: too many arguments for .latch", line.number)),
+    };
+
+    // the emulator only has edge triggered storage, latches without a type are assumed to be clocked on the rising edge
+    let rising = match kind.map(|kind| kind.as_str()) {
+        None | Some("re") => true,
+        Some("fe") => false,
+        Some(other) => return Err(format!("line {}: latch type {} is not supported, only re and fe are", line.number, other)),
+    };
+
+    // unspecified and don't care initial values start low
+    let initial = match initial.map(|initial| initial.as_str()) {
+        None | Some("0") | Some("2") => LogicState::Low,
+        Some("1") => LogicState::High,
+        Some("3") => LogicState::Metastable,
+        Some(other) => return Err(format!("line {}: invalid initial value {}", line.number, other)),
+    };
+
+    let control = control.filter(|control| control.as_str() != "NIL").cloned();
+    let latch = Latch {
+        line: line.number,
+        input: input,
+        output: output,
+        rising: rising,
+        initial: initial,
+    };
+
+    return Ok((latch, control));
+}
+
+pub fn import(source: &str) -> Result<Netlist, String> {
+
+    let lines = lines(source);
+    let mut name = None;
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut covers: Vec<Cover> = Vec::new();
+    let mut latches = Vec::new();
+    let mut clock: Option<String> = None;
+    // rows only belong to a .names that directly precedes them
+    let mut in_cover = false;
+
+    for line in &lines {
+
+        let keyword = line.words[0].as_str();
+
+        // rows of the most recent cover
+        if !keyword.starts_with('.') {
+            let cover = covers.last_mut().filter(|_cover| in_cover).ok_or(format!("line {}: cover row outside of .names", line.number))?;
+
+            let (plane, output) = match line.words.as_slice() {
+                [output] if cover.inputs.is_empty() => (String::new(), output.as_str()),
+                [plane, output] => (plane.clone(), output.as_str()),
+                _other => return Err(format!("line {}: malformed cover row", line.number)),
+            };
+
+            if plane.len() != cover.inputs.len() || !plane.chars().all(|character| "01-".contains(character)) {
+                return Err(format!("line {}: cover row {} does not match the {} inputs of {}", line.number, plane, cover.inputs.len(), cover.output));
+            }
+
+            let output = match output {
+                "1" => '1',
+                "0" => '0',
+                other => return Err(format!("line {}: invalid cover output {}", line.number, other)),
+            };
+
+            if cover.rows.first().map(|(_plane, first)| *first != output).unwrap_or(false) {
+                return Err(format!("line {}: cover of {} mixes on-set and off-set rows", line.number, cover.output));
+            }
+
+            cover.rows.push((plane, output));
+            continue;
+        }
+
+        in_cover = keyword == ".names";
+
+        match keyword {
+
+            ".model" => {
+                if name.is_some() {
+                    return Err(format!("line {}: files with multiple models are not supported", line.number));
+                }
+                name = Some(line.words.get(1).cloned().unwrap_or_else(|| String::from("model")));
+            },
+
+            ".inputs" => inputs.extend(line.words[1..].iter().cloned()),
+
+            ".outputs" => outputs.extend(line.words[1..].iter().cloned()),
+
+            ".names" => {
+                let (output, inputs) = line.words[1..].split_last().ok_or(format!("line {}: .names needs an output", line.number))?;
+                covers.push(Cover {
+                    line: line.number,
+                    inputs: inputs.to_vec(),
+                    output: output.clone(),
+                    rows: Vec::new(),
+                });
+            },
+
+            ".latch" => {
+                let (latch, control) = parse_latch(line)?;
+
+                if let Some(control) = control {
+                    match &clock {
+                        Some(clock) if *clock != control => return Err(format!("line {}: design uses more than one clock", line.number)),
+                        _other => clock = Some(control),
+                    }
+                }
+
+                latches.push(latch);
+            },
+
+            ".end" => break,
+
+            ".clock" | ".default_input_arrival" | ".default_output_required" | ".area" | ".delay" | ".wire_load_slope" => { },
+
+            other => return Err(format!("line {}: {} is not supported", line.number, other)),
+        }
+    }
+
+    let mut importer = BlifImporter {
+        netlist: Netlist::new(&name.unwrap_or_else(|| String::from("model"))),
+        nets: HashMap::new(),
+        inverted: HashMap::new(),
+    };
+
+    importer.build(&inputs, &outputs, &covers, &latches, clock)?;
+    return Ok(importer.netlist);
+}
+
+impl BlifImporter {
+
+    fn net(&mut self, name: &str) -> Net {
+
+        if let Some(net) = self.nets.get(name) {
+            return *net;
+        }
+
+        let net = self.netlist.add_net();
+        self.nets.insert(String::from(name), net);
+        return net;
+    }
+
+    // inverted inputs are shared between all covers
+    fn inverted_net(&mut self, net: Net) -> Net {
+
+        if let Some(inverted) = self.inverted.get(&net) {
+            return *inverted;
+        }
+
+        let inverted = self.netlist.add_not(net);
+        self.inverted.insert(net, inverted);
+        return inverted;
+    }
+
+    // the cover is built as a sum of products, covers that list the off-set are inverted at the end
+    fn build_cover(&mut self, cover: &Cover) -> Result<(), String> {
+
+        let output = self.net(&cover.output);
+        let on_set = cover.rows.first().map(|(_plane, output)| *output == '1').unwrap_or(true);
+
+        // a row without literals makes the whole cover constant
+        if cover.rows.is_empty() || cover.rows.iter().any(|(plane, _output)| plane.chars().all(|character| character == '-')) {
+            let state = LogicState::from_boolean(!cover.rows.is_empty() == on_set);
+            let constant = self.netlist.add_constant(state);
+            self.netlist.add_buffer(constant, output);
+            return Ok(());
+        }
+
+        let inputs: Vec<Net> = cover.inputs.iter().map(|input| self.net(input)).collect();
+        let sum_output = match on_set {
+            true => Some(output),
+            false => None,
+        };
+
+        let mut terms = Vec::new();
+        for (plane, _output) in &cover.rows {
+
+            let mut literals = Vec::new();
+            for (character, input) in plane.chars().zip(inputs.iter()) {
+                match character {
+                    '1' => literals.push(*input),
+                    '0' => literals.push(self.inverted_net(*input)),
+                    _other => { },
+                }
+            }
+
+            // with a single row, the product itself drives the output
+            match cover.rows.len() {
+                1 => terms.push(self.netlist.add_tree(Operator::And, &literals, sum_output)?),
+                _other => terms.push(self.netlist.add_tree(Operator::And, &literals, None)?),
+            }
+        }
+
+        let sum = match terms.len() {
+            1 => terms[0],
+            _other => self.netlist.add_tree(Operator::Or, &terms, sum_output)?,
+        };
+
+        if !on_set {
+            self.netlist.add_not_to(sum, output);
+        }
+
+        return Ok(());
+    }
+
+    fn build(&mut self, inputs: &[String], outputs: &[String], covers: &[Cover], latches: &[Latch], clock: Option<String>) -> Result<(), String> {
+
+        // counted once up front, rescanning for every cover is too slow for large benchmarks
+        let mut drivers: HashMap<&str, usize> = HashMap::new();
+        for output in covers.iter().map(|cover| &cover.output).chain(latches.iter().map(|latch| &latch.output)) {
+            *drivers.entry(output.as_str()).or_insert(0) += 1;
+        }
+
+        for cover in covers {
+            if drivers[cover.output.as_str()] > 1 {
+                return Err(format!("line {}: {} has multiple drivers", cover.line, cover.output));
+            }
+            self.build_cover(cover)?;
+        }
+
+        for latch in latches {
+            let input = self.net(&latch.input);
+            let output = self.net(&latch.output);
+            self.netlist.add_flip_flop(latch.rising, latch.initial, input, output);
+        }
+
+        // the clock is provided by the emulator, so it can not be used as data
+        if let Some(clock) = &clock {
+            if self.nets.contains_key(clock) {
+                let line = latches.first().map(|latch| latch.line).unwrap_or(0);
+                return Err(format!("line {}: the clock {} is used as data, which the global clock of the emulator can not model", line, clock));
+            }
+        }
+
+        for input in inputs {
+            if Some(input) != clock.as_ref() {
+                let net = self.net(input);
+                self.netlist.add_port(input, PortDirection::Input, net);
+                self.netlist.add_signal(input, vec![net]);
+            }
+        }
+
+        for output in outputs {
+            let net = self.net(output);
+            self.netlist.add_port(output, PortDirection::Output, net);
+            self.netlist.add_signal(output, vec![net]);
+        }
+
+        for latch in latches {
+            if !outputs.contains(&latch.output) {
+                let net = self.net(&latch.output);
+                self.netlist.add_signal(&latch.output, vec![net]);
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::super::LogicState;
+    use super::super::Netlist;
+
+    fn outputs(netlist: &Netlist, inputs: &[&str], output: &str) -> Vec<LogicState> {
+        let inputs: Vec<String> = inputs.iter().map(|input| String::from(*input)).collect();
+        let table = netlist.truth_table(&inputs, &[String::from(output)], false).unwrap();
+        return table.rows.iter().map(|(_inputs, outputs)| outputs[0]).collect();
+    }
+
+    #[test]
+    fn off_set_rows_are_inverted() {
+
+        // y lists the off-set of a nand, z the on-set of an exclusive or, c is constant high
+        let source = "
+            .model covers
+            .inputs a b
+            .outputs y z c
+            .names a b y
+            11 0
+            .names a b z
+            01 1
+            10 1
+            .names c
+            1
+            .end
+        ";
+
+        let netlist = Netlist::from_blif(source).unwrap();
+        let (low, high) = (LogicState::Low, LogicState::High);
+        assert_eq!(outputs(&netlist, &["a", "b"], "y"), vec![high, high, high, low]);
+        assert_eq!(outputs(&netlist, &["a", "b"], "z"), vec![low, high, high, low]);
+        assert_eq!(outputs(&netlist, &["a", "b"], "c"), vec![high; 4]);
+    }
+
+    #[test]
+    fn rows_after_other_directives_are_rejected() {
+
+        let source = "
+            .model stray
+            .inputs a b
+            .outputs y
+            .names a b y
+            11 1
+            .latch y q re clk 0
+            01 1
+            .end
+        ";
+
+        assert_eq!(Netlist::from_blif(source).unwrap_err(), "line 8: cover row outside of .names");
+
+        let source = ".model mixed\n.inputs a\n.outputs y\n.names a y\n1 1\n0 0\n";
+        assert_eq!(Netlist::from_blif(source).unwrap_err(), "line 6: cover of y mixes on-set and off-set rows");
+    }
+}
@@ -0,0 +1,599 @@
+use super::{ Netlist, Net, Driver, Sink, PortDirection };
+use super::sat::{ Solver, Literal, positive, negative };
+use super::super::{ LogicState, Operator };
+use std::collections::HashMap;
+use std::fmt;
+
+// cones with at most this many inputs are compared on every possible assignment
+const EXHAUSTIVE_LIMIT: usize = 16;
+
+// bit i of pattern p is bit p of i, so one word covers all assignments of the first six inputs
+const PATTERNS: [u64; 6] = [0xAAAA_AAAA_AAAA_AAAA, 0xCCCC_CCCC_CCCC_CCCC, 0xF0F0_F0F0_F0F0_F0F0, 0xFF00_FF00_FF00_FF00, 0xFFFF_0000_FFFF_0000, 0xFFFF_FFFF_0000_0000];
+
+const FALSE: usize = 0;
+const TRUE: usize = 1;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Method {
+    Structural,
+    Exhaustive,
+    Satisfiability,
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Method::Structural => return write!(f, "structure"),
+            Method::Exhaustive => return write!(f, "exhaustive"),
+            Method::Satisfiability => return write!(f, "sat"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Counterexample {
+    pub assignment: Vec<(String, bool)>,
+    pub left: bool,
+    pub right: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    pub name: String,
+    pub method: Method,
+    pub counterexample: Option<Counterexample>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EquivalenceReport {
+    pub left: String,
+    pub right: String,
+    pub comparisons: Vec<Comparison>,
+}
+
+impl EquivalenceReport {
+
+    pub fn is_equivalent(&self) -> bool {
+        return self.comparisons.iter().all(|comparison| comparison.counterexample.is_none());
+    }
+}
+
+impl fmt::Display for EquivalenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        let differences = self.comparisons.iter().filter(|comparison| comparison.counterexample.is_some()).count();
+        match differences {
+            0 => write!(f, "{} and {} are equivalent on all {} outputs", self.left, self.right, self.comparisons.len())?,
+            count => write!(f, "{} and {} differ on {} of {} outputs", self.left, self.right, count, self.comparisons.len())?,
+        }
+
+        for comparison in &self.comparisons {
+            match &comparison.counterexample {
+                None => write!(f, "\n  {}: equal ({})", comparison.name, comparison.method)?,
+                Some(counterexample) => {
+                    let assignment: Vec<String> = counterexample.assignment.iter().map(|(name, value)| format!("{}={}", name, *value as u8)).collect();
+                    write!(f, "\n  {}: differs ({}), {} gives {} and {} gives {}", comparison.name, comparison.method, self.left, counterexample.left as u8, self.right, counterexample.right as u8)?;
+                    match assignment.is_empty() {
+                        true => write!(f, " without any inputs")?,
+                        false => write!(f, " for {}", assignment.join(" "))?,
+                    }
+                },
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+// the children of a node always have smaller indices, so the node list is in topological order
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Node {
+    Constant(bool),
+    Variable(usize),
+    And(usize, usize),
+    Or(usize, usize),
+    Xor(usize, usize),
+}
+
+// a hash consed graph shared by both netlists, so identical logic ends up in the same node
+struct Graph {
+    nodes: Vec<Node>,
+    table: HashMap<Node, usize>,
+    variables: Vec<String>,
+    variable_table: HashMap<String, usize>,
+}
+
+impl Graph {
+
+    fn new() -> Self {
+        let mut graph = Self {
+            nodes: Vec::new(),
+            table: HashMap::new(),
+            variables: Vec::new(),
+            variable_table: HashMap::new(),
+        };
+        graph.insert(Node::Constant(false));
+        graph.insert(Node::Constant(true));
+        return graph;
+    }
+
+    fn insert(&mut self, node: Node) -> usize {
+        if let Some(index) = self.table.get(&node) {
+            return *index;
+        }
+
+        self.nodes.push(node);
+        self.table.insert(node, self.nodes.len() - 1);
+        return self.nodes.len() - 1;
+    }
+
+    // the key keeps ports and registers of the same name apart
+    fn variable(&mut self, key: String, name: &str) -> usize {
+        let variable = match self.variable_table.get(&key) {
+            Some(variable) => *variable,
+            None => {
+                self.variables.push(name.to_string());
+                self.variable_table.insert(key, self.variables.len() - 1);
+                self.variables.len() - 1
+            },
+        };
+        return self.insert(Node::Variable(variable));
+    }
+
+    // floating and metastable values are treated as unknown but equal in both netlists
+    fn constant(&mut self, state: LogicState) -> usize {
+        match state {
+            LogicState::Low => return FALSE,
+            LogicState::High => return TRUE,
+            LogicState::Floating => return self.variable(String::from("state floating"), "floating"),
+            LogicState::Metastable => return self.variable(String::from("state metastable"), "metastable"),
+        }
+    }
+
+    fn gate(&mut self, operator: Operator, left: usize, right: usize) -> usize {
+
+        // constants have the smallest indices, so after sorting they are always on the left
+        let (left, right) = (left.min(right), left.max(right));
+
+        match (operator, left, right) {
+            (Operator::Xor, left, right) if left == right => return FALSE,
+            (_, left, right) if left == right => return left,
+            (Operator::And, FALSE, _) => return FALSE,
+            (Operator::And, TRUE, right) => return right,
+            (Operator::Or, FALSE, right) => return right,
+            (Operator::Or, TRUE, _) => return TRUE,
+            (Operator::Xor, FALSE, right) => return right,
+            (Operator::And, left, right) => return self.insert(Node::And(left, right)),
+            (Operator::Or, left, right) => return self.insert(Node::Or(left, right)),
+            (Operator::Xor, left, right) => return self.insert(Node::Xor(left, right)),
+        }
+    }
+
+    // all nodes the roots depend on, in topological order
+    fn cone(&self, roots: &[usize]) -> Vec<usize> {
+
+        let mut visited = vec![false; self.nodes.len()];
+        let mut pending = roots.to_vec();
+
+        while let Some(node) = pending.pop() {
+            if visited[node] {
+                continue;
+            }
+
+            visited[node] = true;
+            match self.nodes[node] {
+                Node::And(left, right) | Node::Or(left, right) | Node::Xor(left, right) => pending.extend_from_slice(&[left, right]),
+                Node::Constant(_) | Node::Variable(_) => (),
+            }
+        }
+
+        return (0..self.nodes.len()).filter(|node| visited[*node]).collect();
+    }
+
+    fn evaluate(&self, cone: &[usize], values: &mut [u64], inputs: &dyn Fn(usize) -> u64) {
+        for node in cone {
+            values[*node] = match self.nodes[*node] {
+                Node::Constant(false) => 0,
+                Node::Constant(true) => !0,
+                Node::Variable(_) => inputs(*node),
+                Node::And(left, right) => values[left] & values[right],
+                Node::Or(left, right) => values[left] | values[right],
+                Node::Xor(left, right) => values[left] ^ values[right],
+            };
+        }
+    }
+
+    // returns an assignment of the support that makes the two nodes differ
+    fn exhaustive(&self, cone: &[usize], support: &[usize], left: usize, right: usize) -> Option<Vec<bool>> {
+
+        let count = 1usize << support.len();
+        let mask = match count < 64 {
+            true => (1u64 << count) - 1,
+            false => !0,
+        };
+
+        let mut positions = vec![0; self.nodes.len()];
+        support.iter().enumerate().for_each(|(position, node)| positions[*node] = position);
+
+        let mut values = vec![0; self.nodes.len()];
+        for word in 0..count.div_ceil(64) {
+
+            let pattern = |node: usize| match positions[node] {
+                position if position < PATTERNS.len() => PATTERNS[position],
+                position => match (word >> (position - PATTERNS.len())) & 1 {
+                    1 => !0,
+                    _ => 0,
+                },
+            };
+            self.evaluate(cone, &mut values, &pattern);
+
+            let difference = (values[left] ^ values[right]) & mask;
+            if difference != 0 {
+                let index = word * 64 + difference.trailing_zeros() as usize;
+                return Some((0..support.len()).map(|position| (index >> position) & 1 == 1).collect());
+            }
+        }
+
+        return None;
+    }
+
+    // encodes a miter of the two nodes and asks the solver for an assignment that sets it
+    fn satisfiability(&self, cone: &[usize], support: &[usize], left: usize, right: usize) -> Option<Vec<bool>> {
+
+        let mut solver = Solver::new();
+        let mut variables = vec![0; self.nodes.len()];
+
+        // inputs come first, so the solver prefers deciding on them while activities are tied
+        for node in support {
+            variables[*node] = solver.add_variable();
+        }
+
+        for node in cone {
+            let output = match self.nodes[*node] {
+                Node::Variable(_) => continue,
+                _ => solver.add_variable(),
+            };
+            variables[*node] = output;
+
+            let literal = |node: usize| -> (Literal, Literal) { (positive(variables[node]), negative(variables[node])) };
+            let (o, not_o) = (positive(output), negative(output));
+
+            match self.nodes[*node] {
+                Node::Constant(true) => solver.add_clause(&[o]),
+                Node::Constant(false) => solver.add_clause(&[not_o]),
+                Node::Variable(_) => (),
+                Node::And(a, b) => {
+                    let ((a, not_a), (b, not_b)) = (literal(a), literal(b));
+                    solver.add_clause(&[not_o, a]);
+                    solver.add_clause(&[not_o, b]);
+                    solver.add_clause(&[o, not_a, not_b]);
+                },
+                Node::Or(a, b) => {
+                    let ((a, not_a), (b, not_b)) = (literal(a), literal(b));
+                    solver.add_clause(&[o, not_a]);
+                    solver.add_clause(&[o, not_b]);
+                    solver.add_clause(&[not_o, a, b]);
+                },
+                Node::Xor(a, b) => add_xor(&mut solver, (o, not_o), literal(a), literal(b)),
+            }
+        }
+
+        let miter = solver.add_variable();
+        add_xor(&mut solver, (positive(miter), negative(miter)), (positive(variables[left]), negative(variables[left])), (positive(variables[right]), negative(variables[right])));
+        solver.add_clause(&[positive(miter)]);
+
+        let model = solver.solve()?;
+        return Some(support.iter().map(|node| model[variables[*node]]).collect());
+    }
+
+    fn compare(&self, left: usize, right: usize) -> (Method, Option<Counterexample>) {
+
+        if left == right {
+            return (Method::Structural, None);
+        }
+
+        let cone = self.cone(&[left, right]);
+        let support: Vec<usize> = cone.iter().cloned().filter(|node| matches!(self.nodes[*node], Node::Variable(_))).collect();
+
+        let (method, assignment) = match support.len() <= EXHAUSTIVE_LIMIT {
+            true => (Method::Exhaustive, self.exhaustive(&cone, &support, left, right)),
+            false => (Method::Satisfiability, self.satisfiability(&cone, &support, left, right)),
+        };
+
+        let counterexample = assignment.map(|assignment| {
+
+            let mut values = vec![0; self.nodes.len()];
+            let input = |node: usize| match assignment[support.iter().position(|other| *other == node).unwrap()] {
+                true => !0,
+                false => 0,
+            };
+            self.evaluate(&cone, &mut values, &input);
+
+            let names = support.iter().map(|node| match self.nodes[*node] {
+                Node::Variable(variable) => self.variables[variable].clone(),
+                _ => unreachable!(),
+            });
+
+            Counterexample {
+                assignment: names.zip(assignment.iter().cloned()).collect(),
+                left: values[left] & 1 == 1,
+                right: values[right] & 1 == 1,
+            }
+        });
+
+        return (method, counterexample);
+    }
+}
+
+fn add_xor(solver: &mut Solver, (o, not_o): (Literal, Literal), (a, not_a): (Literal, Literal), (b, not_b): (Literal, Literal)) {
+    solver.add_clause(&[not_o, a, b]);
+    solver.add_clause(&[not_o, not_a, not_b]);
+    solver.add_clause(&[o, not_a, b]);
+    solver.add_clause(&[o, a, not_b]);
+}
+
+// builds the combinational cones of one netlist into the shared graph
+struct Side<'a> {
+    netlist: &'a Netlist,
+    drivers: Vec<Option<Driver>>,
+    internal: Vec<bool>,
+    nodes: Vec<Option<usize>>,
+    visiting: Vec<bool>,
+}
+
+impl<'a> Side<'a> {
+
+    fn new(netlist: &'a Netlist) -> Result<Self, String> {
+
+        // registers that only feed other registers, like the first half of a flip flop, are internal
+        let sinks = netlist.sinks()?;
+        let internal = netlist.registers.iter().map(|register| sinks[register.output].iter().all(|sink| matches!(sink, Sink::Register(_)))).collect();
+
+        return Ok(Self {
+            netlist: netlist,
+            drivers: netlist.drivers()?,
+            internal: internal,
+            nodes: vec![None; netlist.net_count],
+            visiting: vec![false; netlist.net_count],
+        });
+    }
+
+    // registers are cut points and are paired by name between the two netlists
+    fn register_variable(&self, graph: &mut Graph, index: usize) -> usize {
+        let output = self.netlist.registers[index].output;
+        match self.netlist.signal_name(output) {
+            Some(name) => return graph.variable(format!("register {}", name), &name),
+            None => {
+                let name = format!("{} register {}", self.netlist.name, index);
+                return graph.variable(format!("register {}", name), &name);
+            },
+        }
+    }
+
+    fn cone(&mut self, graph: &mut Graph, net: Net) -> Result<usize, String> {
+
+        if let Some(node) = self.nodes[net] {
+            return Ok(node);
+        }
+
+        if self.visiting[net] {
+            return Err(format!("{} is part of a combinational loop in {}", self.netlist.net_name(net), self.netlist.name));
+        }
+        self.visiting[net] = true;
+
+        let node = match self.drivers[net] {
+            Some(Driver::Gate(index)) => {
+                let gate = &self.netlist.gates[index];
+                let (operator, left, right) = (gate.operator, gate.left, gate.right);
+                let left = self.cone(graph, left)?;
+                let right = self.cone(graph, right)?;
+                graph.gate(operator, left, right)
+            },
+            Some(Driver::Register(index)) => self.register_variable(graph, index),
+            Some(Driver::Constant(index)) => graph.constant(self.netlist.constants[index].state),
+            Some(Driver::Port(index)) => {
+                let name = &self.netlist.ports[index].name;
+                graph.variable(format!("port {}", name), name)
+            },
+            // an undriven gate input never lets the gate fire, so it stays floating
+            None => graph.constant(LogicState::Floating),
+        };
+
+        self.nodes[net] = Some(node);
+        return Ok(node);
+    }
+
+    // the registers feeding logic with their name, edge and the net their next state comes from,
+    // internal registers in between are looked through so a flip flop of two registers matches a single one
+    fn registers(&self) -> Result<Vec<(String, bool, Net)>, String> {
+
+        let mut registers: Vec<(String, bool, Net)> = Vec::new();
+        for (index, register) in self.netlist.registers.iter().enumerate() {
+
+            if self.internal[index] {
+                continue;
+            }
+
+            let name = self.netlist.signal_name(register.output).ok_or_else(|| format!("register {} of {} has no name to pair it by", index, self.netlist.name))?;
+
+            if registers.iter().any(|(other, _rising, _input)| *other == name) {
+                return Err(format!("register {} exists twice in {}", name, self.netlist.name));
+            }
+
+            let mut input = register.input;
+            let mut steps = 0;
+            while let Some(Driver::Register(index)) = self.drivers[input] {
+                if !self.internal[index] || steps == self.netlist.registers.len() {
+                    break;
+                }
+                input = self.netlist.registers[index].input;
+                steps += 1;
+            }
+
+            registers.push((name, register.rising, input));
+        }
+
+        return Ok(registers);
+    }
+}
+
+impl Netlist {
+
+    // compares the combinational logic of two netlists, inputs and outputs are paired by name
+    // and registers feeding logic are treated as both an input and an output of the logic
+    pub fn check_equivalence(&self, other: &Netlist) -> Result<EquivalenceReport, String> {
+
+        for (first, second) in &[(self, other), (other, self)] {
+            for port in &first.ports {
+                if !second.ports.iter().any(|other| other.name == port.name && other.direction == port.direction) {
+                    return Err(format!("port {} of {} has no counterpart in {}", port.name, first.name, second.name));
+                }
+            }
+        }
+
+        let mut left = Side::new(self)?;
+        let mut right = Side::new(other)?;
+        let left_registers = left.registers()?;
+        let right_registers = right.registers()?;
+
+        for (first, second, first_name, second_name) in &[(&left_registers, &right_registers, &self.name, &other.name), (&right_registers, &left_registers, &other.name, &self.name)] {
+            for (name, _rising, _input) in first.iter() {
+                if !second.iter().any(|(other, _rising, _input)| other == name) {
+                    return Err(format!("register {} of {} has no counterpart in {}", name, first_name, second_name));
+                }
+            }
+        }
+
+        let mut graph = Graph::new();
+        let mut pairs = Vec::new();
+
+        for port in self.ports.iter().filter(|port| port.direction == PortDirection::Output) {
+            let other_port = other.ports.iter().find(|other| other.name == port.name).unwrap();
+            pairs.push((port.name.clone(), left.cone(&mut graph, port.net)?, right.cone(&mut graph, other_port.net)?));
+        }
+
+        for (name, rising, input) in &left_registers {
+            let (_name, other_rising, other_input) = right_registers.iter().find(|(other, _rising, _input)| other == name).unwrap();
+            if rising != other_rising {
+                return Err(format!("register {} is clocked on different edges in {} and {}", name, self.name, other.name));
+            }
+            pairs.push((format!("register {}", name), left.cone(&mut graph, *input)?, right.cone(&mut graph, *other_input)?));
+        }
+
+        let comparisons = pairs.into_iter().map(|(name, left, right)| {
+            let (method, counterexample) = graph.compare(left, right);
+            Comparison {
+                name: name,
+                method: method,
+                counterexample: counterexample,
+            }
+        });
+
+        return Ok(EquivalenceReport {
+            left: self.name.clone(),
+            right: other.name.clone(),
+            comparisons: comparisons.collect(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ Graph, Node, Method };
+    use super::super::{ Netlist, Random };
+    use super::super::super::{ Core, LogicState, Operator };
+    use crate::types::FloatVector;
+
+    // a random graph over a few variables, every node only uses the ones before it
+    fn random_graph(random: &mut Random, variables: usize, gates: usize) -> Graph {
+        let mut graph = Graph::new();
+        (0..variables).for_each(|variable| { graph.variable(format!("port {}", variable), &variable.to_string()); });
+
+        for _gate in 0..gates {
+            let operator = [Operator::And, Operator::Or, Operator::Xor][random.below(3)];
+            let (left, right) = (random.below(graph.nodes.len()), random.below(graph.nodes.len()));
+            graph.gate(operator, left, right);
+        }
+
+        return graph;
+    }
+
+    fn differs(graph: &Graph, cone: &[usize], support: &[usize], left: usize, right: usize, assignment: &[bool]) -> bool {
+        let mut values = vec![0; graph.nodes.len()];
+        let input = |node: usize| match assignment[support.iter().position(|other| *other == node).unwrap()] {
+            true => !0,
+            false => 0,
+        };
+        graph.evaluate(cone, &mut values, &input);
+        return (values[left] ^ values[right]) & 1 == 1;
+    }
+
+    #[test]
+    fn exhaustive_and_sat_agree() {
+
+        let mut random = Random::new(11);
+        let mut differences = 0;
+
+        for case in 0..300 {
+
+            let mut graph = random_graph(&mut random, 8, 40);
+            let left = random.below(graph.nodes.len());
+
+            // every other case compares against a node that is equal but built differently
+            let right = match case % 2 {
+                0 => random.below(graph.nodes.len()),
+                _other => {
+                    let other = random.below(graph.nodes.len());
+                    let inverted = graph.gate(Operator::Xor, left, other);
+                    graph.gate(Operator::Xor, inverted, other)
+                },
+            };
+
+            let cone = graph.cone(&[left, right]);
+            let support: Vec<usize> = cone.iter().cloned().filter(|node| matches!(graph.nodes[*node], Node::Variable(_))).collect();
+
+            let exhaustive = graph.exhaustive(&cone, &support, left, right);
+            let satisfiability = graph.satisfiability(&cone, &support, left, right);
+            assert_eq!(exhaustive.is_some(), satisfiability.is_some());
+            differences += exhaustive.is_some() as usize;
+
+            for assignment in exhaustive.iter().chain(satisfiability.iter()) {
+                assert!(differs(&graph, &cone, &support, left, right, assignment));
+            }
+        }
+
+        // both outcomes have to show up for the comparison to mean anything
+        assert!(differences > 50 && differences < 150);
+    }
+
+    // applies the counterexample to the inputs and returns the output the circuit gives
+    fn simulate(netlist: &Netlist, assignment: &[(String, bool)]) -> LogicState {
+        let mut core = Core::from_netlist(netlist.name.clone(), netlist, FloatVector::new(), false).unwrap();
+        for (name, value) in assignment {
+            core.set_port_state(core.find_port(name).unwrap(), LogicState::from_boolean(*value));
+        }
+        core.tick(true);
+        return core.port_state(core.find_port("y").unwrap());
+    }
+
+    #[test]
+    fn counterexamples_reproduce_the_difference() {
+
+        let narrow = ("module narrow(input a, input b, input c, output y); assign y = a & b | c; endmodule", "module narrow(input a, input b, input c, output y); assign y = a & (b | c); endmodule");
+        let wide = ("module wide(input [16:0] i, output y); assign y = &i; endmodule", "module wide(input [16:0] i, output y); assign y = &(i ^ 17'b1); endmodule");
+
+        for ((left, right), method) in [(narrow, Method::Exhaustive), (wide, Method::Satisfiability)].iter() {
+
+            let (left, right) = (Netlist::from_verilog(left).unwrap(), Netlist::from_verilog(right).unwrap());
+            let report = left.check_equivalence(&right).unwrap();
+            assert!(!report.is_equivalent());
+            assert_eq!(report.comparisons[0].method, *method);
+
+            let counterexample = report.comparisons[0].counterexample.as_ref().unwrap();
+            assert_eq!(simulate(&left, &counterexample.assignment), LogicState::from_boolean(counterexample.left));
+            assert_eq!(simulate(&right, &counterexample.assignment), LogicState::from_boolean(counterexample.right));
+            assert_ne!(counterexample.left, counterexample.right);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use super::super::logic::{ Output, Gate, Register, Constant, Port };
+use super::super::{ LabelSource, Signal };
+use super::{ Netlist, Net, PortDirection };
+
+struct Extractor {
+    netlist: Netlist,
+    gate_inputs: Vec<(Option<Net>, Option<Net>)>,
+    register_inputs: Vec<Option<Net>>,
+    port_inputs: Vec<Option<Net>>,
+}
+
+impl Extractor {
+
+    fn connect(&mut self, output: &Output, net: Net) {
+        match output {
+            Output::Register(index) => self.register_inputs[*index] = Some(net),
+            Output::Gate(index, false) => self.gate_inputs[*index].0 = Some(net),
+            Output::Gate(index, true) => self.gate_inputs[*index].1 = Some(net),
+            Output::Port(index) => self.port_inputs[*index] = Some(net),
+            Output::Disconnected => { },
+            Output::Splitter(left, right) => {
+                self.connect(left, net);
+                self.connect(right, net);
+            },
+        }
+    }
+
+    fn undriven(&mut self, net: Option<Net>) -> Net {
+        match net {
+            Some(net) => return net,
+            None => return self.netlist.add_net(),
+        }
+    }
+}
+
+// every driver gets its own net, registers start with the state they currently hold
+pub fn extract(name: &str, gates: &[Gate], registers: &[Register], constants: &[Constant], ports: &[Port], signals: &[Signal]) -> Netlist {
+
+    let mut extractor = Extractor {
+        netlist: Netlist::new(name),
+        gate_inputs: vec![(None, None); gates.len()],
+        register_inputs: vec![None; registers.len()],
+        port_inputs: vec![None; ports.len()],
+    };
+
+    let gate_outputs: Vec<Net> = gates.iter().map(|_gate| extractor.netlist.add_net()).collect();
+    let register_outputs: Vec<Net> = registers.iter().map(|_register| extractor.netlist.add_net()).collect();
+    let constant_outputs: Vec<Net> = constants.iter().map(|_constant| extractor.netlist.add_net()).collect();
+    let port_outputs: Vec<Option<Net>> = ports.iter().map(|port| port.is_input().then(|| extractor.netlist.add_net())).collect();
+
+    gates.iter().zip(gate_outputs.iter()).for_each(|(gate, net)| extractor.connect(&gate.output, *net));
+    registers.iter().zip(register_outputs.iter()).for_each(|(register, net)| extractor.connect(&register.output, *net));
+    constants.iter().zip(constant_outputs.iter()).for_each(|(constant, net)| extractor.connect(&constant.output, *net));
+
+    for (port, net) in ports.iter().zip(port_outputs.iter()) {
+        if let (Some(output), Some(net)) = (&port.output, net) {
+            extractor.connect(output, *net);
+        }
+    }
+
+    for (index, gate) in gates.iter().enumerate() {
+        let (left, right) = extractor.gate_inputs[index];
+        let left = extractor.undriven(left);
+        let right = extractor.undriven(right);
+        extractor.netlist.add_gate(gate.operator, left, right, gate_outputs[index]);
+    }
+
+    for (index, register) in registers.iter().enumerate() {
+        let input = extractor.undriven(extractor.register_inputs[index]);
+        extractor.netlist.add_register(register.rising, register.state, input, register_outputs[index]);
+    }
+
+    for (constant, net) in constants.iter().zip(constant_outputs.iter()) {
+        extractor.netlist.constants.push(super::NetlistConstant {
+            state: constant.state,
+            output: *net,
+        });
+    }
+
+    for (index, port) in ports.iter().enumerate() {
+        match port_outputs[index] {
+            Some(net) => extractor.netlist.add_port(&port.name, PortDirection::Input, net),
+            None => {
+                let net = extractor.undriven(extractor.port_inputs[index]);
+                extractor.netlist.add_port(&port.name, PortDirection::Output, net);
+            },
+        }
+    }
+
+    // signals are named by the inspector path without the name of the root group
+    let netlist = &extractor.netlist;
+    let source_net = |source: &LabelSource| match source {
+        LabelSource::Register(index) => netlist.registers[*index].input,
+        LabelSource::Gate(index, false) => netlist.gates[*index].left,
+        LabelSource::Gate(index, true) => netlist.gates[*index].right,
+        LabelSource::Port(index) => netlist.ports[*index].net,
+    };
+
+    let named: Vec<(String, Vec<Net>)> = signals.iter().map(|signal| {
+        let name = signal.name.split_once('/').map_or(signal.name.as_str(), |(_core, name)| name);
+        (String::from(name), signal.sources.iter().map(source_net).collect())
+    }).collect();
+
+    named.into_iter().for_each(|(name, nets)| extractor.netlist.add_signal(&name, nets));
+    return extractor.netlist;
+}
@@ -0,0 +1,372 @@
+use super::{ Netlist, Net, Driver, Sink, PortDirection, Stimulus };
+use super::super::{ LogicState, Operator };
+use std::fmt;
+
+// the first machine of every word is fault free, the others carry one fault each
+const MACHINES: usize = 64;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FaultSite {
+    GateInput(usize, bool),
+    GateOutput(usize),
+    Register(usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct Fault {
+    pub site: FaultSite,
+    pub stuck: bool,
+    pub name: String,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{} stuck at {}", self.name, self.stuck as u8);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FaultReport {
+    pub cycles: usize,
+    // detected faults with the cycle they were first seen at an output
+    pub detected: Vec<(Fault, usize)>,
+    pub undetected: Vec<Fault>,
+}
+
+impl FaultReport {
+
+    pub fn coverage(&self) -> f64 {
+        match self.detected.len() + self.undetected.len() {
+            0 => return 100.0,
+            total => return self.detected.len() as f64 * 100.0 / total as f64,
+        }
+    }
+}
+
+impl fmt::Display for FaultReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} faults detected in {} cycles, {:.1}% coverage", self.detected.len(), self.detected.len() + self.undetected.len(), self.cycles, self.coverage())?;
+        for fault in &self.undetected {
+            write!(f, "\n  undetected: {}", fault)?;
+        }
+        return Ok(());
+    }
+}
+
+// the states of 64 machines, a set floating bit clears the metastable and value bits and a set metastable bit clears the value bit
+#[derive(Copy, Clone, Debug)]
+struct Word {
+    value: u64,
+    floating: u64,
+    metastable: u64,
+}
+
+impl Word {
+
+    fn broadcast(state: LogicState) -> Self {
+        match state {
+            LogicState::Low => return Self { value: 0, floating: 0, metastable: 0 },
+            LogicState::High => return Self { value: !0, floating: 0, metastable: 0 },
+            LogicState::Floating => return Self { value: 0, floating: !0, metastable: 0 },
+            LogicState::Metastable => return Self { value: 0, floating: 0, metastable: !0 },
+        }
+    }
+
+    fn known(&self) -> u64 {
+        return !(self.floating | self.metastable);
+    }
+
+    // floating wins over metastable, which wins over the boolean result, just like a single gate
+    fn operator(self, other: Self, operator: Operator) -> Self {
+        let floating = self.floating | other.floating;
+        let metastable = (self.metastable | other.metastable) & !floating;
+        let value = match operator {
+            Operator::And => self.value & other.value,
+            Operator::Or => self.value | other.value,
+            Operator::Xor => self.value ^ other.value,
+        };
+
+        return Self {
+            value: value & !(floating | metastable),
+            floating: floating,
+            metastable: metastable,
+        };
+    }
+
+    fn force(self, stuck: (u64, u64)) -> Self {
+        let (hit, ones) = stuck;
+        return Self {
+            value: (self.value & !hit) | (ones & hit),
+            floating: self.floating & !hit,
+            metastable: self.metastable & !hit,
+        };
+    }
+
+    // machines that hold a known value different from the fault free machine
+    fn differences(&self) -> u64 {
+        if self.known() & 1 == 0 {
+            return 0;
+        }
+
+        let good = match self.value & 1 {
+            1 => !0,
+            _ => 0,
+        };
+        return (self.value ^ good) & self.known() & !1;
+    }
+}
+
+// which nets are driven in a tick only depends on the structure of the netlist, so it is the same for every machine
+struct Phase {
+    sources: Vec<Net>,
+    registers: Vec<usize>,
+    gates: Vec<usize>,
+    register_inputs: Vec<usize>,
+    output_ports: Vec<usize>,
+}
+
+impl Netlist {
+
+    fn phase(&self, rising: bool, sinks: &[Vec<Sink>]) -> Phase {
+
+        let mut active = vec![false; self.net_count];
+        let mut pending = Vec::new();
+        let mut sources = Vec::new();
+        let mut registers = Vec::new();
+
+        // constants push on every tick, input ports only on the rising one
+        for constant in &self.constants {
+            sources.push(constant.output);
+        }
+
+        if rising {
+            self.ports.iter().filter(|port| port.direction == PortDirection::Input).for_each(|port| sources.push(port.net));
+        }
+
+        for (index, register) in self.registers.iter().enumerate() {
+            if register.rising == rising {
+                registers.push(index);
+                pending.push(register.output);
+                active[register.output] = true;
+            }
+        }
+
+        for net in &sources {
+            active[*net] = true;
+            pending.push(*net);
+        }
+
+        // a gate fires once both of its inputs were driven, which also orders the gates
+        let mut driven_inputs = vec![0; self.gates.len()];
+        let mut gates = Vec::new();
+        while let Some(net) = pending.pop() {
+            for sink in &sinks[net] {
+                if let Sink::Gate(index, _right) = sink {
+                    driven_inputs[*index] += 1;
+                    if driven_inputs[*index] == 2 {
+                        gates.push(*index);
+                        active[self.gates[*index].output] = true;
+                        pending.push(self.gates[*index].output);
+                    }
+                }
+            }
+        }
+
+        let register_inputs = (0..self.registers.len()).filter(|index| active[self.registers[*index].input]).collect();
+        let output_ports = (0..self.ports.len()).filter(|index| self.ports[*index].direction == PortDirection::Output && active[self.ports[*index].net]).collect();
+
+        return Phase {
+            sources: sources,
+            registers: registers,
+            gates: gates,
+            register_inputs: register_inputs,
+            output_ports: output_ports,
+        };
+    }
+
+    fn describe_fault_site(&self, site: FaultSite) -> String {
+        match site {
+            FaultSite::GateInput(index, false) => return format!("left input of {}", self.gate_element_name(index)),
+            FaultSite::GateInput(index, true) => return format!("right input of {}", self.gate_element_name(index)),
+            FaultSite::GateOutput(index) => return format!("output of {}", self.gate_element_name(index)),
+            FaultSite::Register(index) => match self.signal_name(self.registers[index].output) {
+                Some(name) => return format!("register {}", name),
+                None => return format!("register {}", index),
+            },
+        }
+    }
+
+    // every gate pin and register stuck at both low and high
+    pub fn faults(&self) -> Vec<Fault> {
+
+        let mut sites = Vec::new();
+        for index in 0..self.gates.len() {
+            sites.extend_from_slice(&[FaultSite::GateInput(index, false), FaultSite::GateInput(index, true), FaultSite::GateOutput(index)]);
+        }
+        sites.extend((0..self.registers.len()).map(FaultSite::Register));
+
+        return sites.into_iter().flat_map(|site| vec![false, true].into_iter().map(move |stuck| (site, stuck))).map(|(site, stuck)| Fault {
+            site: site,
+            stuck: stuck,
+            name: self.describe_fault_site(site),
+        }).collect();
+    }
+
+    // simulates 63 faulty machines next to a fault free one at a time, starting from the initial register states,
+    // a fault is detected once an output port holds a known value that differs from the fault free machine
+    pub fn simulate_faults(&self, faults: Vec<Fault>, stimulus: &Stimulus) -> Result<FaultReport, String> {
+
+        let drivers = self.drivers()?;
+        let sinks = self.sinks()?;
+        let cycles = stimulus.resolve(self)?;
+        let phases = [self.phase(true, &sinks), self.phase(false, &sinks)];
+
+        let mut first_detected: Vec<Option<usize>> = vec![None; faults.len()];
+
+        for (chunk, chunk_faults) in faults.chunks(MACHINES - 1).enumerate() {
+
+            // the machines a fault applies to and the value it forces, for every site
+            let mut left = vec![(0u64, 0u64); self.gates.len()];
+            let mut right = vec![(0u64, 0u64); self.gates.len()];
+            let mut outputs = vec![(0u64, 0u64); self.gates.len()];
+            let mut stuck_registers = vec![(0u64, 0u64); self.registers.len()];
+
+            for (position, fault) in chunk_faults.iter().enumerate() {
+                let bit = 1u64 << (position + 1);
+                let site = match fault.site {
+                    FaultSite::GateInput(index, false) => &mut left[index],
+                    FaultSite::GateInput(index, true) => &mut right[index],
+                    FaultSite::GateOutput(index) => &mut outputs[index],
+                    FaultSite::Register(index) => &mut stuck_registers[index],
+                };
+                site.0 |= bit;
+                if fault.stuck {
+                    site.1 |= bit;
+                }
+            }
+
+            let all = match chunk_faults.len() + 1 {
+                MACHINES => !0,
+                count => (1u64 << count) - 1,
+            } & !1;
+
+            let mut nets = vec![Word::broadcast(LogicState::Floating); self.net_count];
+            let mut ports = vec![Word::broadcast(LogicState::Floating); self.ports.len()];
+            let mut inputs: Vec<Word> = self.registers.iter().map(|register| Word::broadcast(register.initial)).collect();
+            let mut states = inputs.clone();
+            let mut detected = 0u64;
+
+            for (cycle, values) in cycles.iter().enumerate() {
+
+                for (port, state) in values {
+                    ports[*port] = Word::broadcast(*state);
+                }
+
+                for (phase, rising) in phases.iter().zip([true, false].iter()) {
+
+                    states.copy_from_slice(&inputs);
+
+                    for net in &phase.sources {
+                        nets[*net] = match drivers[*net] {
+                            Some(Driver::Constant(index)) => Word::broadcast(self.constants[index].state),
+                            Some(Driver::Port(index)) => ports[index],
+                            _ => unreachable!(),
+                        };
+                    }
+
+                    for index in &phase.registers {
+                        nets[self.registers[*index].output] = states[*index].force(stuck_registers[*index]);
+                    }
+
+                    for index in &phase.gates {
+                        let gate = &self.gates[*index];
+                        let state = nets[gate.left].force(left[*index]).operator(nets[gate.right].force(right[*index]), gate.operator);
+                        nets[gate.output] = state.force(outputs[*index]);
+                    }
+
+                    // registers written on their own edge turn metastable
+                    for index in &phase.register_inputs {
+                        let register = &self.registers[*index];
+                        inputs[*index] = match register.rising == *rising {
+                            true => Word::broadcast(LogicState::Metastable),
+                            false => nets[register.input],
+                        };
+                    }
+
+                    for index in &phase.output_ports {
+                        ports[*index] = nets[self.ports[*index].net];
+                        let newly = ports[*index].differences() & all & !detected;
+                        if newly != 0 {
+                            (1..MACHINES).filter(|bit| newly >> bit & 1 == 1).for_each(|bit| first_detected[chunk * (MACHINES - 1) + bit - 1] = Some(cycle));
+                            detected |= newly;
+                        }
+                    }
+                }
+
+                if detected == all {
+                    break;
+                }
+            }
+        }
+
+        let mut report = FaultReport {
+            cycles: cycles.len(),
+            detected: Vec::new(),
+            undetected: Vec::new(),
+        };
+
+        for (fault, cycle) in faults.into_iter().zip(first_detected) {
+            match cycle {
+                Some(cycle) => report.detected.push((fault, cycle)),
+                None => report.undetected.push(fault),
+            }
+        }
+
+        return Ok(report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::{ Netlist, Stimulus };
+    use super::FaultReport;
+
+    const AND_OR: &str = "
+        module and_or(input a, input b, input c, output y);
+            wire n;
+            and g1 (n, a, b);
+            or g2 (y, n, c);
+        endmodule
+    ";
+
+    fn report(netlist: &Netlist, stimulus: &str) -> FaultReport {
+        return netlist.simulate_faults(netlist.faults(), &Stimulus::parse(stimulus).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn coverage_of_two_gates() {
+
+        let netlist = Netlist::from_verilog(AND_OR).unwrap();
+        assert_eq!(netlist.faults().len(), 12);
+
+        // a single pattern with y high only finds the faults that pull the path from a and b low
+        let partial = report(&netlist, "a b c\n1 1 0\n");
+        let detected: Vec<String> = partial.detected.iter().map(|(fault, _cycle)| fault.to_string()).collect();
+        assert_eq!(detected, vec![
+            "left input of and gate 0 driving n stuck at 0",
+            "right input of and gate 0 driving n stuck at 0",
+            "output of and gate 0 driving n stuck at 0",
+            "left input of or gate 1 driving y stuck at 0",
+            "output of or gate 1 driving y stuck at 0",
+        ]);
+        assert_eq!(partial.undetected.len(), 7);
+
+        // every fault is found by the pattern that first exposes it
+        let full = report(&netlist, "a b c\n1 1 0\n0 1 0\n1 0 0\n0 0 1\n");
+        assert_eq!(full.cycles, 4);
+        assert_eq!(full.coverage(), 100.0);
+        let cycles: Vec<usize> = full.detected.iter().map(|(_fault, cycle)| *cycle).collect();
+        assert_eq!(cycles, vec![0, 1, 0, 2, 0, 1, 0, 1, 3, 1, 0, 1]);
+    }
+}
@@ -0,0 +1,255 @@
+// a small json parser, objects keep the order of their members
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+
+        let mut parser = JsonParser {
+            characters: source.chars().collect(),
+            position: 0,
+        };
+
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+
+        if parser.position < parser.characters.len() {
+            return Err(parser.error("unexpected characters after the end of the document"));
+        }
+
+        return Ok(value);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => return members.iter().find(|(name, _value)| name == key).map(|(_name, value)| value),
+            _other => return None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => return Some(string),
+            _other => return None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(number) if *number >= 0.0 && number.fract() == 0.0 => return Some(*number as usize),
+            _other => return None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(elements) => return Some(elements),
+            _other => return None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Vec<(String, Json)>> {
+        match self {
+            Json::Object(members) => return Some(members),
+            _other => return None,
+        }
+    }
+}
+
+struct JsonParser {
+    characters: Vec<char>,
+    position: usize,
+}
+
+impl JsonParser {
+
+    fn error(&self, message: &str) -> String {
+        let consumed = &self.characters[..self.position.min(self.characters.len())];
+        let line = consumed.iter().filter(|character| **character == '\n').count() + 1;
+        let column = consumed.iter().rev().take_while(|character| **character != '\n').count() + 1;
+        return format!("line {} column {}: {}", line, column, message);
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.characters.get(self.position).cloned();
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let character = self.peek();
+        self.position += 1;
+        return character;
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map(|character| character.is_whitespace()).unwrap_or(false) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(character) if character == expected => {
+                self.position += 1;
+                return Ok(());
+            },
+            Some(character) => return Err(self.error(&format!("expected '{}', found '{}'", expected, character))),
+            None => return Err(self.error(&format!("expected '{}', found end of file", expected))),
+        }
+    }
+
+    fn expect_word(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for expected in word.chars() {
+            if self.next() != Some(expected) {
+                self.position -= 1;
+                return Err(self.error(&format!("invalid literal, expected {}", word)));
+            }
+        }
+        return Ok(value);
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => return self.parse_object(),
+            Some('[') => return self.parse_array(),
+            Some('"') => return self.parse_string().map(Json::String),
+            Some('t') => return self.expect_word("true", Json::Boolean(true)),
+            Some('f') => return self.expect_word("false", Json::Boolean(false)),
+            Some('n') => return self.expect_word("null", Json::Null),
+            Some(character) if character == '-' || character.is_ascii_digit() => return self.parse_number(),
+            Some(character) => return Err(self.error(&format!("unexpected character '{}'", character))),
+            None => return Err(self.error("unexpected end of file")),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a member name"));
+            }
+
+            let name = self.parse_string()?;
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            members.push((name, value));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Object(members)),
+                _other => {
+                    self.position -= 1;
+                    return Err(self.error("expected ',' or '}'"));
+                },
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut elements = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(elements));
+        }
+
+        loop {
+            elements.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Array(elements)),
+                _other => {
+                    self.position -= 1;
+                    return Err(self.error("expected ',' or ']'"));
+                },
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+
+        loop {
+            match self.next() {
+
+                Some('"') => return Ok(string),
+
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape()?,
+                        _other => {
+                            self.position -= 1;
+                            return Err(self.error("invalid escape sequence"));
+                        },
+                    };
+                    string.push(escaped);
+                },
+
+                Some(character) => string.push(character),
+
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    // surrogate pairs are not combined, netlists practically never contain them
+    fn parse_unicode_escape(&mut self) -> Result<char, String> {
+        let digits: String = (0..4).filter_map(|_index| self.next()).collect();
+
+        match u32::from_str_radix(&digits, 16) {
+            Ok(code) => return Ok(std::char::from_u32(code).unwrap_or('\u{fffd}')),
+            Err(_error) => return Err(self.error(&format!("invalid unicode escape {}", digits))),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+
+        while self.peek().map(|character| character.is_ascii_digit() || "+-.eE".contains(character)).unwrap_or(false) {
+            self.position += 1;
+        }
+
+        let text: String = self.characters[start..self.position].iter().collect();
+        match text.parse() {
+            Ok(number) => return Ok(Json::Number(number)),
+            Err(_error) => {
+                self.position = start;
+                return Err(self.error(&format!("invalid number {}", text)));
+            },
+        }
+    }
+}
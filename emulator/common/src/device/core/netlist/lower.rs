@@ -0,0 +1,109 @@
+use super::super::logic::{ LogicState, Input, Output, Gate, Register, Constant, Port };
+use super::super::inspector::{ InspectorItem, Group, Label, Formatting, LogicTracker, ValueTracker, LabelSource };
+use super::{ Netlist, Net, Driver, Sink, PortDirection };
+
+pub struct LoweredNetlist {
+    pub gates: Vec<Gate>,
+    pub registers: Vec<Register>,
+    pub constants: Vec<Constant>,
+    pub ports: Vec<Port>,
+    pub root_item: InspectorItem,
+    pub logic_trackers: Vec<LogicTracker>,
+    pub value_trackers: Vec<ValueTracker>,
+}
+
+// fan-out is expressed as a chain of splitters
+fn output_tree(sinks: &[Sink]) -> Output {
+
+    let output = |sink: &Sink| match sink {
+        Sink::Gate(index, right) => Output::Gate(*index, *right),
+        Sink::Register(index) => Output::Register(*index),
+        Sink::Port(index) => Output::Port(*index),
+    };
+
+    match sinks {
+        [] => return Output::Disconnected,
+        [sink] => return output(sink),
+        [sink, rest @ ..] => return Output::Splitter(Box::new(output(sink)), Box::new(output_tree(rest))),
+    }
+}
+
+impl Netlist {
+
+    pub fn lower(&self) -> Result<LoweredNetlist, String> {
+
+        self.validate()?;
+        let drivers = self.drivers()?;
+        let sinks = self.sinks()?;
+
+        let gates = self.gates.iter().map(|gate| Gate::new(gate.operator, output_tree(&sinks[gate.output]))).collect();
+        let registers = self.registers.iter().map(|register| Register::new(Input::new(register.initial), output_tree(&sinks[register.output]), register.rising)).collect();
+        let constants = self.constants.iter().map(|constant| Constant::new(constant.state, output_tree(&sinks[constant.output]))).collect();
+
+        // unconnected input ports float until a wire or the user drives them
+        let ports = self.ports.iter().map(|port| match port.direction {
+            PortDirection::Input => Port::input(&port.name, LogicState::Floating, output_tree(&sinks[port.net])),
+            PortDirection::Output => Port::output(&port.name),
+        }).collect();
+
+        // nets are observed where they arrive, or at the driver if nothing reads them
+        let source = |net: Net| {
+            match sinks[net].first() {
+                Some(Sink::Register(index)) => return Some(LabelSource::Register(*index)),
+                Some(Sink::Gate(index, right)) => return Some(LabelSource::Gate(*index, *right)),
+                Some(Sink::Port(index)) => return Some(LabelSource::Port(*index)),
+                None => match drivers[net] {
+                    Some(Driver::Port(index)) => return Some(LabelSource::Port(index)),
+                    _other => return None,
+                },
+            }
+        };
+
+        let mut logic_trackers = Vec::new();
+        let mut value_trackers = Vec::new();
+        let mut tracker_indices = vec![None; self.net_count];
+        let mut items = Vec::new();
+
+        for signal in &self.signals {
+
+            let sources: Option<Vec<LabelSource>> = signal.nets.iter().map(|net| source(*net)).collect();
+            let sources = match sources {
+                Some(sources) if !sources.is_empty() => sources,
+                _other => continue,
+            };
+
+            let mut trackers = Vec::new();
+            for (net, source) in signal.nets.iter().zip(sources) {
+                let tracker = *tracker_indices[*net].get_or_insert_with(|| {
+                    logic_trackers.push(LogicTracker::new(source));
+                    logic_trackers.len() - 1
+                });
+                trackers.push(tracker);
+            }
+
+            match trackers.len() {
+
+                1 => items.push(InspectorItem::Label(Label::new(&signal.name, trackers[0]))),
+
+                width => {
+                    let bit_items = trackers.iter().enumerate().map(|(offset, tracker)| {
+                        InspectorItem::Label(Label::new(&format!("{}[{}]", signal.name, width - offset - 1), *tracker))
+                    }).collect();
+
+                    value_trackers.push(ValueTracker::new(trackers));
+                    items.push(InspectorItem::Group(Group::new(&signal.name, Some((value_trackers.len() - 1, Formatting::Binary)), bit_items)));
+                },
+            }
+        }
+
+        return Ok(LoweredNetlist {
+            gates: gates,
+            registers: registers,
+            constants: constants,
+            ports: ports,
+            root_item: InspectorItem::Group(Group::new(&self.name, None, items)),
+            logic_trackers: logic_trackers,
+            value_trackers: value_trackers,
+        });
+    }
+}
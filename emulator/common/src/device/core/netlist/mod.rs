@@ -0,0 +1,357 @@
+mod json;
+mod yosys;
+mod blif;
+mod lower;
+mod extract;
+mod verilog_reader;
+mod verilog_writer;
+mod sat;
+mod equivalence;
+mod timing;
+mod stimulus;
+mod fault;
+mod truth;
+mod random;
+
+use super::{ LogicState, Operator };
+
+pub use self::lower::LoweredNetlist;
+pub use self::extract::extract;
+pub use self::equivalence::{ EquivalenceReport, Comparison, Counterexample, Method };
+pub use self::timing::{ Delays, TimingPath, TimingReport };
+pub use self::stimulus::Stimulus;
+pub use self::fault::{ Fault, FaultSite, FaultReport };
+pub use self::truth::{ TruthTable, TableFormat };
+pub use self::random::Random;
+
+pub type Net = usize;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PortDirection {
+    Input,
+    Output,
+}
+
+#[derive(Clone, Debug)]
+pub struct NetlistGate {
+    pub operator: Operator,
+    pub left: Net,
+    pub right: Net,
+    pub output: Net,
+}
+
+#[derive(Clone, Debug)]
+pub struct NetlistRegister {
+    pub rising: bool,
+    pub initial: LogicState,
+    pub input: Net,
+    pub output: Net,
+}
+
+#[derive(Clone, Debug)]
+pub struct NetlistConstant {
+    pub state: LogicState,
+    pub output: Net,
+}
+
+#[derive(Clone, Debug)]
+pub struct NetlistPort {
+    pub name: String,
+    pub direction: PortDirection,
+    pub net: Net,
+}
+
+// named nets shown in the inspector, buses list their nets starting with the most significant bit
+#[derive(Clone, Debug)]
+pub struct NetlistSignal {
+    pub name: String,
+    pub nets: Vec<Net>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Driver {
+    Gate(usize),
+    Register(usize),
+    Constant(usize),
+    Port(usize),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Sink {
+    Gate(usize, bool),
+    Register(usize),
+    Port(usize),
+}
+
+// an intermediate representation of a circuit that every importer and exporter goes through
+#[derive(Clone, Debug)]
+pub struct Netlist {
+    pub name: String,
+    pub net_count: usize,
+    pub gates: Vec<NetlistGate>,
+    pub registers: Vec<NetlistRegister>,
+    pub constants: Vec<NetlistConstant>,
+    pub ports: Vec<NetlistPort>,
+    pub signals: Vec<NetlistSignal>,
+}
+
+impl Netlist {
+
+    pub fn new(name: &str) -> Self {
+        return Self {
+            name: String::from(name),
+            net_count: 0,
+            gates: Vec::new(),
+            registers: Vec::new(),
+            constants: Vec::new(),
+            ports: Vec::new(),
+            signals: Vec::new(),
+        };
+    }
+
+    pub fn from_yosys_json(source: &str) -> Result<Self, String> {
+        return yosys::import(source);
+    }
+
+    pub fn from_blif(source: &str) -> Result<Self, String> {
+        return blif::import(source);
+    }
+
+    pub fn from_verilog(source: &str) -> Result<Self, String> {
+        return verilog_reader::import(source);
+    }
+
+    pub fn add_net(&mut self) -> Net {
+        self.net_count += 1;
+        return self.net_count - 1;
+    }
+
+    pub fn add_gate(&mut self, operator: Operator, left: Net, right: Net, output: Net) {
+        self.gates.push(NetlistGate {
+            operator: operator,
+            left: left,
+            right: right,
+            output: output,
+        });
+    }
+
+    // same as add_gate but the output is driven onto a new net
+    pub fn add_gate_net(&mut self, operator: Operator, left: Net, right: Net) -> Net {
+        let output = self.add_net();
+        self.add_gate(operator, left, right, output);
+        return output;
+    }
+
+    pub fn add_register(&mut self, rising: bool, initial: LogicState, input: Net, output: Net) {
+        self.registers.push(NetlistRegister {
+            rising: rising,
+            initial: initial,
+            input: input,
+            output: output,
+        });
+    }
+
+    // an edge triggered flip flop is a pair of registers on opposite edges, otherwise any path between two
+    // flip flops on the same edge would be a timing violation
+    pub fn add_flip_flop(&mut self, rising: bool, initial: LogicState, input: Net, output: Net) {
+        let intermediate = self.add_net();
+        self.add_register(!rising, initial, input, intermediate);
+        self.add_register(rising, initial, intermediate, output);
+    }
+
+    // constants are shared, so asking for the same state twice returns the same net
+    pub fn add_constant(&mut self, state: LogicState) -> Net {
+
+        if let Some(constant) = self.constants.iter().find(|constant| constant.state == state) {
+            return constant.output;
+        }
+
+        let output = self.add_net();
+        self.constants.push(NetlistConstant {
+            state: state,
+            output: output,
+        });
+        return output;
+    }
+
+    // there is no inverter, so an exclusive or with a high constant is used instead
+    pub fn add_not(&mut self, input: Net) -> Net {
+        let high = self.add_constant(LogicState::High);
+        return self.add_gate_net(Operator::Xor, input, high);
+    }
+
+    pub fn add_not_to(&mut self, input: Net, output: Net) {
+        let high = self.add_constant(LogicState::High);
+        self.add_gate(Operator::Xor, input, high, output);
+    }
+
+    // an or with a low constant passes its input through unchanged
+    pub fn add_buffer(&mut self, input: Net, output: Net) {
+        let low = self.add_constant(LogicState::Low);
+        self.add_gate(Operator::Or, input, low, output);
+    }
+
+    // combines all nets as a balanced tree, the last gate drives output if one is given
+    pub fn add_tree(&mut self, operator: Operator, nets: &[Net], output: Option<Net>) -> Result<Net, String> {
+        match nets {
+
+            [] => return Err(String::from("a gate tree needs at least one input")),
+
+            [net] => match output {
+                Some(output) => {
+                    self.add_buffer(*net, output);
+                    return Ok(output);
+                },
+                None => return Ok(*net),
+            },
+
+            nets => {
+                let (left, right) = nets.split_at(nets.len() / 2);
+                let left = self.add_tree(operator, left, None)?;
+                let right = self.add_tree(operator, right, None)?;
+                let output = output.unwrap_or_else(|| self.add_net());
+                self.add_gate(operator, left, right, output);
+                return Ok(output);
+            },
+        }
+    }
+
+    // selects right if select is high and left otherwise
+    pub fn add_multiplexer(&mut self, left: Net, right: Net, select: Net, output: Net) {
+        let inverted_select = self.add_not(select);
+        let left = self.add_gate_net(Operator::And, left, inverted_select);
+        let right = self.add_gate_net(Operator::And, right, select);
+        self.add_gate(Operator::Or, left, right, output);
+    }
+
+    pub fn add_port(&mut self, name: &str, direction: PortDirection, net: Net) {
+        self.ports.push(NetlistPort {
+            name: String::from(name),
+            direction: direction,
+            net: net,
+        });
+    }
+
+    pub fn add_signal(&mut self, name: &str, nets: Vec<Net>) {
+        self.signals.push(NetlistSignal {
+            name: String::from(name),
+            nets: nets,
+        });
+    }
+
+    // the first signal that contains the net gives it its name
+    pub fn signal_name(&self, net: Net) -> Option<String> {
+        for signal in &self.signals {
+            if let Some(position) = signal.nets.iter().position(|other| *other == net) {
+                match signal.nets.len() {
+                    1 => return Some(signal.name.clone()),
+                    width => return Some(format!("{}[{}]", signal.name, width - position - 1)),
+                }
+            }
+        }
+
+        return None;
+    }
+
+    pub fn net_name(&self, net: Net) -> String {
+        return self.signal_name(net).unwrap_or_else(|| format!("net {}", net));
+    }
+
+    // the indices of the ports that make up a bus named name[index], starting with the most significant bit
+    pub fn bus_ports(&self, name: &str) -> Vec<usize> {
+        let mut bits: Vec<(usize, usize)> = self.ports.iter().enumerate().filter_map(|(index, port)| {
+            let bit = port.name.strip_prefix(name)?.strip_prefix('[')?.strip_suffix(']')?.parse().ok()?;
+            return Some((bit, index));
+        }).collect();
+
+        bits.sort();
+        return bits.into_iter().rev().map(|(_bit, index)| index).collect();
+    }
+
+    pub fn drivers(&self) -> Result<Vec<Option<Driver>>, String> {
+
+        let mut drivers = vec![None; self.net_count];
+        let mut drive = |net: Net, driver: Driver| {
+
+            if net >= self.net_count {
+                return Err(format!("net {} does not exist", net));
+            }
+
+            if drivers[net].is_some() {
+                return Err(format!("{} has multiple drivers", self.net_name(net)));
+            }
+
+            drivers[net] = Some(driver);
+            return Ok(());
+        };
+
+        for (index, gate) in self.gates.iter().enumerate() {
+            drive(gate.output, Driver::Gate(index))?;
+        }
+
+        for (index, register) in self.registers.iter().enumerate() {
+            drive(register.output, Driver::Register(index))?;
+        }
+
+        for (index, constant) in self.constants.iter().enumerate() {
+            drive(constant.output, Driver::Constant(index))?;
+        }
+
+        for (index, port) in self.ports.iter().enumerate() {
+            if port.direction == PortDirection::Input {
+                drive(port.net, Driver::Port(index))?;
+            }
+        }
+
+        return Ok(drivers);
+    }
+
+    pub fn sinks(&self) -> Result<Vec<Vec<Sink>>, String> {
+
+        let mut sinks = vec![Vec::new(); self.net_count];
+        let mut sink = |net: Net, sink: Sink| {
+            match sinks.get_mut(net) {
+                Some(net_sinks) => net_sinks.push(sink),
+                None => return Err(format!("net {} does not exist", net)),
+            }
+            return Ok(());
+        };
+
+        for (index, gate) in self.gates.iter().enumerate() {
+            sink(gate.left, Sink::Gate(index, false))?;
+            sink(gate.right, Sink::Gate(index, true))?;
+        }
+
+        for (index, register) in self.registers.iter().enumerate() {
+            sink(register.input, Sink::Register(index))?;
+        }
+
+        for (index, port) in self.ports.iter().enumerate() {
+            if port.direction == PortDirection::Output {
+                sink(port.net, Sink::Port(index))?;
+            }
+        }
+
+        return Ok(sinks);
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+
+        self.drivers()?;
+        self.sinks()?;
+
+        for signal in &self.signals {
+            if let Some(net) = signal.nets.iter().find(|net| **net >= self.net_count) {
+                return Err(format!("signal {} refers to net {} which does not exist", signal.name, net));
+            }
+        }
+
+        for (index, port) in self.ports.iter().enumerate() {
+            if self.ports[..index].iter().any(|other| other.name == port.name) {
+                return Err(format!("port {} is declared twice", port.name));
+            }
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,25 @@
+// xorshift, which must not start from zero, the same seed always gives the same numbers
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+
+    // neighbouring seeds are scrambled first, otherwise their first numbers are nearly the same
+    pub fn new(seed: u64) -> Self {
+        return Self {
+            state: (seed ^ 0x9e37_79b9_7f4a_7c15).wrapping_mul(0xbf58_476d_1ce4_e5b9).max(1),
+        };
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        return self.state;
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        return (self.next() % bound as u64) as usize;
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::BinaryHeap;
+use std::cmp::Reverse;
+
+// a small cdcl solver with two watched literals, first uip learning and activity based decisions
+// literals are 2 * variable for the positive and 2 * variable + 1 for the negative form
+pub type Literal = usize;
+
+const ACTIVITY_DECAY: f64 = 0.95;
+const ACTIVITY_LIMIT: f64 = 1e100;
+const FIRST_RESTART: usize = 100;
+
+pub fn positive(variable: usize) -> Literal {
+    return variable * 2;
+}
+
+pub fn negative(variable: usize) -> Literal {
+    return variable * 2 + 1;
+}
+
+pub struct Solver {
+    clauses: Vec<Vec<Literal>>,
+    units: Vec<Literal>,
+    empty: bool,
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    phases: Vec<bool>,
+    activities: Vec<f64>,
+    increment: f64,
+    // entries go stale when the activity of their variable changes, they are skipped when popped
+    order: BinaryHeap<(u64, Reverse<usize>)>,
+    trail: Vec<Literal>,
+    trail_limits: Vec<usize>,
+    head: usize,
+}
+
+impl Solver {
+
+    pub fn new() -> Self {
+        return Self {
+            clauses: Vec::new(),
+            units: Vec::new(),
+            empty: false,
+            watches: Vec::new(),
+            values: Vec::new(),
+            levels: Vec::new(),
+            reasons: Vec::new(),
+            phases: Vec::new(),
+            activities: Vec::new(),
+            increment: 1.0,
+            order: BinaryHeap::new(),
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            head: 0,
+        };
+    }
+
+    pub fn add_variable(&mut self) -> usize {
+        let variable = self.values.len();
+        self.watches.extend_from_slice(&[Vec::new(), Vec::new()]);
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.phases.push(false);
+        self.activities.push(0.0);
+        self.order.push((0.0f64.to_bits(), Reverse(variable)));
+        return variable;
+    }
+
+    pub fn add_clause(&mut self, literals: &[Literal]) {
+
+        let mut clause = literals.to_vec();
+        clause.sort();
+        clause.dedup();
+
+        // clauses containing a literal and its negation are always satisfied
+        if clause.windows(2).any(|pair| pair[0] ^ 1 == pair[1]) {
+            return;
+        }
+
+        match clause.len() {
+            0 => self.empty = true,
+            1 => self.units.push(clause[0]),
+            _ => {
+                self.watches[clause[0]].push(self.clauses.len());
+                self.watches[clause[1]].push(self.clauses.len());
+                self.clauses.push(clause);
+            },
+        }
+    }
+
+    // returns a satisfying assignment for every variable, or none if there is no such assignment
+    pub fn solve(mut self) -> Option<Vec<bool>> {
+
+        if self.empty {
+            return None;
+        }
+
+        for unit in self.units.clone() {
+            match self.value(unit) {
+                Some(true) => (),
+                Some(false) => return None,
+                None => self.assign(unit, None),
+            }
+        }
+
+        let mut conflicts = 0;
+        let mut restart = FIRST_RESTART;
+
+        loop {
+
+            match self.propagate() {
+
+                Some(conflict) => {
+
+                    if self.trail_limits.is_empty() {
+                        return None;
+                    }
+
+                    let (learned, level) = self.analyze(conflict);
+                    self.backtrack(level);
+
+                    match learned.len() {
+                        1 => self.assign(learned[0], None),
+                        _ => {
+                            let index = self.clauses.len();
+                            self.watches[learned[0]].push(index);
+                            self.watches[learned[1]].push(index);
+                            self.assign(learned[0], Some(index));
+                            self.clauses.push(learned);
+                        },
+                    }
+
+                    self.increment /= ACTIVITY_DECAY;
+                    conflicts += 1;
+                },
+
+                None => {
+
+                    if conflicts >= restart {
+                        conflicts = 0;
+                        restart += restart / 2;
+                        self.backtrack(0);
+                        continue;
+                    }
+
+                    match self.decide() {
+                        Some(variable) => {
+                            self.trail_limits.push(self.trail.len());
+                            let literal = match self.phases[variable] {
+                                true => positive(variable),
+                                false => negative(variable),
+                            };
+                            self.assign(literal, None);
+                        },
+                        None => return Some(self.values.iter().map(|value| value.unwrap()).collect()),
+                    }
+                },
+            }
+        }
+    }
+
+    fn value(&self, literal: Literal) -> Option<bool> {
+        return self.values[literal / 2].map(|value| value == (literal & 1 == 0));
+    }
+
+    fn assign(&mut self, literal: Literal, reason: Option<usize>) {
+        let variable = literal / 2;
+        self.values[variable] = Some(literal & 1 == 0);
+        self.levels[variable] = self.trail_limits.len();
+        self.reasons[variable] = reason;
+        self.trail.push(literal);
+    }
+
+    fn decide(&mut self) -> Option<usize> {
+        while let Some((activity, Reverse(variable))) = self.order.pop() {
+            if self.values[variable].is_none() && activity == self.activities[variable].to_bits() {
+                return Some(variable);
+            }
+        }
+        return None;
+    }
+
+    fn bump(&mut self, variable: usize) {
+
+        self.activities[variable] += self.increment;
+
+        if self.activities[variable] > ACTIVITY_LIMIT {
+            self.activities.iter_mut().for_each(|activity| *activity /= ACTIVITY_LIMIT);
+            self.increment /= ACTIVITY_LIMIT;
+            self.order = (0..self.values.len()).map(|variable| (self.activities[variable].to_bits(), Reverse(variable))).collect();
+            return;
+        }
+
+        self.order.push((self.activities[variable].to_bits(), Reverse(variable)));
+    }
+
+    fn backtrack(&mut self, level: usize) {
+
+        if self.trail_limits.len() <= level {
+            return;
+        }
+
+        let start = self.trail_limits[level];
+        for literal in self.trail.drain(start..).collect::<Vec<Literal>>() {
+            let variable = literal / 2;
+            self.phases[variable] = literal & 1 == 0;
+            self.values[variable] = None;
+            self.order.push((self.activities[variable].to_bits(), Reverse(variable)));
+        }
+
+        self.trail_limits.truncate(level);
+        self.head = self.trail.len();
+    }
+
+    // returns the clause that is in conflict, if there is one
+    fn propagate(&mut self) -> Option<usize> {
+
+        while self.head < self.trail.len() {
+
+            let false_literal = self.trail[self.head] ^ 1;
+            self.head += 1;
+
+            let watching = std::mem::take(&mut self.watches[false_literal]);
+            let mut kept = Vec::with_capacity(watching.len());
+
+            for (position, clause_index) in watching.iter().enumerate() {
+
+                let clause = &mut self.clauses[*clause_index];
+                if clause[0] == false_literal {
+                    clause.swap(0, 1);
+                }
+
+                let first = clause[0];
+                if self.values[first / 2].map(|value| value == (first & 1 == 0)) == Some(true) {
+                    kept.push(*clause_index);
+                    continue;
+                }
+
+                // move the watch to another literal that is not false
+                let values = &self.values;
+                let other = (2..clause.len()).find(|other| values[clause[*other] / 2].map(|value| value == (clause[*other] & 1 == 0)) != Some(false));
+                if let Some(other) = other {
+                    clause.swap(1, other);
+                    let watched = clause[1];
+                    self.watches[watched].push(*clause_index);
+                    continue;
+                }
+
+                kept.push(*clause_index);
+                match self.value(first) {
+                    Some(false) => {
+                        kept.extend_from_slice(&watching[position + 1..]);
+                        self.watches[false_literal] = kept;
+                        return Some(*clause_index);
+                    },
+                    _ => self.assign(first, Some(*clause_index)),
+                }
+            }
+
+            self.watches[false_literal] = kept;
+        }
+
+        return None;
+    }
+
+    // learns a clause at the first unique implication point, its first literal is the one it asserts
+    // and its second literal has the level to jump back to
+    fn analyze(&mut self, conflict: usize) -> (Vec<Literal>, usize) {
+
+        let level = self.trail_limits.len();
+        let mut seen = vec![false; self.values.len()];
+        let mut learned = vec![0];
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut index = self.trail.len();
+        let mut implied: Option<Literal> = None;
+
+        loop {
+
+            // the first literal of a reason clause is the literal it implied
+            let skip = match implied {
+                Some(_) => 1,
+                None => 0,
+            };
+
+            for position in skip..self.clauses[clause].len() {
+                let literal = self.clauses[clause][position];
+                let variable = literal / 2;
+                if !seen[variable] && self.levels[variable] > 0 {
+                    seen[variable] = true;
+                    self.bump(variable);
+                    match self.levels[variable] == level {
+                        true => pending += 1,
+                        false => learned.push(literal),
+                    }
+                }
+            }
+
+            loop {
+                index -= 1;
+                if seen[self.trail[index] / 2] {
+                    break;
+                }
+            }
+
+            let literal = self.trail[index];
+            seen[literal / 2] = false;
+            pending -= 1;
+            implied = Some(literal);
+
+            if pending == 0 {
+                break;
+            }
+
+            clause = self.reasons[literal / 2].unwrap();
+        }
+
+        learned[0] = implied.unwrap() ^ 1;
+
+        let mut jump = 0;
+        if learned.len() > 1 {
+            let highest = (1..learned.len()).max_by_key(|position| self.levels[learned[*position] / 2]).unwrap();
+            learned.swap(1, highest);
+            jump = self.levels[learned[1] / 2];
+        }
+
+        return (learned, jump);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ Solver, Literal, positive, negative };
+    use super::super::Random;
+
+    fn solve(variables: usize, clauses: &[Vec<Literal>]) -> Option<Vec<bool>> {
+        let mut solver = Solver::new();
+        (0..variables).for_each(|_variable| { solver.add_variable(); });
+        clauses.iter().for_each(|clause| solver.add_clause(clause));
+        return solver.solve();
+    }
+
+    fn satisfies(model: &[bool], clauses: &[Vec<Literal>]) -> bool {
+        return clauses.iter().all(|clause| clause.iter().any(|literal| model[literal / 2] == (literal % 2 == 0)));
+    }
+
+    // every pigeon sits in a hole and no hole holds two pigeons
+    fn pigeonhole(pigeons: usize, holes: usize) -> Vec<Vec<Literal>> {
+        let variable = |pigeon: usize, hole: usize| pigeon * holes + hole;
+        let mut clauses: Vec<Vec<Literal>> = (0..pigeons).map(|pigeon| (0..holes).map(|hole| positive(variable(pigeon, hole))).collect()).collect();
+
+        for hole in 0..holes {
+            for first in 0..pigeons {
+                for second in first + 1..pigeons {
+                    clauses.push(vec![negative(variable(first, hole)), negative(variable(second, hole))]);
+                }
+            }
+        }
+
+        return clauses;
+    }
+
+    #[test]
+    fn known_instances() {
+
+        let (a, b, c) = (0, 1, 2);
+        let satisfiable = vec![vec![positive(a), positive(b)], vec![negative(a), positive(b)], vec![negative(b), positive(c)], vec![negative(c), negative(a)]];
+        let model = solve(3, &satisfiable).unwrap();
+        assert!(satisfies(&model, &satisfiable));
+        assert_eq!(model, vec![false, true, true]);
+
+        let unsatisfiable = vec![vec![positive(a)], vec![negative(a), positive(b)], vec![negative(b), positive(c)], vec![negative(c)]];
+        assert_eq!(solve(3, &unsatisfiable), None);
+
+        // an empty clause can never be satisfied, a tautology always is
+        assert_eq!(solve(1, &[vec![]]), None);
+        assert_eq!(solve(1, &[vec![positive(a), negative(a)]]).map(|model| model.len()), Some(1));
+
+        assert_eq!(solve(6, &pigeonhole(3, 2)), None);
+        assert_eq!(solve(20, &pigeonhole(5, 4)), None);
+
+        let fitting = pigeonhole(3, 3);
+        assert!(satisfies(&solve(9, &fitting).unwrap(), &fitting));
+    }
+
+    // small random instances around the threshold checked against trying every assignment
+    #[test]
+    fn random_instances_match_brute_force() {
+
+        let mut random = Random::new(7);
+        let variables = 10;
+
+        for _instance in 0..200 {
+
+            let clauses: Vec<Vec<Literal>> = (0..43).map(|_clause| (0..3).map(|_literal| random.below(variables) * 2 + random.below(2)).collect()).collect();
+            let brute_force = (0..1usize << variables).any(|bits| satisfies(&(0..variables).map(|variable| (bits >> variable) & 1 == 1).collect::<Vec<bool>>(), &clauses));
+
+            let model = solve(variables, &clauses);
+            assert_eq!(model.is_some(), brute_force);
+            assert!(model.is_none_or(|model| satisfies(&model, &clauses)));
+        }
+    }
+}
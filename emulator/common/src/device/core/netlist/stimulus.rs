@@ -0,0 +1,122 @@
+use super::{ Netlist, PortDirection, Random };
+use super::super::LogicState;
+
+// input values for a number of cycles, columns name either a single port or a bus of ports named name[index]
+#[derive(Clone, Debug)]
+pub struct Stimulus {
+    pub names: Vec<String>,
+    pub cycles: Vec<Vec<String>>,
+}
+
+impl Stimulus {
+
+    // a header line with the column names followed by one line per cycle, values are written with 0, 1, z and x
+    // and buses take one character per bit starting with the most significant one, # starts a comment
+    pub fn parse(source: &str) -> Result<Self, String> {
+
+        let mut names: Option<Vec<String>> = None;
+        let mut cycles = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+
+            let line = line.split('#').next().unwrap();
+            let tokens: Vec<String> = line.split_whitespace().map(String::from).collect();
+            if tokens.is_empty() {
+                continue;
+            }
+
+            match &names {
+                None => names = Some(tokens),
+                Some(names) => {
+                    if tokens.len() != names.len() {
+                        return Err(format!("line {}: expected {} values but found {}", index + 1, names.len(), tokens.len()));
+                    }
+
+                    if let Some(token) = tokens.iter().find(|token| token.chars().any(|character| !"01zx".contains(character))) {
+                        return Err(format!("line {}: invalid value {}, expected 0, 1, z or x", index + 1, token));
+                    }
+
+                    cycles.push(tokens);
+                },
+            }
+        }
+
+        return Ok(Self {
+            names: names.ok_or_else(|| String::from("the stimulus has no header"))?,
+            cycles: cycles,
+        });
+    }
+
+    // random values for every input port, the same seed always gives the same stimulus
+    pub fn random(netlist: &Netlist, cycles: usize, seed: u64) -> Self {
+
+        let names: Vec<String> = netlist.ports.iter().filter(|port| port.direction == PortDirection::Input).map(|port| port.name.clone()).collect();
+
+        let mut random = Random::new(seed);
+        let cycles = (0..cycles).map(|_cycle| names.iter().map(|_name| match random.below(2) {
+            1 => String::from("1"),
+            _ => String::from("0"),
+        }).collect()).collect();
+
+        return Self {
+            names: names,
+            cycles: cycles,
+        };
+    }
+
+    // the input port indices of every column, most significant bit first
+    fn columns(&self, netlist: &Netlist) -> Result<Vec<Vec<usize>>, String> {
+
+        let inputs: Vec<(usize, &str)> = netlist.ports.iter().enumerate().filter(|(_index, port)| port.direction == PortDirection::Input).map(|(index, port)| (index, port.name.as_str())).collect();
+
+        let mut columns = Vec::new();
+        for name in &self.names {
+
+            if let Some((index, _name)) = inputs.iter().find(|(_index, other)| other == name) {
+                columns.push(vec![*index]);
+                continue;
+            }
+
+            let bits: Vec<usize> = netlist.bus_ports(name).into_iter().filter(|index| netlist.ports[*index].direction == PortDirection::Input).collect();
+            if bits.is_empty() {
+                return Err(format!("{} has no input port {}", netlist.name, name));
+            }
+
+            columns.push(bits);
+        }
+
+        return Ok(columns);
+    }
+
+    // the port states to apply in every cycle
+    pub fn resolve(&self, netlist: &Netlist) -> Result<Vec<Vec<(usize, LogicState)>>, String> {
+
+        let columns = self.columns(netlist)?;
+
+        let mut resolved = Vec::new();
+        for (cycle, values) in self.cycles.iter().enumerate() {
+
+            let mut states = Vec::new();
+            for ((name, ports), value) in self.names.iter().zip(columns.iter()).zip(values.iter()) {
+
+                if value.len() != ports.len() {
+                    return Err(format!("cycle {}: {} has {} bits but the value {} has {}", cycle, name, ports.len(), value, value.len()));
+                }
+
+                for (port, character) in ports.iter().zip(value.chars()) {
+                    let state = match character {
+                        '0' => LogicState::Low,
+                        '1' => LogicState::High,
+                        'z' => LogicState::Floating,
+                        _ => LogicState::Metastable,
+                    };
+                    states.push((*port, state));
+                }
+            }
+
+            resolved.push(states);
+        }
+
+        return Ok(resolved);
+    }
+}
@@ -0,0 +1,369 @@
+use super::{ Netlist, Net, Driver, Sink, PortDirection };
+use super::super::Operator;
+use std::fmt;
+
+// gate delays in arbitrary units, by default every gate takes one unit so delays count levels
+#[derive(Clone, Debug)]
+pub struct Delays {
+    pub and: f64,
+    pub or: f64,
+    pub xor: f64,
+    pub gates: Vec<(usize, f64)>,
+}
+
+impl Delays {
+
+    pub fn unit() -> Self {
+        return Self {
+            and: 1.0,
+            or: 1.0,
+            xor: 1.0,
+            gates: Vec::new(),
+        };
+    }
+
+    // a comma separated list like "and=1,or=1.5,xor=2,gate4=3", anything not listed keeps a delay of one
+    pub fn parse(text: &str) -> Result<Self, String> {
+
+        let mut delays = Self::unit();
+        for entry in text.split(',') {
+
+            let (name, value) = entry.split_once('=').ok_or_else(|| format!("invalid delay {}, expected name=delay", entry))?;
+            let delay: f64 = value.trim().parse().map_err(|_| format!("invalid delay {}", value))?;
+            if !delay.is_finite() {
+                return Err(format!("delay {} is not finite", value));
+            }
+            if delay < 0.0 {
+                return Err(format!("delay {} is negative", value));
+            }
+
+            match name.trim() {
+                "and" => delays.and = delay,
+                "or" => delays.or = delay,
+                "xor" => delays.xor = delay,
+                other => {
+                    let index = other.strip_prefix("gate").and_then(|index| index.parse().ok()).ok_or_else(|| format!("unknown delay {}, expected and, or, xor or gate<index>", other))?;
+                    delays.gates.push((index, delay));
+                },
+            }
+        }
+
+        return Ok(delays);
+    }
+
+    pub fn gate(&self, index: usize, operator: Operator) -> f64 {
+        if let Some((_index, delay)) = self.gates.iter().rev().find(|(gate, _delay)| *gate == index) {
+            return *delay;
+        }
+
+        match operator {
+            Operator::And => return self.and,
+            Operator::Or => return self.or,
+            Operator::Xor => return self.xor,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TimingPath {
+    pub start: String,
+    pub end: String,
+    pub elements: Vec<String>,
+    pub levels: usize,
+    pub delay: f64,
+    pub required: Option<f64>,
+}
+
+impl TimingPath {
+
+    pub fn slack(&self) -> Option<f64> {
+        return self.required.map(|required| required - self.delay);
+    }
+
+    pub fn violates(&self) -> bool {
+        return self.slack().is_some_and(|slack| slack < 0.0);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TimingReport {
+    pub clock_period: Option<f64>,
+    // the slowest paths come first
+    pub paths: Vec<TimingPath>,
+}
+
+impl TimingReport {
+
+    pub fn critical_path(&self) -> Option<&TimingPath> {
+        return self.paths.first();
+    }
+
+    pub fn max_levels(&self) -> usize {
+        return self.paths.iter().map(|path| path.levels).max().unwrap_or(0);
+    }
+
+    pub fn violations(&self) -> Vec<&TimingPath> {
+        return self.paths.iter().filter(|path| path.violates()).collect();
+    }
+}
+
+impl fmt::Display for TimingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        let critical = match self.critical_path() {
+            Some(critical) => critical,
+            None => return write!(f, "no paths through logic"),
+        };
+
+        write!(f, "{} paths, at most {} levels", self.paths.len(), self.max_levels())?;
+        write!(f, "\n  critical path from {} to {}, {} levels, delay {}", critical.start, critical.end, critical.levels, critical.delay)?;
+        for element in &critical.elements {
+            write!(f, "\n    {}", element)?;
+        }
+
+        if let Some(period) = self.clock_period {
+            let violations = self.violations();
+            match violations.len() {
+                0 => write!(f, "\n  all paths meet the clock period of {}", period)?,
+                count => write!(f, "\n  {} paths exceed the clock period of {}", count, period)?,
+            }
+
+            for path in violations {
+                write!(f, "\n    {} to {}, delay {} of {}, slack {}", path.start, path.end, path.delay, path.required.unwrap(), path.slack().unwrap())?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+// where a path starts or ends, with the edge it is clocked on
+struct Point {
+    name: String,
+    net: Net,
+    rising: bool,
+}
+
+#[derive(Copy, Clone)]
+struct Arrival {
+    delay: f64,
+    levels: usize,
+    // the gate input the slowest path arrives through
+    previous: Option<Net>,
+}
+
+impl Netlist {
+
+    // unnamed registers, like the first half of a flip flop, are named after the register they feed
+    fn register_point_names(&self, sinks: &[Vec<Sink>]) -> Vec<String> {
+        return self.registers.iter().enumerate().map(|(index, register)| {
+            if let Some(name) = self.signal_name(register.output) {
+                return format!("register {}", name);
+            }
+
+            let fed = sinks[register.output].iter().find_map(|sink| match sink {
+                Sink::Register(other) => self.signal_name(self.registers[*other].output),
+                _ => None,
+            });
+
+            match fed {
+                Some(name) => return format!("register {} before {}", index, name),
+                None => return format!("register {}", index),
+            }
+        }).collect();
+    }
+
+    pub(super) fn gate_element_name(&self, index: usize) -> String {
+        let operator = match self.gates[index].operator {
+            Operator::And => "and",
+            Operator::Or => "or",
+            Operator::Xor => "xor",
+        };
+
+        match self.signal_name(self.gates[index].output) {
+            Some(name) => return format!("{} gate {} driving {}", operator, index, name),
+            None => return format!("{} gate {}", operator, index),
+        }
+    }
+
+    // gates ordered so that every gate comes after the gates driving its inputs
+    pub(super) fn gate_order(&self, drivers: &[Option<Driver>]) -> Result<Vec<usize>, String> {
+
+        let mut order = Vec::with_capacity(self.gates.len());
+        // 0 is unvisited, 1 is on the current path and 2 is done
+        let mut marks = vec![0u8; self.gates.len()];
+
+        for root in 0..self.gates.len() {
+
+            let mut pending = vec![(root, false)];
+            while let Some((gate, expanded)) = pending.pop() {
+
+                if expanded {
+                    marks[gate] = 2;
+                    order.push(gate);
+                    continue;
+                }
+
+                match marks[gate] {
+                    2 => continue,
+                    1 => return Err(format!("{} is part of a combinational loop", self.gate_element_name(gate))),
+                    _ => marks[gate] = 1,
+                }
+
+                pending.push((gate, true));
+                for input in &[self.gates[gate].left, self.gates[gate].right] {
+                    if let Some(Driver::Gate(previous)) = drivers[*input] {
+                        match marks[previous] {
+                            1 => return Err(format!("{} is part of a combinational loop", self.gate_element_name(previous))),
+                            0 => pending.push((previous, false)),
+                            _ => (),
+                        }
+                    }
+                }
+            }
+        }
+
+        return Ok(order);
+    }
+
+    // finds the slowest path between every pair of registers and ports that is connected through logic,
+    // input and output ports count as rising edge registers and paths between opposite edges get half a clock period
+    pub fn timing(&self, delays: &Delays, clock_period: Option<f64>) -> Result<TimingReport, String> {
+
+        if let Some(period) = clock_period.filter(|period| !period.is_finite() || *period <= 0.0) {
+            return Err(format!("invalid clock period {}", period));
+        }
+
+        let drivers = self.drivers()?;
+        let order = self.gate_order(&drivers)?;
+        let names = self.register_point_names(&self.sinks()?);
+
+        let mut starts = Vec::new();
+        let mut ends = Vec::new();
+
+        for (index, register) in self.registers.iter().enumerate() {
+            starts.push(Point { name: names[index].clone(), net: register.output, rising: register.rising });
+            ends.push(Point { name: names[index].clone(), net: register.input, rising: register.rising });
+        }
+
+        for port in &self.ports {
+            match port.direction {
+                PortDirection::Input => starts.push(Point { name: format!("input {}", port.name), net: port.net, rising: true }),
+                PortDirection::Output => ends.push(Point { name: format!("output {}", port.name), net: port.net, rising: true }),
+            }
+        }
+
+        let mut paths = Vec::new();
+        for start in &starts {
+
+            let mut arrivals: Vec<Option<Arrival>> = vec![None; self.net_count];
+            arrivals[start.net] = Some(Arrival { delay: 0.0, levels: 0, previous: None });
+
+            for gate in &order {
+                let gate_index = *gate;
+                let gate = &self.gates[gate_index];
+
+                let inputs = [(gate.left, arrivals[gate.left]), (gate.right, arrivals[gate.right])];
+                let slowest = inputs.iter().filter_map(|(net, arrival)| arrival.map(|arrival| (*net, arrival))).fold(None, |slowest: Option<(Net, Arrival)>, (net, arrival)| match slowest {
+                    Some((_net, other)) if other.delay >= arrival.delay => slowest,
+                    _ => Some((net, arrival)),
+                });
+
+                if let Some((net, arrival)) = slowest {
+                    let levels = inputs.iter().filter_map(|(_net, arrival)| arrival.map(|arrival| arrival.levels)).max().unwrap();
+                    arrivals[gate.output] = Some(Arrival {
+                        delay: arrival.delay + delays.gate(gate_index, gate.operator),
+                        levels: levels + 1,
+                        previous: Some(net),
+                    });
+                }
+            }
+
+            for end in &ends {
+
+                // paths without any logic in between, like the two halves of a flip flop, are left out
+                let arrival = match arrivals[end.net] {
+                    Some(arrival) if arrival.levels > 0 => arrival,
+                    _ => continue,
+                };
+
+                let mut elements = vec![end.name.clone()];
+                let mut net = end.net;
+                while let Some(previous) = arrivals[net].and_then(|arrival| arrival.previous) {
+                    if let Some(Driver::Gate(index)) = drivers[net] {
+                        elements.push(self.gate_element_name(index));
+                    }
+                    net = previous;
+                }
+                elements.push(start.name.clone());
+                elements.reverse();
+
+                let required = clock_period.map(|period| match start.rising == end.rising {
+                    true => period,
+                    false => period / 2.0,
+                });
+
+                paths.push(TimingPath {
+                    start: start.name.clone(),
+                    end: end.name.clone(),
+                    elements: elements,
+                    levels: arrival.levels,
+                    delay: arrival.delay,
+                    required: required,
+                });
+            }
+        }
+
+        // paths are ordered by slack if there is a clock period, otherwise by delay
+        paths.sort_by(|first, second| {
+            let key = |path: &TimingPath| path.slack().map_or(-path.delay, |slack| slack);
+            return key(first).total_cmp(&key(second)).then(second.levels.cmp(&first.levels));
+        });
+
+        return Ok(TimingReport {
+            clock_period: clock_period,
+            paths: paths,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::Netlist;
+    use super::Delays;
+
+    // three gates between a and y, one between b and z
+    const CHAIN: &str = "
+        module chain(input a, input b, input c, output y, output z);
+            wire n1, n2;
+            and g1 (n1, a, b);
+            or g2 (n2, n1, c);
+            xor g3 (y, n2, a);
+            and g4 (z, b, c);
+        endmodule
+    ";
+
+    #[test]
+    fn chain_levels_and_critical_path() {
+
+        let netlist = Netlist::from_verilog(CHAIN).unwrap();
+        let report = netlist.timing(&Delays::unit(), None).unwrap();
+
+        // only the slowest path between each start and end is reported
+        let paths: Vec<(&str, &str, usize)> = report.paths.iter().map(|path| (path.start.as_str(), path.end.as_str(), path.levels)).collect();
+        assert_eq!(paths, vec![("input a", "output y", 3), ("input b", "output y", 3), ("input c", "output y", 2), ("input b", "output z", 1), ("input c", "output z", 1)]);
+        assert_eq!(report.max_levels(), 3);
+
+        let critical = report.critical_path().unwrap();
+        assert_eq!(critical.delay, 3.0);
+        assert_eq!(critical.elements, vec!["input a", "and gate 0 driving n1", "or gate 1 driving n2", "xor gate 2 driving y", "output y"]);
+
+        let report = netlist.timing(&Delays::parse("and=2,or=1,xor=4,gate3=0.5").unwrap(), Some(5.0)).unwrap();
+        let delays: Vec<f64> = report.paths.iter().map(|path| path.delay).collect();
+        assert_eq!(delays, vec![7.0, 7.0, 5.0, 0.5, 0.5]);
+
+        let violations: Vec<(&str, Option<f64>)> = report.violations().iter().map(|path| (path.start.as_str(), path.slack())).collect();
+        assert_eq!(violations, vec![("input a", Some(-2.0)), ("input b", Some(-2.0))]);
+    }
+}
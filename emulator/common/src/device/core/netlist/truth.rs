@@ -0,0 +1,262 @@
+use super::{ Netlist, Net, Driver, PortDirection };
+use super::super::LogicState;
+use std::fmt;
+
+// the number of rows grows exponentially with the number of inputs, so larger tables are refused
+pub const TRUTH_TABLE_LIMIT: usize = 1 << 16;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TableFormat {
+    Text,
+    Csv,
+    Markdown,
+}
+
+impl TableFormat {
+
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "text" => return Ok(TableFormat::Text),
+            "csv" => return Ok(TableFormat::Csv),
+            "markdown" => return Ok(TableFormat::Markdown),
+            other => return Err(format!("unknown table format {}, expected text, csv or markdown", other)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TruthTable {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    // the input states of every row followed by the output states they give
+    pub rows: Vec<(Vec<LogicState>, Vec<LogicState>)>,
+}
+
+impl TruthTable {
+
+    fn character(state: &LogicState) -> char {
+        match state {
+            LogicState::High => return '1',
+            LogicState::Low => return '0',
+            LogicState::Floating => return 'z',
+            LogicState::Metastable => return 'x',
+        }
+    }
+
+    fn cells(&self) -> Vec<Vec<String>> {
+        return self.rows.iter().map(|(inputs, outputs)| inputs.iter().chain(outputs.iter()).map(|state| Self::character(state).to_string()).collect()).collect();
+    }
+
+    pub fn format(&self, format: TableFormat) -> String {
+        match format {
+            TableFormat::Text => return self.to_string(),
+            TableFormat::Csv => return self.to_csv(),
+            TableFormat::Markdown => return self.to_markdown(),
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        let header: Vec<String> = self.inputs.iter().chain(self.outputs.iter()).map(|name| Self::quote(name)).collect();
+        csv.push_str(&header.join(","));
+        csv.push('\n');
+
+        for cells in self.cells() {
+            csv.push_str(&cells.join(","));
+            csv.push('\n');
+        }
+
+        return csv;
+    }
+
+    // names may contain commas, like buses written as name[1,0] by some tools
+    fn quote(name: &str) -> String {
+        match name.contains([',', '"']) {
+            true => return format!("\"{}\"", name.replace('"', "\"\"")),
+            false => return String::from(name),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        let names: Vec<&String> = self.inputs.iter().chain(self.outputs.iter()).collect();
+        markdown.push_str(&format!("| {} |\n", names.iter().map(|name| name.replace('|', "\\|")).collect::<Vec<String>>().join(" | ")));
+        markdown.push_str(&format!("|{}\n", ":-:|".repeat(names.len())));
+
+        for cells in self.cells() {
+            markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+
+        return markdown;
+    }
+}
+
+// inputs and outputs are separated by a bar and every column is as wide as its name
+impl fmt::Display for TruthTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        let line = |cells: Vec<&str>| {
+            let (inputs, outputs) = cells.split_at(self.inputs.len());
+            let columns = |cells: &[&str], names: &Vec<String>| cells.iter().zip(names.iter()).map(|(cell, name)| format!("{:width$}", cell, width = name.len())).collect::<Vec<String>>().join(" ");
+            return format!("{} | {}", columns(inputs, &self.inputs), columns(outputs, &self.outputs)).trim_end().to_string();
+        };
+
+        write!(f, "{}", line(self.inputs.iter().chain(self.outputs.iter()).map(String::as_str).collect()))?;
+        for cells in self.cells() {
+            write!(f, "\n{}", line(cells.iter().map(String::as_str).collect()))?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl Netlist {
+
+    // a port, a signal or a single bit of a signal, buses give one name per bit starting with the most significant one
+    fn resolve_name(&self, name: &str) -> Result<Vec<(String, Net)>, String> {
+
+        if let Some(port) = self.ports.iter().find(|port| port.name == name) {
+            return Ok(vec![(port.name.clone(), port.net)]);
+        }
+
+        if let Some(signal) = self.signals.iter().find(|signal| signal.name == name) {
+            return Ok(signal.nets.iter().map(|net| (self.net_name(*net), *net)).collect());
+        }
+
+        if let Some(net) = (0..self.net_count).find(|net| self.signal_name(*net).as_deref() == Some(name)) {
+            return Ok(vec![(String::from(name), net)]);
+        }
+
+        let bits = self.bus_ports(name);
+        if bits.is_empty() {
+            return Err(format!("{} has no port or signal {}", self.name, name));
+        }
+
+        return Ok(bits.into_iter().map(|index| (self.ports[index].name.clone(), self.ports[index].net)).collect());
+    }
+
+    fn resolve_names(&self, names: &[String], direction: PortDirection) -> Result<Vec<(String, Net)>, String> {
+
+        // without a selection every port of the direction is used
+        if names.is_empty() {
+            return Ok(self.ports.iter().filter(|port| port.direction == direction).map(|port| (port.name.clone(), port.net)).collect());
+        }
+
+        let mut resolved = Vec::new();
+        for name in names {
+            resolved.extend(self.resolve_name(name)?);
+        }
+        return Ok(resolved);
+    }
+
+    // enumerates every combination of the inputs through the gates, optionally including floating and metastable inputs,
+    // inputs may also be internal nets, which cuts them off from whatever drives them
+    pub fn truth_table(&self, inputs: &[String], outputs: &[String], unknown: bool) -> Result<TruthTable, String> {
+
+        let drivers = self.drivers()?;
+        let order = self.gate_order(&drivers)?;
+        let inputs = self.resolve_names(inputs, PortDirection::Input)?;
+        let outputs = self.resolve_names(outputs, PortDirection::Output)?;
+
+        let mut is_input = vec![false; self.net_count];
+        for (name, net) in &inputs {
+            if is_input[*net] {
+                return Err(format!("{} is selected as an input more than once", name));
+            }
+            is_input[*net] = true;
+        }
+
+        // every register and input port the outputs depend on has to be one of the inputs
+        let mut visited = vec![false; self.net_count];
+        for (name, net) in &outputs {
+            let mut pending = vec![*net];
+            while let Some(net) = pending.pop() {
+
+                if visited[net] || is_input[net] {
+                    continue;
+                }
+                visited[net] = true;
+
+                match drivers[net] {
+                    Some(Driver::Gate(index)) => pending.extend_from_slice(&[self.gates[index].left, self.gates[index].right]),
+                    Some(Driver::Register(..)) | Some(Driver::Port(..)) => return Err(format!("{} depends on {}, which is not one of the inputs", name, self.net_name(net))),
+                    _ => (),
+                }
+            }
+        }
+
+        let states: &[LogicState] = match unknown {
+            true => &[LogicState::Low, LogicState::High, LogicState::Floating, LogicState::Metastable],
+            false => &[LogicState::Low, LogicState::High],
+        };
+
+        let count = states.len().checked_pow(inputs.len() as u32).filter(|count| *count <= TRUTH_TABLE_LIMIT).ok_or_else(|| match unknown {
+            true => format!("{} inputs with floating and metastable states give more than {} rows, select fewer inputs or leave out unknown states", inputs.len(), TRUTH_TABLE_LIMIT),
+            false => format!("{} inputs give more than {} rows, select fewer inputs", inputs.len(), TRUTH_TABLE_LIMIT),
+        })?;
+
+        // nets without a driver float, just like they do in the emulator
+        let mut nets = vec![LogicState::Floating; self.net_count];
+        self.constants.iter().for_each(|constant| nets[constant.output] = constant.state);
+
+        let mut rows = Vec::with_capacity(count);
+        for row in 0..count {
+
+            // the first input changes slowest
+            let mut remaining = row;
+            let mut values = vec![LogicState::Floating; inputs.len()];
+            for (position, (_name, net)) in inputs.iter().enumerate().rev() {
+                values[position] = states[remaining % states.len()];
+                nets[*net] = values[position];
+                remaining /= states.len();
+            }
+
+            for index in &order {
+                let gate = &self.gates[*index];
+                if !is_input[gate.output] {
+                    nets[gate.output] = nets[gate.left].operator(nets[gate.right], gate.operator);
+                }
+            }
+
+            rows.push((values, outputs.iter().map(|(_name, net)| nets[*net]).collect()));
+        }
+
+        return Ok(TruthTable {
+            inputs: inputs.into_iter().map(|(name, _net)| name).collect(),
+            outputs: outputs.into_iter().map(|(name, _net)| name).collect(),
+            rows: rows,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::Netlist;
+    use super::{ TruthTable, TableFormat };
+
+    const HALF_ADDER: &str = "
+        module half_adder(input a, input b, output sum, output carry);
+            assign sum = a ^ b;
+            assign carry = a & b;
+        endmodule
+    ";
+
+    #[test]
+    fn half_adder() {
+
+        let netlist = Netlist::from_verilog(HALF_ADDER).unwrap();
+        let table = netlist.truth_table(&[], &[], false).unwrap();
+        assert_eq!(table.to_string(), "a b | sum carry\n0 0 | 0   0\n0 1 | 1   0\n1 0 | 1   0\n1 1 | 0   1");
+        assert_eq!(table.format(TableFormat::Csv), "a,b,sum,carry\n0,0,0,0\n0,1,1,0\n1,0,1,0\n1,1,0,1\n");
+        assert_eq!(table.format(TableFormat::Markdown), "| a | b | sum | carry |\n|:-:|:-:|:-:|:-:|\n| 0 | 0 | 0 | 0 |\n| 0 | 1 | 1 | 0 |\n| 1 | 0 | 1 | 0 |\n| 1 | 1 | 0 | 1 |\n");
+
+        // gates let floating win over metastable, and an unknown input makes the output unknown even next to a low one
+        let table = netlist.truth_table(&[String::from("a"), String::from("b")], &[String::from("carry")], true).unwrap();
+        let carry: String = table.rows.iter().map(|(_inputs, outputs)| TruthTable::character(&outputs[0])).collect();
+        assert_eq!(carry, "00zx01zxzzzzxxzx");
+
+        let error = netlist.truth_table(&[String::from("a")], &[String::from("carry")], false).unwrap_err();
+        assert_eq!(error, "carry depends on b, which is not one of the inputs");
+    }
+}
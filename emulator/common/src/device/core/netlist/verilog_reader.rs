@@ -0,0 +1,1286 @@
+use std::collections::HashMap;
+use super::super::{ LogicState, Operator };
+use super::verilog_writer::KEYWORDS;
+use super::{ Netlist, Net, PortDirection, Driver };
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Location {
+    line: usize,
+    column: usize,
+}
+
+impl Location {
+
+    fn error<T>(&self, message: String) -> Result<T, String> {
+        return Err(format!("line {} column {}: {}", self.line, self.column, message));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Identifier(String),
+    Number(String),
+    Symbol(&'static str),
+    End,
+}
+
+const SYMBOLS: [&str; 25] = ["<=", "(", ")", "[", "]", ";", ",", ":", "=", "@", "&", "|", "^", "~", "{", "}", "?", "!", "+", "-", "*", "#", ".", "<", ">"];
+
+enum Expression {
+    Reference(Location, String, Option<usize>),
+    Literal(Location, Vec<LogicState>, bool),
+    Not(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+    Reduce(Operator, Box<Expression>),
+}
+
+struct Target {
+    location: Location,
+    name: String,
+    index: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct Declaration {
+    location: Location,
+    name: String,
+    direction: Option<PortDirection>,
+    reg: bool,
+    range: Option<(usize, usize)>,
+}
+
+enum Item {
+    Assign(Target, Expression),
+    Primitive(Location, String, Vec<Expression>),
+    Always(Location, bool, String, Vec<(Target, Expression)>),
+    Initial(Target, Expression),
+}
+
+struct Module {
+    name: String,
+    ports: Vec<(String, Location)>,
+    declarations: Vec<Declaration>,
+    items: Vec<Item>,
+}
+
+// the name of the declaration and the offset from its least significant bit
+type Bit = (String, usize);
+
+enum Source {
+    Bit(Bit),
+    Constant(LogicState),
+}
+
+struct Flop {
+    rising: bool,
+    initial: LogicState,
+    input: Net,
+    output: Net,
+}
+
+struct Parser {
+    tokens: Vec<(Token, Location)>,
+    position: usize,
+}
+
+struct VerilogImporter {
+    netlist: Netlist,
+    declarations: HashMap<String, Declaration>,
+    order: Vec<String>,
+    clock: Option<String>,
+    aliases: HashMap<Bit, (Source, Location)>,
+    driven: HashMap<Bit, Location>,
+    initial: HashMap<Bit, LogicState>,
+    nets: HashMap<Bit, Net>,
+    flops: Vec<Flop>,
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Identifier(name) => return format!("identifier {}", name),
+        Token::Number(number) => return format!("number {}", number),
+        Token::Symbol(symbol) => return format!("`{}`", symbol),
+        Token::End => return String::from("end of file"),
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, Location)>, String> {
+
+    let characters: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    let mut location = Location { line: 1, column: 1 };
+
+    // moves past count characters while keeping track of the location
+    let advance = |index: &mut usize, location: &mut Location, count: usize| {
+        for _step in 0..count {
+            match characters.get(*index) {
+                Some('\n') => *location = Location { line: location.line + 1, column: 1 },
+                _other => location.column += 1,
+            }
+            *index += 1;
+        }
+    };
+
+    let word_length = |start: usize, accept: &dyn Fn(char) -> bool| characters[start..].iter().take_while(|character| accept(**character)).count();
+
+    while index < characters.len() {
+
+        let character = characters[index];
+        let next = characters.get(index + 1).cloned();
+        let start = location;
+
+        if character.is_whitespace() {
+            advance(&mut index, &mut location, 1);
+            continue;
+        }
+
+        if character == '/' && next == Some('/') {
+            let length = characters[index..].iter().take_while(|character| **character != '\n').count();
+            advance(&mut index, &mut location, length);
+            continue;
+        }
+
+        if character == '/' && next == Some('*') {
+            let end = (index + 2..characters.len()).find(|end| characters[*end - 1] == '*' && characters[*end] == '/' && *end > index + 2);
+            match end {
+                Some(end) => {
+                    let length = end + 1 - index;
+                    advance(&mut index, &mut location, length);
+                },
+                None => return start.error(String::from("unterminated comment")),
+            }
+            continue;
+        }
+
+        if character.is_ascii_alphabetic() || character == '_' {
+            let length = word_length(index, &|character: char| character.is_ascii_alphanumeric() || character == '_' || character == '$');
+            tokens.push((Token::Identifier(characters[index..index + length].iter().collect()), start));
+            advance(&mut index, &mut location, length);
+            continue;
+        }
+
+        // escaped identifiers end at the next white space
+        if character == '\\' {
+            let length = word_length(index + 1, &|character: char| !character.is_whitespace());
+            tokens.push((Token::Identifier(characters[index + 1..index + 1 + length].iter().collect()), start));
+            advance(&mut index, &mut location, length + 1);
+            continue;
+        }
+
+        // plain numbers and literals like 4'b10x1, the digits of based literals may contain x and z
+        if character.is_ascii_digit() || character == '\'' {
+            let mut length = word_length(index, &|character: char| character.is_ascii_digit() || character == '_');
+
+            if characters.get(index + length) == Some(&'\'') {
+                length += 2;
+                length += word_length(index + length, &|character: char| character.is_ascii_hexdigit() || "xXzZ_?".contains(character));
+            }
+
+            tokens.push((Token::Number(characters[index..(index + length).min(characters.len())].iter().collect()), start));
+            advance(&mut index, &mut location, length);
+            continue;
+        }
+
+        if character == '`' {
+            return start.error(String::from("compiler directives are not supported"));
+        }
+
+        let symbol = SYMBOLS.iter().find(|symbol| symbol.chars().enumerate().all(|(offset, symbol)| characters.get(index + offset) == Some(&symbol)));
+        match symbol {
+            Some(symbol) => {
+                tokens.push((Token::Symbol(symbol), start));
+                advance(&mut index, &mut location, symbol.len());
+            },
+            None => return start.error(format!("unexpected character {}", character)),
+        }
+    }
+
+    tokens.push((Token::End, location));
+    return Ok(tokens);
+}
+
+// literals are returned with their most significant bit first, unsized literals are extended where they are used
+fn parse_literal(text: &str, location: Location) -> Result<(Vec<LogicState>, bool), String> {
+
+    let text = text.replace('_', "");
+    let invalid = || location.error(format!("invalid literal {}", text));
+
+    let (size, base, digits) = match text.split_once('\'') {
+        Some((size, rest)) => {
+            let mut rest = rest.chars();
+            let base = rest.next().map(|base| base.to_ascii_lowercase());
+            (Some(size), base, rest.collect::<String>())
+        },
+        None => (None, Some('d'), text.clone()),
+    };
+
+    let bits_per_digit = match base {
+        Some('b') => 1,
+        Some('o') => 3,
+        Some('h') => 4,
+        Some('d') => 0,
+        _other => return invalid(),
+    };
+
+    if digits.is_empty() {
+        return invalid();
+    }
+
+    let mut states = Vec::new();
+    if bits_per_digit == 0 {
+        let mut value: u64 = match digits.parse() {
+            Ok(value) => value,
+            Err(..) => return invalid(),
+        };
+
+        while value > 0 || states.is_empty() {
+            states.insert(0, LogicState::from_boolean(value & 1 == 1));
+            value >>= 1;
+        }
+    } else {
+        for digit in digits.chars() {
+            let state = match digit.to_ascii_lowercase() {
+                'x' => Some(LogicState::Metastable),
+                'z' | '?' => Some(LogicState::Floating),
+                _other => None,
+            };
+
+            match (state, digit.to_digit(1 << bits_per_digit)) {
+                (Some(state), _value) => states.extend(vec![state; bits_per_digit]),
+                (None, Some(value)) => states.extend((0..bits_per_digit).rev().map(|bit| LogicState::from_boolean(value >> bit & 1 == 1))),
+                (None, None) => return invalid(),
+            }
+        }
+    }
+
+    let size = match size {
+        Some("") | None => return Ok((states, false)),
+        Some(size) => match size.parse::<usize>() {
+            Ok(size) if size > 0 => size,
+            _other => return invalid(),
+        },
+    };
+
+    // sized literals are padded with zeros, or with x and z if that is their leftmost digit
+    while states.len() > size {
+        match states[0] {
+            LogicState::Low => { states.remove(0); },
+            _other => return location.error(format!("literal {} does not fit into {} bits", text, size)),
+        }
+    }
+
+    let padding = match states[0] {
+        LogicState::Metastable | LogicState::Floating => states[0],
+        _other => LogicState::Low,
+    };
+
+    while states.len() < size {
+        states.insert(0, padding);
+    }
+
+    return Ok((states, true));
+}
+
+impl Parser {
+
+    fn peek(&self) -> &Token {
+        return &self.tokens[self.position].0;
+    }
+
+    fn location(&self) -> Location {
+        return self.tokens[self.position].1;
+    }
+
+    fn next(&mut self) -> (Token, Location) {
+        let token = self.tokens[self.position].clone();
+        if token.0 != Token::End {
+            self.position += 1;
+        }
+        return token;
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T, String> {
+        return self.location().error(format!("expected {} but found {}", expected, describe(self.peek())));
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        match self.peek() {
+            Token::Symbol(other) => return *other == symbol,
+            _other => return false,
+        }
+    }
+
+    fn accept_symbol(&mut self, symbol: &str) -> bool {
+        if self.is_symbol(symbol) {
+            self.next();
+            return true;
+        }
+        return false;
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), String> {
+        if self.accept_symbol(symbol) {
+            return Ok(());
+        }
+        return self.unexpected(&format!("`{}`", symbol));
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        return *self.peek() == Token::Identifier(String::from(keyword));
+    }
+
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.next();
+            return true;
+        }
+        return false;
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.accept_keyword(keyword) {
+            return Ok(());
+        }
+        return self.unexpected(keyword);
+    }
+
+    fn expect_identifier(&mut self) -> Result<(String, Location), String> {
+        match self.peek().clone() {
+            Token::Identifier(name) if !KEYWORDS.contains(&name.as_str()) => {
+                let (_token, location) = self.next();
+                return Ok((name, location));
+            },
+            _other => return self.unexpected("an identifier"),
+        }
+    }
+
+    fn expect_number(&mut self) -> Result<usize, String> {
+        match self.peek().clone() {
+            Token::Number(number) => match number.parse() {
+                Ok(number) => {
+                    self.next();
+                    return Ok(number);
+                },
+                Err(..) => return self.location().error(format!("expected a plain number but found {}", number)),
+            },
+            _other => return self.unexpected("a number"),
+        }
+    }
+
+    fn parse_range(&mut self) -> Result<Option<(usize, usize)>, String> {
+
+        if !self.is_symbol("[") {
+            return Ok(None);
+        }
+
+        let location = self.location();
+        self.next();
+        let msb = self.expect_number()?;
+        self.expect_symbol(":")?;
+        let lsb = self.expect_number()?;
+        self.expect_symbol("]")?;
+
+        if msb < lsb {
+            return location.error(String::from("ascending ranges are not supported"));
+        }
+
+        return Ok(Some((msb, lsb)));
+    }
+
+    fn parse_direction(&mut self) -> Option<PortDirection> {
+        if self.accept_keyword("input") {
+            return Some(PortDirection::Input);
+        }
+        if self.accept_keyword("output") {
+            return Some(PortDirection::Output);
+        }
+        return None;
+    }
+
+    fn parse_target(&mut self) -> Result<Target, String> {
+
+        let (name, location) = self.expect_identifier()?;
+        let index = match self.accept_symbol("[") {
+            true => {
+                let index = self.expect_number()?;
+                if self.is_symbol(":") {
+                    return self.location().error(String::from("part selects are not supported"));
+                }
+                self.expect_symbol("]")?;
+                Some(index)
+            },
+            false => None,
+        };
+
+        return Ok(Target {
+            location: location,
+            name: name,
+            index: index,
+        });
+    }
+
+    // the operators bind like in verilog, and before xor before or
+    fn parse_expression(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_xor()?;
+        while self.accept_symbol("|") {
+            if self.is_symbol("|") {
+                return self.location().error(String::from("logical operators are not supported"));
+            }
+            expression = Expression::Binary(Operator::Or, Box::new(expression), Box::new(self.parse_xor()?));
+        }
+
+        match self.peek() {
+            Token::Symbol(symbol) if ["+", "-", "*", "?", "<", ">", "!"].contains(symbol) => return self.location().error(format!("the {} operator is not supported", symbol)),
+            _other => return Ok(expression),
+        }
+    }
+
+    fn parse_xor(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_and()?;
+        while self.accept_symbol("^") {
+            if self.is_symbol("~") {
+                return self.location().error(String::from("the xnor operator is not supported, use ~(a ^ b) instead"));
+            }
+            expression = Expression::Binary(Operator::Xor, Box::new(expression), Box::new(self.parse_and()?));
+        }
+        return Ok(expression);
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_unary()?;
+        while self.accept_symbol("&") {
+            if self.is_symbol("&") {
+                return self.location().error(String::from("logical operators are not supported"));
+            }
+            expression = Expression::Binary(Operator::And, Box::new(expression), Box::new(self.parse_unary()?));
+        }
+        return Ok(expression);
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, String> {
+
+        let location = self.location();
+        match self.peek().clone() {
+
+            Token::Symbol("~") => {
+                self.next();
+                return Ok(Expression::Not(Box::new(self.parse_unary()?)));
+            },
+
+            Token::Symbol("(") => {
+                self.next();
+                let expression = self.parse_expression()?;
+                self.expect_symbol(")")?;
+                return Ok(expression);
+            },
+
+            Token::Symbol("{") => return location.error(String::from("concatenation is not supported")),
+
+            Token::Symbol(symbol) if ["&", "|", "^"].contains(&symbol) => {
+                self.next();
+                let operator = match symbol {
+                    "&" => Operator::And,
+                    "|" => Operator::Or,
+                    _other => Operator::Xor,
+                };
+                return Ok(Expression::Reduce(operator, Box::new(self.parse_unary()?)));
+            },
+
+            Token::Symbol("!") => return location.error(String::from("logical operators are not supported, use ~ instead")),
+
+            Token::Number(number) => {
+                self.next();
+                let (states, sized) = parse_literal(&number, location)?;
+                return Ok(Expression::Literal(location, states, sized));
+            },
+
+            Token::Identifier(..) => {
+                let target = self.parse_target()?;
+                return Ok(Expression::Reference(target.location, target.name, target.index));
+            },
+
+            _other => return self.unexpected("an expression"),
+        }
+    }
+
+    // declarations like input [3:0] a, b; share their direction and range
+    fn parse_declaration(&mut self, direction: Option<PortDirection>, reg: bool, declarations: &mut Vec<Declaration>, items: &mut Vec<Item>) -> Result<(), String> {
+
+        let reg = reg || self.accept_keyword("reg");
+        if !reg {
+            self.accept_keyword("wire");
+        }
+
+        let range = self.parse_range()?;
+
+        loop {
+            let (name, location) = self.expect_identifier()?;
+            declarations.push(Declaration {
+                location: location,
+                name: name.clone(),
+                direction: direction,
+                reg: reg,
+                range: range,
+            });
+
+            // wires are assigned and registers are initialized in their declaration
+            if self.accept_symbol("=") {
+                let target = Target { location: location, name: name, index: None };
+                match reg {
+                    true => items.push(Item::Initial(target, self.parse_expression()?)),
+                    false => items.push(Item::Assign(target, self.parse_expression()?)),
+                }
+            }
+
+            if !self.accept_symbol(",") {
+                break;
+            }
+        }
+
+        return self.expect_symbol(";");
+    }
+
+    fn parse_statements(&mut self, blocking: bool) -> Result<Vec<(Target, Expression)>, String> {
+
+        let block = self.accept_keyword("begin");
+        let mut statements = Vec::new();
+
+        loop {
+            if block && self.accept_keyword("end") {
+                break;
+            }
+
+            for keyword in &["if", "case", "for", "while", "begin"] {
+                if self.is_keyword(keyword) {
+                    return self.location().error(format!("{} statements are not supported", keyword));
+                }
+            }
+
+            let target = self.parse_target()?;
+            match (blocking, self.peek()) {
+                (false, Token::Symbol("=")) => return self.location().error(String::from("blocking assignments are not supported in always blocks, use <= instead")),
+                (true, Token::Symbol("<=")) => return self.location().error(String::from("non-blocking assignments are not supported in initial blocks")),
+                (false, _token) => self.expect_symbol("<=")?,
+                (true, _token) => self.expect_symbol("=")?,
+            }
+
+            statements.push((target, self.parse_expression()?));
+            self.expect_symbol(";")?;
+
+            if !block {
+                break;
+            }
+        }
+
+        return Ok(statements);
+    }
+
+    fn parse_always(&mut self, location: Location) -> Result<Item, String> {
+
+        self.expect_symbol("@")?;
+        if self.is_symbol("*") {
+            return self.location().error(String::from("combinational always blocks are not supported, use assign instead"));
+        }
+
+        self.expect_symbol("(")?;
+        let rising = match self.peek() {
+            Token::Identifier(edge) if edge == "posedge" => true,
+            Token::Identifier(edge) if edge == "negedge" => false,
+            Token::Symbol("*") => return self.location().error(String::from("combinational always blocks are not supported, use assign instead")),
+            _other => return self.location().error(String::from("only always blocks on posedge or negedge of a clock are supported")),
+        };
+
+        self.next();
+        let (clock, _clock_location) = self.expect_identifier()?;
+
+        if self.is_keyword("or") || self.is_symbol(",") {
+            return self.location().error(String::from("asynchronous set and reset are not supported"));
+        }
+
+        self.expect_symbol(")")?;
+        let statements = self.parse_statements(false)?;
+        return Ok(Item::Always(location, rising, clock, statements));
+    }
+
+    fn parse_primitive(&mut self, location: Location, kind: String) -> Result<Item, String> {
+
+        // the instance name is optional
+        if let Token::Identifier(..) = self.peek() {
+            self.expect_identifier()?;
+        }
+
+        self.expect_symbol("(")?;
+        let mut terminals = vec![self.parse_expression()?];
+
+        while self.accept_symbol(",") {
+            terminals.push(self.parse_expression()?);
+        }
+
+        self.expect_symbol(")")?;
+        self.expect_symbol(";")?;
+        return Ok(Item::Primitive(location, kind, terminals));
+    }
+
+    fn parse_module(&mut self) -> Result<Module, String> {
+
+        self.expect_keyword("module")?;
+        let (name, _location) = self.expect_identifier()?;
+        let mut ports = Vec::new();
+        let mut declarations = Vec::new();
+        let mut items = Vec::new();
+
+        if self.is_symbol("#") {
+            return self.location().error(String::from("parameters are not supported"));
+        }
+
+        // ports are either listed by name or declared in the header
+        if self.accept_symbol("(") && !self.accept_symbol(")") {
+            let mut previous: Option<Declaration> = None;
+
+            loop {
+                let direction = self.parse_direction();
+                let declaration = match (direction, &previous) {
+                    (Some(direction), _previous) => {
+                        let reg = self.accept_keyword("reg");
+                        if !reg {
+                            self.accept_keyword("wire");
+                        }
+                        let range = self.parse_range()?;
+                        let (name, location) = self.expect_identifier()?;
+                        Some(Declaration { location: location, name: name, direction: Some(direction), reg: reg, range: range })
+                    },
+                    (None, Some(previous)) => {
+                        let (name, location) = self.expect_identifier()?;
+                        Some(Declaration { location: location, name: name, ..previous.clone() })
+                    },
+                    (None, None) => None,
+                };
+
+                match declaration {
+                    Some(declaration) => {
+                        ports.push((declaration.name.clone(), declaration.location));
+                        declarations.push(declaration.clone());
+                        previous = Some(declaration);
+                    },
+                    None => ports.push(self.expect_identifier()?),
+                }
+
+                if !self.accept_symbol(",") {
+                    break;
+                }
+            }
+
+            self.expect_symbol(")")?;
+        }
+
+        self.expect_symbol(";")?;
+
+        loop {
+            let (token, location) = self.next();
+            let keyword = match token {
+                Token::Identifier(keyword) => keyword,
+                Token::End => return location.error(String::from("missing endmodule")),
+                other => return location.error(format!("unexpected {}", describe(&other))),
+            };
+
+            match keyword.as_str() {
+
+                "endmodule" => break,
+
+                "input" => self.parse_declaration(Some(PortDirection::Input), false, &mut declarations, &mut items)?,
+
+                "output" => self.parse_declaration(Some(PortDirection::Output), false, &mut declarations, &mut items)?,
+
+                "wire" => self.parse_declaration(None, false, &mut declarations, &mut items)?,
+
+                "reg" => self.parse_declaration(None, true, &mut declarations, &mut items)?,
+
+                "assign" => {
+                    loop {
+                        let target = self.parse_target()?;
+                        self.expect_symbol("=")?;
+                        items.push(Item::Assign(target, self.parse_expression()?));
+
+                        if !self.accept_symbol(",") {
+                            break;
+                        }
+                    }
+                    self.expect_symbol(";")?;
+                },
+
+                "always" => items.push(self.parse_always(location)?),
+
+                "initial" => {
+                    for (target, expression) in self.parse_statements(true)? {
+                        items.push(Item::Initial(target, expression));
+                    }
+                },
+
+                "and" | "or" | "xor" | "nand" | "nor" | "xnor" | "not" | "buf" => items.push(self.parse_primitive(location, keyword)?),
+
+                "module" => return location.error(String::from("nested modules are not supported")),
+
+                other if KEYWORDS.contains(&other) || ["inout", "localparam", "generate", "genvar", "integer", "function", "task", "supply0", "supply1"].contains(&other) => {
+                    return location.error(format!("{} is not supported", other));
+                },
+
+                _other => return location.error(String::from("module instances are not supported")),
+            }
+        }
+
+        return Ok(Module {
+            name: name,
+            ports: ports,
+            declarations: declarations,
+            items: items,
+        });
+    }
+}
+
+// a single module with wires and regs, assignments with bitwise and reduction operators, gate primitives,
+// initial values and always blocks on an edge of one clock, anything else is reported with its location
+pub fn import(source: &str) -> Result<Netlist, String> {
+
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+
+    let module = parser.parse_module()?;
+    if *parser.peek() != Token::End {
+        return parser.location().error(String::from("files with multiple modules are not supported"));
+    }
+
+    let mut importer = VerilogImporter {
+        netlist: Netlist::new(&module.name),
+        declarations: HashMap::new(),
+        order: Vec::new(),
+        clock: None,
+        aliases: HashMap::new(),
+        driven: HashMap::new(),
+        initial: HashMap::new(),
+        nets: HashMap::new(),
+        flops: Vec::new(),
+    };
+
+    importer.build(module)?;
+    return Ok(importer.netlist);
+}
+
+impl VerilogImporter {
+
+    // a port declared in the header or with a direction may be declared again as wire or reg
+    fn declare(&mut self, declaration: Declaration) -> Result<(), String> {
+
+        let existing = match self.declarations.get_mut(&declaration.name) {
+            Some(existing) => existing,
+            None => {
+                self.order.push(declaration.name.clone());
+                self.declarations.insert(declaration.name.clone(), declaration);
+                return Ok(());
+            },
+        };
+
+        if existing.direction.is_some() == declaration.direction.is_some() || (existing.reg && declaration.reg) || existing.range != declaration.range {
+            return declaration.location.error(format!("{} is declared twice", declaration.name));
+        }
+
+        existing.direction = existing.direction.or(declaration.direction);
+        existing.reg |= declaration.reg;
+        return Ok(());
+    }
+
+    fn declaration(&self, name: &str, location: Location) -> Result<&Declaration, String> {
+        match self.declarations.get(name) {
+            Some(declaration) => return Ok(declaration),
+            None => return location.error(format!("{} is not declared", name)),
+        }
+    }
+
+    fn width(declaration: &Declaration) -> usize {
+        return declaration.range.map(|(msb, lsb)| msb - lsb + 1).unwrap_or(1);
+    }
+
+    fn bit_name(&self, bit: &Bit) -> String {
+        match self.declarations[&bit.0].range {
+            Some((_msb, lsb)) => return format!("{}[{}]", bit.0, lsb + bit.1),
+            None => return bit.0.clone(),
+        }
+    }
+
+    // bits are returned starting with the most significant one
+    fn bits(&self, name: &str, index: Option<usize>, location: Location) -> Result<Vec<Bit>, String> {
+
+        if self.clock.as_deref() == Some(name) {
+            return location.error(format!("the clock {} is used as data, which the global clock of the emulator can not model", name));
+        }
+
+        let declaration = self.declaration(name, location)?;
+        match (index, declaration.range) {
+            (None, _range) => return Ok((0..Self::width(declaration)).rev().map(|offset| (String::from(name), offset)).collect()),
+            (Some(index), Some((msb, lsb))) if index >= lsb && index <= msb => return Ok(vec![(String::from(name), index - lsb)]),
+            (Some(index), _range) => return location.error(format!("index {} is out of range for {}", index, name)),
+        }
+    }
+
+    fn target_bits(&self, target: &Target) -> Result<Vec<Bit>, String> {
+        return self.bits(&target.name, target.index, target.location);
+    }
+
+    fn drive(&mut self, bit: &Bit, location: Location) -> Result<(), String> {
+        if let Some(previous) = self.driven.get(bit) {
+            return location.error(format!("{} is already driven at line {} column {}", self.bit_name(bit), previous.line, previous.column));
+        }
+        self.driven.insert(bit.clone(), location);
+        return Ok(());
+    }
+
+    // follows assignments of one name to another until a bit with its own net is found
+    fn net(&mut self, bit: &Bit) -> Result<Net, String> {
+
+        let mut bit = bit.clone();
+        for _step in 0..=self.aliases.len() {
+            match self.aliases.get(&bit) {
+                Some((Source::Bit(source), _location)) => bit = source.clone(),
+                Some((Source::Constant(state), _location)) => return Ok(self.netlist.add_constant(*state)),
+                None => {
+                    if let Some(net) = self.nets.get(&bit) {
+                        return Ok(*net);
+                    }
+                    let net = self.netlist.add_net();
+                    self.nets.insert(bit, net);
+                    return Ok(net);
+                },
+            }
+        }
+
+        let location = self.aliases[&bit].1;
+        return location.error(format!("assignments form a loop through {}", self.bit_name(&bit)));
+    }
+
+    fn fit(states: &[LogicState], sized: bool, width: usize, location: Location) -> Result<Vec<LogicState>, String> {
+
+        if sized && states.len() != width {
+            return location.error(format!("literal is {} bits wide where {} bits are expected", states.len(), width));
+        }
+
+        let significant = states.iter().skip_while(|state| **state == LogicState::Low).count();
+        if significant > width {
+            return location.error(format!("literal does not fit into {} bits", width));
+        }
+
+        let mut fitted = vec![LogicState::Low; width - states.len().min(width)];
+        fitted.extend(states[states.len() - states.len().min(width)..].iter().cloned());
+        return Ok(fitted);
+    }
+
+    fn reference_bits(&self, name: &str, index: Option<usize>, width: usize, location: Location) -> Result<Vec<Bit>, String> {
+        let bits = self.bits(name, index, location)?;
+        if bits.len() != width {
+            return location.error(format!("{} is {} bits wide where {} bits are expected", name, bits.len(), width));
+        }
+        return Ok(bits);
+    }
+
+    // the width an expression has on its own, which is what a reduction operator combines
+    fn natural_width(&self, expression: &Expression) -> Result<usize, String> {
+        match expression {
+            Expression::Reference(location, name, index) => return Ok(self.bits(name, *index, *location)?.len()),
+            Expression::Literal(_location, states, _sized) => return Ok(states.len()),
+            Expression::Not(inner) => return self.natural_width(inner),
+            Expression::Binary(_operator, left, right) => return Ok(self.natural_width(left)?.max(self.natural_width(right)?)),
+            Expression::Reduce(..) => return Ok(1),
+        }
+    }
+
+    // the outermost gates drive the target directly if there is one
+    fn compile(&mut self, expression: &Expression, width: usize, target: Option<&[Net]>) -> Result<Vec<Net>, String> {
+
+        let nets = match expression {
+
+            Expression::Reference(location, name, index) => {
+                let bits = self.reference_bits(name, *index, width, *location)?;
+                bits.iter().map(|bit| self.net(bit)).collect::<Result<Vec<Net>, String>>()?
+            },
+
+            Expression::Literal(location, states, sized) => {
+                let states = Self::fit(states, *sized, width, *location)?;
+                states.iter().map(|state| self.netlist.add_constant(*state)).collect()
+            },
+
+            Expression::Not(inner) => {
+                let inner = self.compile(inner, width, None)?;
+                let mut outputs = Vec::new();
+
+                for (position, net) in inner.iter().enumerate() {
+                    match target {
+                        Some(target) => {
+                            self.netlist.add_not_to(*net, target[position]);
+                            outputs.push(target[position]);
+                        },
+                        None => outputs.push(self.netlist.add_not(*net)),
+                    }
+                }
+
+                return Ok(outputs);
+            },
+
+            Expression::Binary(operator, left, right) => {
+                let left = self.compile(left, width, None)?;
+                let right = self.compile(right, width, None)?;
+                let mut outputs = Vec::new();
+
+                for position in 0..width {
+                    match target {
+                        Some(target) => {
+                            self.netlist.add_gate(*operator, left[position], right[position], target[position]);
+                            outputs.push(target[position]);
+                        },
+                        None => outputs.push(self.netlist.add_gate_net(*operator, left[position], right[position])),
+                    }
+                }
+
+                return Ok(outputs);
+            },
+
+            // the single bit result is zero extended to the width of the context
+            Expression::Reduce(operator, inner) => {
+                let inner_width = self.natural_width(inner)?;
+                let inner = self.compile(inner, inner_width, None)?;
+                let mut nets = vec![self.netlist.add_constant(LogicState::Low); width.saturating_sub(1)];
+                nets.push(self.netlist.add_tree(*operator, &inner, None)?);
+                nets
+            },
+        };
+
+        // plain references and literals only reach a target through a buffer
+        match target {
+            Some(target) => {
+                nets.iter().zip(target.iter()).for_each(|(net, target)| self.netlist.add_buffer(*net, *target));
+                return Ok(target.to_vec());
+            },
+            None => return Ok(nets),
+        }
+    }
+
+    // drives the target with new nets, registers can only be assigned in always blocks
+    fn target_nets(&mut self, target: &Target, reg: bool) -> Result<Vec<Net>, String> {
+
+        let declaration = self.declaration(&target.name, target.location)?;
+        match (declaration.reg, reg) {
+            (true, false) => return target.location.error(format!("{} is a reg and can only be assigned in always blocks", target.name)),
+            (false, true) => return target.location.error(format!("{} has to be declared as reg to be assigned in an always block", target.name)),
+            _other => { },
+        }
+
+        let bits = self.target_bits(target)?;
+        for bit in &bits {
+            self.drive(bit, target.location)?;
+        }
+
+        return bits.iter().map(|bit| self.net(bit)).collect();
+    }
+
+    fn build_primitive(&mut self, location: Location, kind: &str, terminals: &[Expression]) -> Result<(), String> {
+
+        // outputs come first and have to name a wire
+        let target = |expression: &Expression| match expression {
+            Expression::Reference(location, name, index) => return Ok(Target { location: *location, name: name.clone(), index: *index }),
+            _other => return location.error(format!("the outputs of {} have to be wires", kind)),
+        };
+
+        let (operator, inverted) = match kind {
+            "and" => (Operator::And, false),
+            "or" => (Operator::Or, false),
+            "xor" => (Operator::Xor, false),
+            "nand" => (Operator::And, true),
+            "nor" => (Operator::Or, true),
+            "xnor" => (Operator::Xor, true),
+
+            // not and buf drive any number of outputs from their last terminal
+            _other => {
+                let (input, outputs) = match terminals.split_last() {
+                    Some((input, outputs)) if !outputs.is_empty() => (input, outputs),
+                    _other => return location.error(format!("{} needs at least one output and an input", kind)),
+                };
+
+                for output in outputs {
+                    let target = target(output)?;
+                    let width = Self::width(self.declaration(&target.name, target.location)?);
+                    let width = target.index.map(|_index| 1).unwrap_or(width);
+                    let outputs = self.target_nets(&target, false)?;
+                    let input = self.compile(input, width, None)?;
+
+                    for (input, output) in input.iter().zip(outputs.iter()) {
+                        match kind {
+                            "not" => self.netlist.add_not_to(*input, *output),
+                            _other => self.netlist.add_buffer(*input, *output),
+                        }
+                    }
+                }
+
+                return Ok(());
+            },
+        };
+
+        if terminals.len() < 3 {
+            return location.error(format!("{} needs an output and at least two inputs", kind));
+        }
+
+        let target = target(&terminals[0])?;
+        let width = Self::width(self.declaration(&target.name, target.location)?);
+        let width = target.index.map(|_index| 1).unwrap_or(width);
+        let outputs = self.target_nets(&target, false)?;
+        let inputs = terminals[1..].iter().map(|terminal| self.compile(terminal, width, None)).collect::<Result<Vec<Vec<Net>>, String>>()?;
+
+        for (position, output) in outputs.iter().enumerate() {
+            let bits: Vec<Net> = inputs.iter().map(|input| input[position]).collect();
+            match inverted {
+                true => {
+                    let result = self.netlist.add_tree(operator, &bits, None)?;
+                    self.netlist.add_not_to(result, *output);
+                },
+                false => { self.netlist.add_tree(operator, &bits, Some(*output))?; },
+            }
+        }
+
+        return Ok(());
+    }
+
+    // the edges on which a net can change, a gate only fires if both of its inputs changed in the same tick
+    fn phases(&self, net: Net, drivers: &Vec<Option<Driver>>, flop_outputs: &HashMap<Net, bool>, memo: &mut Vec<Option<(bool, bool)>>) -> (bool, bool) {
+
+        if let Some(phases) = memo[net] {
+            return phases;
+        }
+
+        // loops without a register never settle
+        memo[net] = Some((false, false));
+
+        let phases = match (flop_outputs.get(&net), drivers[net]) {
+            (Some(rising), _driver) => (*rising, !*rising),
+            (None, Some(Driver::Constant(..))) => (true, true),
+            (None, Some(Driver::Port(..))) => (true, false),
+            (None, Some(Driver::Gate(index))) => {
+                let (left, right) = (self.netlist.gates[index].left, self.netlist.gates[index].right);
+                let left = self.phases(left, drivers, flop_outputs, memo);
+                let right = self.phases(right, drivers, flop_outputs, memo);
+                (left.0 && right.0, left.1 && right.1)
+            },
+            (None, _driver) => (false, false),
+        };
+
+        memo[net] = Some(phases);
+        return phases;
+    }
+
+    // a register is only stable if its input changes on the opposite edge, which is the case for paths
+    // between registers on different edges, everything else needs a pair of registers
+    fn build_flops(&mut self) -> Result<(), String> {
+
+        let drivers = self.netlist.drivers()?;
+        let flop_outputs: HashMap<Net, bool> = self.flops.iter().map(|flop| (flop.output, flop.rising)).collect();
+        let mut memo = vec![None; self.netlist.net_count];
+
+        let phases: Vec<(bool, bool)> = self.flops.iter().map(|flop| self.phases(flop.input, &drivers, &flop_outputs, &mut memo)).collect();
+        let flops: Vec<Flop> = self.flops.drain(..).collect();
+
+        for (flop, (rising, falling)) in flops.iter().zip(phases.iter()) {
+            match flop.rising {
+                true if *falling && !*rising => self.netlist.add_register(true, flop.initial, flop.input, flop.output),
+                false if *rising && !*falling => self.netlist.add_register(false, flop.initial, flop.input, flop.output),
+                _other => self.netlist.add_flip_flop(flop.rising, flop.initial, flop.input, flop.output),
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn build(&mut self, module: Module) -> Result<(), String> {
+
+        for declaration in module.declarations {
+            self.declare(declaration)?;
+        }
+
+        for (name, location) in &module.ports {
+            if self.declaration(name, *location)?.direction.is_none() {
+                return location.error(format!("port {} has no direction", name));
+            }
+        }
+
+        for name in &self.order {
+            let declaration = &self.declarations[name];
+            if declaration.direction.is_some() && !module.ports.iter().any(|(port, _location)| port == name) {
+                return declaration.location.error(format!("{} is not in the port list", name));
+            }
+        }
+
+        // the clock is provided by the emulator, so it is not an input of the core
+        for item in &module.items {
+            if let Item::Always(location, _rising, clock, _statements) = item {
+                let declaration = self.declaration(clock, *location)?;
+
+                if declaration.direction != Some(PortDirection::Input) || declaration.range.is_some() {
+                    return location.error(format!("the clock {} has to be a single bit input", clock));
+                }
+
+                match &self.clock {
+                    Some(previous) if previous != clock => return location.error(String::from("design uses more than one clock")),
+                    _other => self.clock = Some(clock.clone()),
+                }
+            }
+        }
+
+        for name in self.order.clone() {
+            let declaration = self.declarations[&name].clone();
+            if declaration.direction == Some(PortDirection::Input) && self.clock.as_ref() != Some(&name) {
+                for bit in self.bits(&name, None, declaration.location)? {
+                    self.drive(&bit, declaration.location)?;
+                }
+            }
+        }
+
+        for item in &module.items {
+            if let Item::Initial(target, expression) = item {
+
+                if !self.declaration(&target.name, target.location)?.reg {
+                    return target.location.error(format!("{} has to be declared as reg to have an initial value", target.name));
+                }
+
+                let (location, states, sized) = match expression {
+                    Expression::Literal(location, states, sized) => (*location, states, *sized),
+                    _other => return target.location.error(String::from("initial values have to be literals")),
+                };
+
+                let bits = self.target_bits(target)?;
+                let states = Self::fit(states, sized, bits.len(), location)?;
+                bits.into_iter().zip(states).for_each(|(bit, state)| { self.initial.insert(bit, state); });
+            }
+        }
+
+        // assignments without any operator only connect names, so they are resolved before any net is created
+        for item in &module.items {
+            if let Item::Assign(target, expression) = item {
+
+                if self.declaration(&target.name, target.location)?.reg {
+                    return target.location.error(format!("{} is a reg and can only be assigned in always blocks", target.name));
+                }
+
+                let bits = self.target_bits(target)?;
+                let sources: Vec<Source> = match expression {
+                    Expression::Reference(location, name, index) => self.reference_bits(name, *index, bits.len(), *location)?.into_iter().map(Source::Bit).collect(),
+                    Expression::Literal(location, states, sized) => Self::fit(states, *sized, bits.len(), *location)?.into_iter().map(Source::Constant).collect(),
+                    _other => continue,
+                };
+
+                for (bit, source) in bits.into_iter().zip(sources) {
+                    self.drive(&bit, target.location)?;
+                    self.aliases.insert(bit, (source, target.location));
+                }
+            }
+        }
+
+        for item in &module.items {
+            match item {
+
+                Item::Assign(_, Expression::Reference(..)) | Item::Assign(_, Expression::Literal(..)) | Item::Initial(..) => { },
+
+                Item::Assign(target, expression) => {
+                    let outputs = self.target_nets(target, false)?;
+                    self.compile(expression, outputs.len(), Some(&outputs))?;
+                },
+
+                Item::Primitive(location, kind, terminals) => self.build_primitive(*location, kind, terminals)?,
+
+                Item::Always(_location, rising, _clock, statements) => {
+                    for (target, expression) in statements {
+                        let bits = self.target_bits(target)?;
+                        let outputs = self.target_nets(target, true)?;
+                        let inputs = self.compile(expression, outputs.len(), None)?;
+
+                        for ((bit, input), output) in bits.iter().zip(inputs.iter()).zip(outputs.iter()) {
+                            self.flops.push(Flop {
+                                rising: *rising,
+                                initial: self.initial.get(bit).cloned().unwrap_or(LogicState::Low),
+                                input: *input,
+                                output: *output,
+                            });
+                        }
+                    }
+                },
+            }
+        }
+
+        // vectors become one port per bit, named like the bits of a yosys netlist
+        for (name, location) in &module.ports {
+            if self.clock.as_ref() == Some(name) {
+                continue;
+            }
+
+            let direction = self.declarations[name].direction.unwrap();
+            for bit in self.bits(name, None, *location)? {
+                let net = self.net(&bit)?;
+                let port_name = self.bit_name(&bit);
+                self.netlist.add_port(&port_name, direction, net);
+            }
+        }
+
+        for name in self.order.clone() {
+            if self.clock.as_ref() != Some(&name) {
+                let location = self.declarations[&name].location;
+                let nets = self.bits(&name, None, location)?.iter().map(|bit| self.net(bit)).collect::<Result<Vec<Net>, String>>()?;
+                self.netlist.add_signal(&name, nets);
+            }
+        }
+
+        return self.build_flops();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::super::LogicState;
+    use super::super::Netlist;
+
+    const REDUCTIONS: &str = "
+        module reductions(input [3:0] a, input [1:0] b, output parity, output all, output any, output none, output [1:0] wide);
+            assign parity = ^a;
+            assign all = &a;
+            assign any = |(a ^ 4'b0101);
+            assign none = ~|b;
+            assign wide = ^b;
+        endmodule
+    ";
+
+    #[test]
+    fn reductions_become_gate_trees() {
+
+        let netlist = Netlist::from_verilog(REDUCTIONS).unwrap();
+        let table = netlist.truth_table(&[], &[], false).unwrap();
+        assert_eq!(table.inputs, vec!["a[3]", "a[2]", "a[1]", "a[0]", "b[1]", "b[0]"]);
+        assert_eq!(table.outputs, vec!["parity", "all", "any", "none", "wide[1]", "wide[0]"]);
+        assert_eq!(table.rows.len(), 64);
+
+        for (inputs, outputs) in &table.rows {
+            let value = |bits: &[LogicState]| bits.iter().fold(0, |value, state| value * 2 + (*state == LogicState::High) as u32);
+            let (a, b) = (value(&inputs[..4]), value(&inputs[4..]));
+
+            let expected = [a.count_ones() % 2 == 1, a == 0b1111, a != 0b0101, b == 0, false, b.count_ones() == 1];
+            let expected: Vec<LogicState> = expected.iter().map(|bit| LogicState::from_boolean(*bit)).collect();
+            assert_eq!(outputs, &expected);
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_construct() {
+
+        let error = |source: &str| Netlist::from_verilog(source).unwrap_err();
+
+        assert_eq!(error("module m(input a, input b, output y);\n    assign y = a + b;\nendmodule"), "line 2 column 18: the + operator is not supported");
+        assert_eq!(error("module m(input a, output y);\n  assign y = {a};\nendmodule"), "line 2 column 14: concatenation is not supported");
+        assert_eq!(error("module m(input a, output y);\nassign y = a && a;\nendmodule"), "line 2 column 15: logical operators are not supported");
+        assert_eq!(error("module m(input a, output y);\nassign z = a;\nendmodule"), "line 2 column 8: z is not declared");
+    }
+}
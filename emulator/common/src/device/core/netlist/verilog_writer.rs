@@ -0,0 +1,326 @@
+use std::collections::HashSet;
+use super::super::{ LogicState, Operator };
+use super::{ Netlist, Net, PortDirection };
+
+pub const KEYWORDS: [&str; 34] = [
+    "always", "and", "assign", "begin", "buf", "case", "default", "else", "end", "endcase", "endfunction", "endmodule",
+    "for", "function", "if", "initial", "inout", "input", "integer", "module", "nand", "negedge", "nor", "not", "or",
+    "output", "parameter", "posedge", "reg", "task", "tri", "wire", "xnor", "xor",
+];
+
+// ports of the same vector are imported as one port per bit
+struct PortGroup {
+    name: String,
+    direction: PortDirection,
+    nets: Vec<Net>,
+    vector: bool,
+}
+
+// the index and net of every bit of a port vector, empty for scalar ports
+type PortBits = Vec<(usize, Net)>;
+
+struct VerilogWriter {
+    used: HashSet<String>,
+    names: Vec<Option<String>>,
+    declarations: Vec<String>,
+    assignments: Vec<String>,
+}
+
+// splits a bit name like q[2] into the name of the vector and the index
+fn split_index(name: &str) -> Option<(&str, usize)> {
+    let (base, index) = name.strip_suffix(']')?.rsplit_once('[')?;
+    return Some((base, index.parse().ok()?));
+}
+
+fn literal(state: LogicState) -> &'static str {
+    match state {
+        LogicState::Low => return "1'b0",
+        LogicState::High => return "1'b1",
+        LogicState::Floating => return "1'bz",
+        LogicState::Metastable => return "1'bx",
+    }
+}
+
+fn operator_symbol(operator: Operator) -> &'static str {
+    match operator {
+        Operator::And => return "&",
+        Operator::Or => return "|",
+        Operator::Xor => return "^",
+    }
+}
+
+fn width_prefix(width: usize, vector: bool) -> String {
+    match vector {
+        true => return format!("[{}:0] ", width - 1),
+        false => return String::new(),
+    }
+}
+
+fn port_groups(netlist: &Netlist) -> Vec<PortGroup> {
+
+    let mut groups: Vec<(String, PortDirection, PortBits)> = Vec::new();
+    for port in &netlist.ports {
+        match split_index(&port.name) {
+            Some((base, index)) => match groups.last_mut() {
+                Some((name, direction, bits)) if !bits.is_empty() && name == base && *direction == port.direction => bits.push((index, port.net)),
+                _other => groups.push((String::from(base), port.direction, vec![(index, port.net)])),
+            },
+            None => groups.push((port.name.clone(), port.direction, Vec::new())),
+        }
+    }
+
+    let mut ports = Vec::new();
+    let mut port_index = 0;
+
+    for (name, direction, mut bits) in groups {
+
+        // scalar ports have no bits
+        if bits.is_empty() {
+            ports.push(PortGroup { name: name, direction: direction, nets: vec![netlist.ports[port_index].net], vector: false });
+            port_index += 1;
+            continue;
+        }
+
+        let count = bits.len();
+        bits.sort_by_key(|(index, _net)| *index);
+
+        // only complete vectors are kept together, anything else is written bit by bit
+        if bits.iter().enumerate().all(|(position, (index, _net))| position == *index) {
+            let nets = bits.iter().rev().map(|(_index, net)| *net).collect();
+            ports.push(PortGroup { name: name, direction: direction, nets: nets, vector: true });
+        } else {
+            for port in &netlist.ports[port_index..port_index + count] {
+                ports.push(PortGroup { name: port.name.clone(), direction: direction, nets: vec![port.net], vector: false });
+            }
+        }
+
+        port_index += count;
+    }
+
+    return ports;
+}
+
+impl VerilogWriter {
+
+    // turns any name into a unique identifier that is not a keyword
+    fn identifier(&mut self, name: &str) -> String {
+
+        let mut identifier: String = name.chars().filter(|character| *character != ']').map(|character| match character {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => character,
+            _other => '_',
+        }).collect();
+
+        if identifier.is_empty() || identifier.starts_with(|character: char| character.is_ascii_digit()) || KEYWORDS.contains(&identifier.as_str()) {
+            identifier = format!("_{}", identifier);
+        }
+
+        let mut unique = identifier.clone();
+        let mut counter = 1;
+
+        while self.used.contains(&unique) {
+            unique = format!("{}_{}", identifier, counter);
+            counter += 1;
+        }
+
+        self.used.insert(unique.clone());
+        return unique;
+    }
+
+    // nets listed most significant bit first
+    fn name_bits(&mut self, identifier: &str, nets: &[Net], vector: bool) {
+        for (position, net) in nets.iter().enumerate() {
+
+            let reference = match vector {
+                true => format!("{}[{}]", identifier, nets.len() - position - 1),
+                false => String::from(identifier),
+            };
+
+            match &self.names[*net] {
+                Some(name) => self.assignments.push(format!("assign {} = {};", reference, name)),
+                None => self.names[*net] = Some(reference),
+            }
+        }
+    }
+
+    fn name(&self, net: Net) -> &str {
+        return self.names[net].as_ref().unwrap();
+    }
+}
+
+impl Netlist {
+
+    // registers become always blocks on their edge of a shared clock, gates become continuous assignments
+    pub fn to_verilog(&self) -> Result<String, String> {
+
+        self.validate()?;
+        let drivers = self.drivers()?;
+        let sinks = self.sinks()?;
+
+        let mut writer = VerilogWriter {
+            used: HashSet::new(),
+            names: vec![None; self.net_count],
+            declarations: Vec::new(),
+            assignments: Vec::new(),
+        };
+
+        let module = writer.identifier(&self.name);
+        let clock = match self.registers.is_empty() {
+            true => None,
+            false => Some(writer.identifier("clk")),
+        };
+
+        let mut port_list: Vec<String> = clock.iter().cloned().collect();
+        let mut port_declarations: Vec<String> = clock.iter().map(|clock| format!("input {};", clock)).collect();
+        let mut outputs = Vec::new();
+
+        for group in port_groups(self) {
+            let identifier = writer.identifier(&group.name);
+            let keyword = match group.direction {
+                PortDirection::Input => "input",
+                PortDirection::Output => "output",
+            };
+
+            port_declarations.push(format!("{} {}{};", keyword, width_prefix(group.nets.len(), group.vector), identifier));
+            port_list.push(identifier.clone());
+
+            match group.direction {
+                PortDirection::Input => writer.name_bits(&identifier, &group.nets, group.vector),
+                PortDirection::Output => outputs.push((identifier, group.nets, group.vector)),
+            }
+        }
+
+        // outputs are named after inputs, so a port passed straight through becomes an assignment
+        for (identifier, nets, vector) in &outputs {
+            writer.name_bits(identifier, nets, *vector);
+        }
+
+        for signal in &self.signals {
+            if signal.nets.iter().all(|net| writer.names[*net].is_some()) {
+                continue;
+            }
+
+            let identifier = writer.identifier(&signal.name);
+            writer.declarations.push(format!("wire {}{};", width_prefix(signal.nets.len(), signal.nets.len() > 1), identifier));
+            writer.name_bits(&identifier, &signal.nets, signal.nets.len() > 1);
+        }
+
+        // everything else that is connected gets a generated name
+        for net in 0..self.net_count {
+            if writer.names[net].is_none() && (drivers[net].is_some() || !sinks[net].is_empty()) {
+                let identifier = writer.identifier(&format!("n{}", net));
+                writer.declarations.push(format!("wire {};", identifier));
+                writer.names[net] = Some(identifier);
+            }
+        }
+
+        let mut body = Vec::new();
+
+        for constant in &self.constants {
+            body.push(format!("assign {} = {};", writer.name(constant.output), literal(constant.state)));
+        }
+
+        for gate in &self.gates {
+            body.push(format!("assign {} = {} {} {};", writer.name(gate.output), writer.name(gate.left), operator_symbol(gate.operator), writer.name(gate.right)));
+        }
+
+        // registers get their own variable, so the nets they drive can stay wires
+        for register in &self.registers {
+            let identifier = writer.identifier(&format!("{}_reg", writer.name(register.output)));
+            let edge = match register.rising {
+                true => "posedge",
+                false => "negedge",
+            };
+
+            writer.declarations.push(format!("reg {} = {};", identifier, literal(register.initial)));
+            body.push(format!("always @({} {}) {} <= {};", edge, clock.as_ref().unwrap(), identifier, writer.name(register.input)));
+            body.push(format!("assign {} = {};", writer.name(register.output), identifier));
+        }
+
+        let mut verilog = format!("module {}({});\n", module, port_list.join(", "));
+        let sections = [port_declarations, writer.declarations, body, writer.assignments];
+
+        for section in sections.iter().filter(|section| !section.is_empty()) {
+            verilog.push('\n');
+            section.iter().for_each(|line| verilog.push_str(&format!("    {}\n", line)));
+        }
+
+        verilog.push_str("\nendmodule\n");
+        return Ok(verilog);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::super::{ Core, LogicState };
+    use super::super::Netlist;
+    use crate::types::FloatVector;
+
+    const CYCLES: usize = 32;
+
+    // a counter with enable built from master slave flip flops, so the inputs are sampled on the falling edge
+    const COUNTER: &str = "
+        module counter(input clk, input en, output [1:0] q, output carry);
+            reg [1:0] master = 2'b00;
+            reg [1:0] count = 2'b01;
+            wire [1:0] next;
+
+            assign next[0] = count[0] ^ en;
+            assign next[1] = count[1] ^ (count[0] & en);
+            and carry_gate (carry, count[0], count[1], en);
+            assign q = count;
+
+            always @(negedge clk) master <= next;
+            always @(posedge clk) count <= master;
+        endmodule
+    ";
+
+    fn core(netlist: &Netlist) -> Core {
+        return Core::from_netlist(netlist.name.clone(), netlist, FloatVector::new(), false).unwrap();
+    }
+
+    // exports the core, imports it again and checks that both go through the same states in every tick
+    fn round_trip(mut original: Core) {
+
+        let verilog = original.netlist().to_verilog().unwrap();
+        let mut imported = core(&Netlist::from_verilog(&verilog).unwrap());
+
+        assert_eq!(original.registers.len(), imported.registers.len());
+        assert_eq!(original.ports.len(), imported.ports.len());
+
+        for cycle in 0..CYCLES {
+
+            for index in 0..original.ports.len() {
+                if original.ports[index].is_input() {
+                    let state = LogicState::from_boolean((cycle >> index) & 1 == 1);
+                    original.set_port_state(index, state);
+                    imported.set_port_state(index, state);
+                }
+            }
+
+            for rising in [true, false].iter() {
+                original.tick(*rising);
+                imported.tick(*rising);
+
+                for (index, (first, second)) in original.registers.iter().zip(imported.registers.iter()).enumerate() {
+                    assert_eq!(first.state, second.state, "register {} in cycle {}\n{}", index, cycle, verilog);
+                }
+
+                for (first, second) in original.ports.iter().zip(imported.ports.iter()) {
+                    assert_eq!(first.name, second.name);
+                    assert_eq!(first.state, second.state, "port {} in cycle {}\n{}", first.name, cycle, verilog);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sample_round_trip() {
+        round_trip(Core::sample(String::from("sample"), FloatVector::new(), false).unwrap());
+    }
+
+    #[test]
+    fn counter_round_trip() {
+        round_trip(core(&Netlist::from_verilog(COUNTER).unwrap()));
+    }
+}
@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+use super::super::{ LogicState, Operator };
+use super::json::Json;
+use super::{ Netlist, Net, PortDirection };
+
+// a bit of a yosys connection is either a numbered net or a constant
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Bit {
+    Net(usize),
+    Constant(LogicState),
+}
+
+struct YosysImporter {
+    netlist: Netlist,
+    nets: HashMap<usize, Net>,
+    aliases: HashMap<usize, Bit>,
+    initial_states: HashMap<usize, LogicState>,
+    clock: Option<usize>,
+}
+
+// yosys writes boolean attributes and parameters as binary strings or numbers
+fn is_set(value: &Json) -> bool {
+    match value {
+        Json::String(bits) => return bits.contains('1'),
+        Json::Number(number) => return *number != 0.0,
+        _other => return false,
+    }
+}
+
+fn parse_bits(value: &Json, context: &str) -> Result<Vec<Bit>, String> {
+
+    let elements = value.as_array().ok_or(format!("{}: expected a list of bits", context))?;
+    let mut bits = Vec::new();
+
+    for element in elements {
+        let bit = match element {
+            Json::String(state) if state == "0" => Bit::Constant(LogicState::Low),
+            Json::String(state) if state == "1" => Bit::Constant(LogicState::High),
+            Json::String(state) if state == "z" => Bit::Constant(LogicState::Floating),
+            Json::String(state) if state == "x" => Bit::Constant(LogicState::Metastable),
+            other => Bit::Net(other.as_usize().ok_or(format!("{}: invalid bit {:?}", context, other))?),
+        };
+        bits.push(bit);
+    }
+
+    return Ok(bits);
+}
+
+fn select_module(modules: &[(String, Json)]) -> Result<&(String, Json), String> {
+
+    let is_top = |module: &Json| module.get("attributes").and_then(|attributes| attributes.get("top")).map(is_set).unwrap_or(false);
+    let is_blackbox = |module: &Json| module.get("attributes").and_then(|attributes| attributes.get("blackbox")).map(is_set).unwrap_or(false);
+
+    if let Some(module) = modules.iter().find(|(_name, module)| is_top(module)) {
+        return Ok(module);
+    }
+
+    let candidates: Vec<&(String, Json)> = modules.iter().filter(|(_name, module)| !is_blackbox(module)).collect();
+    match candidates.as_slice() {
+        [] => return Err(String::from("netlist does not contain any modules")),
+        [module] => return Ok(module),
+        _other => return Err(format!("netlist contains multiple modules ({}) and none is marked as top", candidates.iter().map(|(name, _module)| name.as_str()).collect::<Vec<&str>>().join(", "))),
+    }
+}
+
+pub fn import(source: &str) -> Result<Netlist, String> {
+
+    let document = Json::parse(source)?;
+    let modules = document.get("modules").and_then(Json::as_object).ok_or(String::from("netlist does not contain any modules"))?;
+    let (module_name, module) = select_module(modules)?;
+
+    let mut importer = YosysImporter {
+        netlist: Netlist::new(module_name),
+        nets: HashMap::new(),
+        aliases: HashMap::new(),
+        initial_states: HashMap::new(),
+        clock: None,
+    };
+
+    importer.collect_initial_states(module)?;
+    importer.collect_aliases(module)?;
+    importer.import_cells(module)?;
+    importer.import_ports(module)?;
+    importer.import_signals(module)?;
+    return Ok(importer.netlist);
+}
+
+impl YosysImporter {
+
+    fn members<'a>(module: &'a Json, key: &str) -> &'a [(String, Json)] {
+        return module.get(key).and_then(Json::as_object).map(|members| members.as_slice()).unwrap_or(&[]);
+    }
+
+    fn resolve(&self, bit: Bit) -> Bit {
+        let mut bit = bit;
+        while let Bit::Net(number) = bit {
+            match self.aliases.get(&number) {
+                Some(alias) => bit = *alias,
+                None => break,
+            }
+        }
+        return bit;
+    }
+
+    fn net(&mut self, bit: Bit) -> Net {
+        match self.resolve(bit) {
+
+            Bit::Constant(state) => return self.netlist.add_constant(state),
+
+            Bit::Net(number) => {
+                if let Some(net) = self.nets.get(&number) {
+                    return *net;
+                }
+
+                let net = self.netlist.add_net();
+                self.nets.insert(number, net);
+                return net;
+            },
+        }
+    }
+
+    // init attributes are written most significant bit first
+    fn collect_initial_states(&mut self, module: &Json) -> Result<(), String> {
+        for (name, netname) in Self::members(module, "netnames") {
+
+            let init = match netname.get("attributes").and_then(|attributes| attributes.get("init")).and_then(Json::as_str) {
+                Some(init) => init,
+                None => continue,
+            };
+
+            let bits = parse_bits(netname.get("bits").unwrap_or(&Json::Null), name)?;
+            for (bit, character) in bits.iter().zip(init.chars().rev()) {
+                let state = match character {
+                    '0' => LogicState::Low,
+                    '1' => LogicState::High,
+                    'z' => LogicState::Floating,
+                    _other => LogicState::Metastable,
+                };
+
+                if let Bit::Net(number) = bit {
+                    self.initial_states.insert(*number, state);
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    // buffers do not need a gate, their output is simply the same net as their input
+    fn collect_aliases(&mut self, module: &Json) -> Result<(), String> {
+        for (name, cell) in Self::members(module, "cells") {
+            if cell.get("type").and_then(Json::as_str) == Some("$_BUF_") {
+                let input = Self::connection(cell, name, "A")?;
+                match Self::connection(cell, name, "Y")? {
+                    Bit::Net(number) => { self.aliases.insert(number, input); },
+                    Bit::Constant(..) => return Err(format!("cell {}: output Y is connected to a constant", name)),
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    fn connection(cell: &Json, name: &str, pin: &str) -> Result<Bit, String> {
+        let connection = cell.get("connections").and_then(|connections| connections.get(pin)).ok_or(format!("cell {}: missing connection {}", name, pin))?;
+        let bits = parse_bits(connection, &format!("cell {} pin {}", name, pin))?;
+
+        match bits.as_slice() {
+            [bit] => return Ok(*bit),
+            _other => return Err(format!("cell {}: pin {} has a width of {}, expected 1", name, pin, bits.len())),
+        }
+    }
+
+    fn input(&mut self, cell: &Json, name: &str, pin: &str) -> Result<Net, String> {
+        let bit = Self::connection(cell, name, pin)?;
+        return Ok(self.net(bit));
+    }
+
+    fn output(&mut self, cell: &Json, name: &str, pin: &str) -> Result<Net, String> {
+        match Self::connection(cell, name, pin)? {
+            Bit::Net(number) => return Ok(self.net(Bit::Net(number))),
+            Bit::Constant(..) => return Err(format!("cell {}: output {} is connected to a constant", name, pin)),
+        }
+    }
+
+    // the emulator has a single global clock, so every flip flop has to be clocked by the same net
+    fn clock(&mut self, cell: &Json, name: &str) -> Result<(), String> {
+        let clock = match self.resolve(Self::connection(cell, name, "C")?) {
+            Bit::Net(number) => number,
+            Bit::Constant(..) => return Err(format!("cell {}: clock is connected to a constant", name)),
+        };
+
+        match self.clock {
+            Some(other) if other != clock => return Err(format!("cell {}: design uses more than one clock", name)),
+            _other => self.clock = Some(clock),
+        }
+
+        return Ok(());
+    }
+
+    fn register(&mut self, cell: &Json, name: &str, rising: bool, enable: Option<bool>) -> Result<(), String> {
+
+        self.clock(cell, name)?;
+
+        let output_bit = self.resolve(Self::connection(cell, name, "Q")?);
+        let initial = match output_bit {
+            Bit::Net(number) => self.initial_states.get(&number).cloned().unwrap_or(LogicState::Low),
+            Bit::Constant(..) => return Err(format!("cell {}: output Q is connected to a constant", name)),
+        };
+
+        let output = self.output(cell, name, "Q")?;
+        let data = self.input(cell, name, "D")?;
+
+        // a flip flop with an enable keeps its own state while the enable is inactive
+        let input = match enable {
+
+            Some(active_high) => {
+                let enable = self.input(cell, name, "E")?;
+                let enable = match active_high {
+                    true => enable,
+                    false => self.netlist.add_not(enable),
+                };
+
+                let input = self.netlist.add_net();
+                self.netlist.add_multiplexer(output, data, enable, input);
+                input
+            },
+
+            None => data,
+        };
+
+        self.netlist.add_flip_flop(rising, initial, input, output);
+        return Ok(());
+    }
+
+    fn import_cells(&mut self, module: &Json) -> Result<(), String> {
+
+        let mut unsupported: Vec<(String, usize)> = Vec::new();
+
+        for (name, cell) in Self::members(module, "cells") {
+
+            let cell_type = cell.get("type").and_then(Json::as_str).ok_or(format!("cell {} has no type", name))?;
+
+            let binary = match cell_type {
+                "$_AND_" | "$_NAND_" => Some(Operator::And),
+                "$_OR_" | "$_NOR_" => Some(Operator::Or),
+                "$_XOR_" | "$_XNOR_" => Some(Operator::Xor),
+                _other => None,
+            };
+
+            if let Some(operator) = binary {
+                let left = self.input(cell, name, "A")?;
+                let right = self.input(cell, name, "B")?;
+                let output = self.output(cell, name, "Y")?;
+
+                match cell_type {
+                    "$_NAND_" | "$_NOR_" | "$_XNOR_" => {
+                        let result = self.netlist.add_gate_net(operator, left, right);
+                        self.netlist.add_not_to(result, output);
+                    },
+                    _other => self.netlist.add_gate(operator, left, right, output),
+                }
+                continue;
+            }
+
+            match cell_type {
+
+                "$_BUF_" => { },
+
+                "$_NOT_" => {
+                    let input = self.input(cell, name, "A")?;
+                    let output = self.output(cell, name, "Y")?;
+                    self.netlist.add_not_to(input, output);
+                },
+
+                "$_ANDNOT_" | "$_ORNOT_" => {
+                    let left = self.input(cell, name, "A")?;
+                    let right = self.input(cell, name, "B")?;
+                    let output = self.output(cell, name, "Y")?;
+                    let inverted = self.netlist.add_not(right);
+
+                    match cell_type {
+                        "$_ANDNOT_" => self.netlist.add_gate(Operator::And, left, inverted, output),
+                        _other => self.netlist.add_gate(Operator::Or, left, inverted, output),
+                    }
+                },
+
+                "$_MUX_" => {
+                    let left = self.input(cell, name, "A")?;
+                    let right = self.input(cell, name, "B")?;
+                    let select = self.input(cell, name, "S")?;
+                    let output = self.output(cell, name, "Y")?;
+                    self.netlist.add_multiplexer(left, right, select, output);
+                },
+
+                "$_DFF_P_" => self.register(cell, name, true, None)?,
+                "$_DFF_N_" => self.register(cell, name, false, None)?,
+                "$_DFFE_PP_" => self.register(cell, name, true, Some(true))?,
+                "$_DFFE_PN_" => self.register(cell, name, true, Some(false))?,
+                "$_DFFE_NP_" => self.register(cell, name, false, Some(true))?,
+                "$_DFFE_NN_" => self.register(cell, name, false, Some(false))?,
+
+                other => match unsupported.iter_mut().find(|(name, _count)| name == other) {
+                    Some((_name, count)) => *count += 1,
+                    None => unsupported.push((String::from(other), 1)),
+                },
+            }
+        }
+
+        if !unsupported.is_empty() {
+            let names: Vec<String> = unsupported.iter().map(|(name, count)| format!("{} ({})", name, count)).collect();
+            return Err(format!("unsupported cell types: {}", names.join(", ")));
+        }
+
+        return Ok(());
+    }
+
+    fn import_ports(&mut self, module: &Json) -> Result<(), String> {
+
+        for (name, port) in Self::members(module, "ports") {
+
+            let direction = match port.get("direction").and_then(Json::as_str) {
+                Some("input") => PortDirection::Input,
+                Some("output") => PortDirection::Output,
+                Some(other) => return Err(format!("port {} has unsupported direction {}", name, other)),
+                None => return Err(format!("port {} has no direction", name)),
+            };
+
+            let bits = parse_bits(port.get("bits").unwrap_or(&Json::Null), &format!("port {}", name))?;
+
+            for (index, bit) in bits.iter().enumerate() {
+
+                let port_name = match bits.len() {
+                    1 => name.clone(),
+                    _width => format!("{}[{}]", name, index),
+                };
+
+                if direction == PortDirection::Input {
+                    match self.resolve(*bit) {
+                        // the clock is provided by the emulator
+                        Bit::Net(number) if Some(number) == self.clock => continue,
+                        Bit::Net(..) => { },
+                        Bit::Constant(..) => return Err(format!("input port {} is connected to a constant", port_name)),
+                    }
+                }
+
+                let net = self.net(*bit);
+                self.netlist.add_port(&port_name, direction, net);
+            }
+        }
+
+        if let Some(clock) = self.clock {
+            if self.nets.contains_key(&clock) {
+                return Err(String::from("the clock is used as data, which the global clock of the emulator can not model"));
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn import_signals(&mut self, module: &Json) -> Result<(), String> {
+
+        for (name, netname) in Self::members(module, "netnames") {
+
+            if netname.get("hide_name").map(is_set).unwrap_or(false) {
+                continue;
+            }
+
+            let bits = parse_bits(netname.get("bits").unwrap_or(&Json::Null), name)?;
+            let nets: Option<Vec<Net>> = bits.iter().rev().map(|bit| match self.resolve(*bit) {
+                Bit::Net(number) => self.nets.get(&number).cloned(),
+                Bit::Constant(..) => None,
+            }).collect();
+
+            // signals that are constant or never used do not show up in the inspector
+            if let Some(nets) = nets {
+                if !nets.is_empty() {
+                    self.netlist.add_signal(name, nets);
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::super::{ Core, LogicState };
+    use super::super::Netlist;
+    use crate::types::FloatVector;
+
+    // q <= s ? b : a on the rising edge, as written by yosys after techmap
+    const MUX_REGISTER: &str = r#"{
+        "creator": "Yosys",
+        "modules": {
+            "mux_register": {
+                "attributes": { "top": "00000000000000000000000000000001" },
+                "ports": {
+                    "clk": { "direction": "input", "bits": [ 2 ] },
+                    "a": { "direction": "input", "bits": [ 3 ] },
+                    "b": { "direction": "input", "bits": [ 4 ] },
+                    "s": { "direction": "input", "bits": [ 5 ] },
+                    "y": { "direction": "output", "bits": [ 6 ] },
+                    "q": { "direction": "output", "bits": [ 7 ] }
+                },
+                "cells": {
+                    "$auto$1": {
+                        "type": "$_MUX_",
+                        "connections": { "A": [ 3 ], "B": [ 4 ], "S": [ 5 ], "Y": [ 6 ] }
+                    },
+                    "$auto$2": {
+                        "type": "$_DFF_P_",
+                        "connections": { "C": [ 2 ], "D": [ 6 ], "Q": [ 7 ] }
+                    }
+                },
+                "netnames": {
+                    "q": { "hide_name": 0, "bits": [ 7 ], "attributes": { "init": "1" } },
+                    "$auto$3": { "hide_name": 1, "bits": [ 6 ], "attributes": { } }
+                }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn cells_are_mapped() {
+
+        let netlist = Netlist::from_yosys_json(MUX_REGISTER).unwrap();
+        assert_eq!(netlist.name, "mux_register");
+
+        // the clock is provided by the emulator
+        let ports: Vec<&str> = netlist.ports.iter().map(|port| port.name.as_str()).collect();
+        assert_eq!(ports, vec!["a", "b", "s", "y", "q"]);
+
+        // a flip flop is a register on the falling edge followed by one on the rising edge
+        let edges: Vec<(bool, LogicState)> = netlist.registers.iter().map(|register| (register.rising, register.initial)).collect();
+        assert_eq!(edges, vec![(false, LogicState::High), (true, LogicState::High)]);
+
+        let inputs = vec![String::from("s"), String::from("a"), String::from("b")];
+        let table = netlist.truth_table(&inputs, &[String::from("y")], false).unwrap();
+        for (inputs, outputs) in &table.rows {
+            let expected = match inputs[0] {
+                LogicState::High => inputs[2],
+                _other => inputs[1],
+            };
+            assert_eq!(outputs, &vec![expected]);
+        }
+
+        // the selected input shows up at q one cycle later
+        let mut core = Core::from_netlist(netlist.name.clone(), &netlist, FloatVector::new(), false).unwrap();
+        let (a, b, s, q) = (core.find_port("a").unwrap(), core.find_port("b").unwrap(), core.find_port("s").unwrap(), core.find_port("q").unwrap());
+        let mut states = Vec::new();
+
+        for (select, left, right) in [(false, false, true), (true, false, true), (true, true, false), (false, true, false)].iter() {
+            core.set_port_state(s, LogicState::from_boolean(*select));
+            core.set_port_state(a, LogicState::from_boolean(*left));
+            core.set_port_state(b, LogicState::from_boolean(*right));
+            core.tick(true);
+            core.tick(false);
+            states.push(core.port_state(q));
+        }
+
+        assert_eq!(states, vec![LogicState::High, LogicState::Low, LogicState::High, LogicState::Low]);
+    }
+
+    #[test]
+    fn unsupported_cells_are_reported() {
+
+        let source = MUX_REGISTER.replace("$_MUX_", "$_AOI3_").replace("$_DFF_P_", "$_DLATCH_P_");
+        assert_eq!(Netlist::from_yosys_json(&source).unwrap_err(), "unsupported cell types: $_AOI3_ (1), $_DLATCH_P_ (1)");
+
+        let source = MUX_REGISTER.replace("\"S\": [ 5 ], ", "");
+        assert_eq!(Netlist::from_yosys_json(&source).unwrap_err(), "cell $auto$1: missing connection S");
+    }
+}
@@ -0,0 +1,597 @@
+use std::collections::HashMap;
+use std::fmt::{ Display, Formatter, Result as FormatResult };
+use std::mem::replace;
+use super::{ Core, LogicState, Operator, LabelSource };
+use super::logic::{ Output, Constant };
+use super::netlist::Driver;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Pass {
+    ConstantPropagation,
+    DuplicateGates,
+    DeadGates,
+    Splitters,
+}
+
+impl Pass {
+
+    // the order in which all passes are run, later passes clean up after earlier ones
+    pub const ALL: [Pass; 4] = [Pass::ConstantPropagation, Pass::DuplicateGates, Pass::DeadGates, Pass::Splitters];
+
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "constants" => return Ok(Pass::ConstantPropagation),
+            "duplicates" => return Ok(Pass::DuplicateGates),
+            "dead" => return Ok(Pass::DeadGates),
+            "splitters" => return Ok(Pass::Splitters),
+            other => return Err(format!("unknown optimization pass {}, expected constants, duplicates, dead or splitters", other)),
+        }
+    }
+}
+
+impl Display for Pass {
+
+    fn fmt(&self, formatter: &mut Formatter) -> FormatResult {
+        match self {
+            Pass::ConstantPropagation => return write!(formatter, "constant propagation"),
+            Pass::DuplicateGates => return write!(formatter, "duplicate gates"),
+            Pass::DeadGates => return write!(formatter, "dead gates"),
+            Pass::Splitters => return write!(formatter, "splitter chains"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PassReport {
+    pub pass: Pass,
+    pub changes: Vec<String>,
+}
+
+impl Display for PassReport {
+
+    fn fmt(&self, formatter: &mut Formatter) -> FormatResult {
+        match self.changes.len() {
+            1 => write!(formatter, "{}: 1 change", self.pass)?,
+            count => write!(formatter, "{}: {} changes", self.pass, count)?,
+        }
+
+        for change in &self.changes {
+            write!(formatter, "\n  {}", change)?;
+        }
+        return Ok(());
+    }
+}
+
+// splitters only fan out, so a tree is fully described by the sinks it reaches
+pub fn output_sinks(output: &Output) -> Vec<LabelSource> {
+    match output {
+        Output::Register(index) => return vec![LabelSource::Register(*index)],
+        Output::Gate(index, right) => return vec![LabelSource::Gate(*index, *right)],
+        Output::Port(index) => return vec![LabelSource::Port(*index)],
+        Output::Splitter(left, right) => {
+            let mut sinks = output_sinks(left);
+            sinks.extend(output_sinks(right));
+            return sinks;
+        },
+        Output::Disconnected => return Vec::new(),
+    }
+}
+
+fn sink_output(sink: LabelSource) -> Output {
+    match sink {
+        LabelSource::Register(index) => return Output::Register(index),
+        LabelSource::Gate(index, right) => return Output::Gate(index, right),
+        LabelSource::Port(index) => return Output::Port(index),
+    }
+}
+
+fn output_chain(sinks: &[LabelSource]) -> Output {
+    match sinks {
+        [] => return Output::Disconnected,
+        [sink] => return sink_output(*sink),
+        [sink, rest @ ..] => return Output::Splitter(Box::new(sink_output(*sink)), Box::new(output_chain(rest))),
+    }
+}
+
+fn splitter_count(output: &Output) -> usize {
+    match output {
+        Output::Splitter(left, right) => return 1 + splitter_count(left) + splitter_count(right),
+        _other => return 0,
+    }
+}
+
+// replaces every sink while keeping the structure of the tree
+fn map_sinks(output: &Output, map: &dyn Fn(LabelSource) -> Output) -> Output {
+    match output {
+        Output::Splitter(left, right) => return Output::Splitter(Box::new(map_sinks(left, map)), Box::new(map_sinks(right, map))),
+        Output::Disconnected => return Output::Disconnected,
+        sink => return map(output_sinks(sink)[0]),
+    }
+}
+
+fn state_name(state: LogicState) -> String {
+    return format!("{:?}", state).to_lowercase();
+}
+
+fn identity(operator: Operator) -> LogicState {
+    match operator {
+        Operator::And => return LogicState::High,
+        Operator::Or => return LogicState::Low,
+        Operator::Xor => return LogicState::Low,
+    }
+}
+
+impl Core {
+
+    pub fn optimize(&mut self, passes: &[Pass]) -> Vec<PassReport> {
+        let reports = passes.iter().map(|pass| self.run_pass(*pass)).collect();
+
+        // gates move to different indices, so collected statistics no longer line up
+        if self.activity.is_some() {
+            self.track_activity();
+        }
+
+        return reports;
+    }
+
+    // every pass keeps the states of ports and of observed gate inputs identical in every tick
+    pub fn run_pass(&mut self, pass: Pass) -> PassReport {
+
+        let changes = match pass {
+            Pass::ConstantPropagation => self.propagate_constants(),
+            Pass::DuplicateGates => self.merge_duplicate_gates(),
+            Pass::DeadGates => self.remove_dead_gates(),
+            Pass::Splitters => self.collapse_splitters(),
+        };
+
+        return PassReport {
+            pass: pass,
+            changes: changes,
+        };
+    }
+
+    fn driver_output(&mut self, driver: Driver) -> &mut Output {
+        match driver {
+            Driver::Gate(index) => return &mut self.gates[index].output,
+            Driver::Register(index) => return &mut self.registers[index].output,
+            Driver::Constant(index) => return &mut self.constants[index].output,
+            Driver::Port(index) => return self.ports[index].output.as_mut().unwrap(),
+        }
+    }
+
+    fn output_of(&self, driver: Driver) -> &Output {
+        match driver {
+            Driver::Gate(index) => return &self.gates[index].output,
+            Driver::Register(index) => return &self.registers[index].output,
+            Driver::Constant(index) => return &self.constants[index].output,
+            Driver::Port(index) => return self.ports[index].output.as_ref().unwrap(),
+        }
+    }
+
+    fn describe_driver(&self, driver: Driver) -> String {
+        match driver {
+            Driver::Gate(index) => return format!("gate {}", index),
+            Driver::Register(index) => return format!("register {}", index),
+            Driver::Constant(index) => return format!("constant {}", index),
+            Driver::Port(index) => return format!("port {}", self.ports[index].name),
+        }
+    }
+
+    fn output_drivers(&self) -> Vec<Driver> {
+        let mut drivers: Vec<Driver> = (0..self.gates.len()).map(Driver::Gate).collect();
+        drivers.extend((0..self.registers.len()).map(Driver::Register));
+        drivers.extend((0..self.constants.len()).map(Driver::Constant));
+        drivers.extend((0..self.ports.len()).filter(|index| self.ports[*index].is_input()).map(Driver::Port));
+        return drivers;
+    }
+
+    // the drivers of the left and right input of every gate
+    fn gate_drivers(&self) -> Vec<[Option<Driver>; 2]> {
+        let mut gate_drivers = vec![[None, None]; self.gates.len()];
+
+        for driver in self.output_drivers() {
+            for sink in output_sinks(self.output_of(driver)) {
+                if let LabelSource::Gate(index, right) = sink {
+                    gate_drivers[index][right as usize] = Some(driver);
+                }
+            }
+        }
+
+        return gate_drivers;
+    }
+
+    // gates with an input shown in the inspector or used by a signal are never removed
+    fn observed_gates(&self) -> Vec<bool> {
+        let mut observed = vec![false; self.gates.len()];
+        let mut sources: Vec<LabelSource> = self.signals.iter().flat_map(|signal| signal.sources.iter().cloned()).collect();
+        sources.extend(self.inspector.iter().flat_map(|inspector| inspector.sources()));
+
+        for source in sources {
+            if let LabelSource::Gate(index, _right) = source {
+                observed[index] = true;
+            }
+        }
+
+        return observed;
+    }
+
+    fn replace_sink(&mut self, driver: Driver, sink: LabelSource, replacement: Output) {
+        let output = self.driver_output(driver);
+        *output = map_sinks(output, &|other| match other == sink {
+            true => replacement.clone(),
+            false => sink_output(other),
+        });
+    }
+
+    fn detach_gate(&mut self, index: usize, drivers: [Option<Driver>; 2]) {
+        for (right, driver) in drivers.iter().enumerate() {
+            if let Some(driver) = driver {
+                self.replace_sink(*driver, LabelSource::Gate(index, right == 1), Output::Disconnected);
+            }
+        }
+    }
+
+    fn remove_gates(&mut self, removed: &[bool]) {
+
+        let mut map = Vec::new();
+        let mut count = 0;
+        for removed in removed {
+            map.push(count);
+            count += !*removed as usize;
+        }
+
+        let remap = |sink: LabelSource| match sink {
+            LabelSource::Gate(index, right) => match removed[index] {
+                true => return Output::Disconnected,
+                false => return Output::Gate(map[index], right),
+            },
+            other => return sink_output(other),
+        };
+
+        for driver in self.output_drivers() {
+            let output = self.driver_output(driver);
+            *output = map_sinks(output, &remap);
+        }
+
+        let mut index = 0;
+        self.gates.retain(|_gate| {
+            index += 1;
+            !removed[index - 1]
+        });
+
+        // observed gates are never removed, so every source still exists
+        let remap_source = |source: LabelSource| match source {
+            LabelSource::Gate(index, right) => return LabelSource::Gate(map[index], right),
+            other => return other,
+        };
+
+        for signal in &mut self.signals {
+            signal.sources.iter_mut().for_each(|source| *source = remap_source(*source));
+        }
+
+        if let Some(inspector) = &mut self.inspector {
+            inspector.remap_sources(&remap_source);
+        }
+    }
+
+    // gates that only see constants become constants, gates that combine a signal with their
+    // identity element pass it on unchanged and are bypassed, both fire in exactly the same ticks
+    fn propagate_constants(&mut self) -> Vec<String> {
+
+        let observed = self.observed_gates();
+        let mut removed = vec![false; self.gates.len()];
+        let mut changes = Vec::new();
+
+        loop {
+            let drivers = self.gate_drivers();
+            let candidate = (0..self.gates.len()).find(|index| {
+                let constant = |driver: Option<Driver>| match driver {
+                    Some(Driver::Constant(constant)) => Some(self.constants[constant].state),
+                    _other => None,
+                };
+
+                let states = [constant(drivers[*index][0]), constant(drivers[*index][1])];
+                let bypass = states.iter().any(|state| *state == Some(identity(self.gates[*index].operator))) && drivers[*index].iter().all(|driver| *driver != Some(Driver::Gate(*index)));
+                return !removed[*index] && !output_sinks(&self.gates[*index].output).is_empty() && (states.iter().all(Option::is_some) || bypass);
+            });
+
+            let index = match candidate {
+                Some(index) => index,
+                None => break,
+            };
+
+            let gate_drivers = drivers[index];
+            let output = replace(&mut self.gates[index].output, Output::Disconnected);
+
+            match gate_drivers {
+                [Some(Driver::Constant(left)), Some(Driver::Constant(right))] => {
+                    let state = self.constants[left].state.operator(self.constants[right].state, self.gates[index].operator);
+                    self.constants.push(Constant::new(state, output));
+                    changes.push(format!("gate {} only depends on constants and was replaced by a {} constant", index, state_name(state)));
+                },
+
+                _other => {
+                    let operator = self.gates[index].operator;
+                    let data_right = match gate_drivers[0] {
+                        Some(Driver::Constant(constant)) if self.constants[constant].state == identity(operator) => true,
+                        _other => false,
+                    };
+
+                    // observed gates keep receiving their input, an input without a driver never fires the gate
+                    if let Some(driver) = gate_drivers[data_right as usize] {
+                        let sink = LabelSource::Gate(index, data_right);
+                        let replacement = match observed[index] {
+                            true => Output::Splitter(Box::new(sink_output(sink)), Box::new(output)),
+                            false => output,
+                        };
+                        self.replace_sink(driver, sink, replacement);
+                    }
+
+                    changes.push(format!("gate {} passes its input through unchanged and was bypassed", index));
+                },
+            }
+
+            if !observed[index] {
+                self.detach_gate(index, gate_drivers);
+                removed[index] = true;
+            }
+        }
+
+        self.remove_gates(&removed);
+        return changes;
+    }
+
+    // gates with the same operator and the same drivers always fire together with the same state
+    fn merge_duplicate_gates(&mut self) -> Vec<String> {
+
+        let observed = self.observed_gates();
+        let mut removed = vec![false; self.gates.len()];
+        let mut changes = Vec::new();
+
+        loop {
+            let drivers = self.gate_drivers();
+            let mut seen: HashMap<(usize, Driver, Driver), usize> = HashMap::new();
+            let mut duplicate = None;
+
+            for index in 0..self.gates.len() {
+                let (left, right) = match drivers[index] {
+                    [Some(left), Some(right)] if left != Driver::Gate(index) && right != Driver::Gate(index) => (left.min(right), left.max(right)),
+                    _other => continue,
+                };
+
+                if removed[index] || output_sinks(&self.gates[index].output).is_empty() {
+                    continue;
+                }
+
+                let key = (self.gates[index].operator as usize, left, right);
+                match seen.get(&key) {
+                    Some(original) => {
+                        duplicate = Some((*original, index));
+                        break;
+                    },
+                    None => { seen.insert(key, index); },
+                }
+            }
+
+            let (original, index) = match duplicate {
+                Some(duplicate) => duplicate,
+                None => break,
+            };
+
+            let output = replace(&mut self.gates[index].output, Output::Disconnected);
+            let original_output = replace(&mut self.gates[original].output, Output::Disconnected);
+            self.gates[original].output = Output::Splitter(Box::new(original_output), Box::new(output));
+            changes.push(format!("gate {} duplicates gate {} and was merged into it", index, original));
+
+            if !observed[index] {
+                self.detach_gate(index, drivers[index]);
+                removed[index] = true;
+            }
+        }
+
+        self.remove_gates(&removed);
+        return changes;
+    }
+
+    // removing a gate can leave the gates before it without fan-out, so this runs until nothing changes
+    fn remove_dead_gates(&mut self) -> Vec<String> {
+
+        let observed = self.observed_gates();
+        let mut removed = vec![false; self.gates.len()];
+        let mut changes = Vec::new();
+
+        loop {
+            let drivers = self.gate_drivers();
+            let dead: Vec<usize> = (0..self.gates.len()).filter(|index| !removed[*index] && !observed[*index] && output_sinks(&self.gates[*index].output).is_empty()).collect();
+
+            if dead.is_empty() {
+                break;
+            }
+
+            for index in dead {
+                self.detach_gate(index, drivers[index]);
+                removed[index] = true;
+                changes.push(format!("gate {} has no observable fan-out and was removed", index));
+            }
+        }
+
+        self.remove_gates(&removed);
+
+        // constants are not part of snapshots, so unused ones can simply be dropped
+        let count = self.constants.len();
+        self.constants.retain(|constant| !output_sinks(&constant.output).is_empty());
+        if self.constants.len() < count {
+            changes.push(format!("{} constants without fan-out were removed", count - self.constants.len()));
+        }
+
+        return changes;
+    }
+
+    // every sink is reached through a single chain, disconnected ends and repeated sinks are dropped
+    fn collapse_splitters(&mut self) -> Vec<String> {
+
+        let mut changes = Vec::new();
+        for driver in self.output_drivers() {
+
+            let output = self.driver_output(driver);
+            let mut sinks = Vec::new();
+            for sink in output_sinks(output) {
+                if !sinks.contains(&sink) {
+                    sinks.push(sink);
+                }
+            }
+
+            let collapsed = output_chain(&sinks);
+            let (before, after) = (splitter_count(output), splitter_count(&collapsed));
+            *output = collapsed;
+
+            if before != after {
+                changes.push(format!("{} reaches {} sinks through {} splitters instead of {}", self.describe_driver(driver), sinks.len(), after, before));
+            }
+        }
+
+        return changes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Pass;
+    use super::super::{ Core, LogicState, LabelSource, Operator };
+    use super::super::fuzz::Case;
+    use super::super::netlist::{ Netlist, PortDirection, Random };
+    use crate::types::FloatVector;
+
+    const CASES: u64 = 500;
+
+    fn core(netlist: &Netlist) -> Core {
+        return Core::from_netlist(netlist.name.clone(), netlist, FloatVector::new(), true).unwrap();
+    }
+
+    // runs both cores through the stimulus and compares everything that can be observed from outside
+    fn compare(original: &mut Core, optimized: &mut Core, stimulus: &[Vec<LogicState>], context: &str) {
+
+        assert_eq!(original.signals.len(), optimized.signals.len(), "{}", context);
+
+        for (cycle, states) in stimulus.iter().enumerate() {
+
+            for (index, state) in states.iter().enumerate() {
+                if original.ports[index].is_input() {
+                    original.set_port_state(index, *state);
+                    optimized.set_port_state(index, *state);
+                }
+            }
+
+            for rising in [true, false].iter() {
+                original.tick(*rising);
+                optimized.tick(*rising);
+
+                for index in 0..original.ports.len() {
+                    assert_eq!(original.port_state(index), optimized.port_state(index), "port {} in cycle {}\n{}", original.ports[index].name, cycle, context);
+                }
+
+                for index in 0..original.registers.len() {
+                    assert_eq!(original.registers[index].state, optimized.registers[index].state, "register {} in cycle {}\n{}", index, cycle, context);
+                }
+
+                for index in 0..original.signals.len() {
+                    assert_eq!(original.signal_state(index), optimized.signal_state(index), "signal {} in cycle {}\n{}", original.signals[index].name, cycle, context);
+                }
+
+                assert_eq!(original.states(), optimized.states(), "inspector in cycle {}\n{}", cycle, context);
+            }
+        }
+    }
+
+    // a generated case where some gate outputs are observed by signals, so the passes have to keep and renumber them
+    fn observed_case(seed: u64) -> Case {
+        let mut random = Random::new(seed);
+        let mut case = Case::generate(&mut random);
+        let outputs: Vec<usize> = case.netlist.gates.iter().map(|gate| gate.output).collect();
+
+        for (index, net) in outputs.into_iter().enumerate() {
+            if random.below(3) == 0 {
+                case.netlist.add_signal(&format!("s{}", index), vec![net]);
+            }
+        }
+
+        return case;
+    }
+
+    // returns the number of changes made by the last pass, so every test can check that its pass was exercised
+    fn check_passes(passes: &[Pass]) -> usize {
+
+        let mut changes = 0;
+        for seed in 0..CASES {
+
+            let case = observed_case(seed);
+            let mut original = core(&case.netlist);
+            let mut optimized = core(&case.netlist);
+            let reports = optimized.optimize(passes);
+            changes += reports.last().unwrap().changes.len();
+
+            let context = format!("seed {}\n{}", seed, reports.iter().map(|report| report.to_string()).collect::<Vec<String>>().join("\n"));
+            compare(&mut original, &mut optimized, &case.stimulus, &context);
+        }
+
+        return changes;
+    }
+
+    #[test]
+    fn constant_propagation_is_equivalent() {
+        assert!(check_passes(&[Pass::ConstantPropagation]) > 0);
+    }
+
+    #[test]
+    fn duplicate_gates_are_equivalent() {
+        assert!(check_passes(&[Pass::DuplicateGates]) > 0);
+    }
+
+    #[test]
+    fn dead_gates_are_equivalent() {
+        assert!(check_passes(&[Pass::DeadGates]) > 0);
+    }
+
+    // lowered netlists only contain plain chains, merging duplicates is what nests them
+    #[test]
+    fn splitters_are_equivalent() {
+        assert!(check_passes(&[Pass::DuplicateGates, Pass::Splitters]) > 0);
+    }
+
+    #[test]
+    fn all_passes_are_equivalent() {
+        assert!(check_passes(&Pass::ALL) > 0);
+    }
+
+    // gate 0 is dead and gate 2 is observed, so removing the first one has to move the signal and the inspector along
+    #[test]
+    fn removed_gates_renumber_observers() {
+
+        let mut netlist = Netlist::new("remap");
+        let (a, b) = (netlist.add_net(), netlist.add_net());
+        netlist.add_port("a", PortDirection::Input, a);
+        netlist.add_port("b", PortDirection::Input, b);
+
+        let (dead, middle, observed, output) = (netlist.add_net(), netlist.add_net(), netlist.add_net(), netlist.add_net());
+        netlist.add_gate(Operator::And, a, b, dead);
+        netlist.add_gate(Operator::Xor, a, b, middle);
+        netlist.add_gate(Operator::Or, middle, b, output);
+        netlist.add_port("y", PortDirection::Output, output);
+        netlist.add_gate(Operator::And, middle, a, observed);
+        netlist.add_signal("middle", vec![middle]);
+
+        let mut original = core(&netlist);
+        let mut optimized = core(&netlist);
+
+        // the xor output is first read by the or gate, which is gate 2 before and gate 1 after the removal
+        assert_eq!(original.signals[0].sources, vec![LabelSource::Gate(2, false)]);
+        let reports = optimized.optimize(&[Pass::DeadGates]);
+        assert_eq!(reports[0].changes.len(), 2);
+        assert_eq!(optimized.gates.len(), 2);
+        assert_eq!(optimized.signals[0].sources, vec![LabelSource::Gate(1, false)]);
+
+        let stimulus: Vec<Vec<LogicState>> = (0..8).map(|cycle| vec![LogicState::from_boolean(cycle & 1 == 1), LogicState::from_boolean(cycle & 2 == 2), LogicState::Floating]).collect();
+        compare(&mut original, &mut optimized, &stimulus, "remap");
+    }
+}
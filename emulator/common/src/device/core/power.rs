@@ -0,0 +1,266 @@
+use super::{ Core, Operator };
+use super::netlist::{ Netlist, Net, Sink };
+use std::fmt;
+
+// capacitance weights for every kind of element along with the supply voltage and the clock frequency,
+// all in arbitrary units so the default of one everywhere gives energy in toggles
+#[derive(Clone, Debug)]
+pub struct Technology {
+    pub and: f64,
+    pub or: f64,
+    pub xor: f64,
+    pub register: f64,
+    pub voltage: f64,
+    pub frequency: f64,
+}
+
+impl Technology {
+
+    pub fn unit() -> Self {
+        return Self {
+            and: 1.0,
+            or: 1.0,
+            xor: 1.0,
+            register: 1.0,
+            voltage: 1.0,
+            frequency: 1.0,
+        };
+    }
+
+    // one "name value" pair per line, # starts a comment and anything not listed keeps a weight of one
+    pub fn parse(source: &str) -> Result<Self, String> {
+
+        let mut technology = Self::unit();
+        for (index, line) in source.lines().enumerate() {
+
+            let line = line.split('#').next().unwrap();
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let (name, value) = match tokens.as_slice() {
+                [] => continue,
+                [name, value] => (*name, *value),
+                _ => return Err(format!("line {}: expected a name and a value", index + 1)),
+            };
+
+            let value: f64 = value.parse().map_err(|_| format!("line {}: invalid value {}", index + 1, value))?;
+            if !value.is_finite() {
+                return Err(format!("line {}: {} is not finite", index + 1, value));
+            }
+            if value < 0.0 {
+                return Err(format!("line {}: {} is negative", index + 1, value));
+            }
+
+            match name {
+                "and" => technology.and = value,
+                "or" => technology.or = value,
+                "xor" => technology.xor = value,
+                "register" => technology.register = value,
+                "voltage" => technology.voltage = value,
+                "frequency" => technology.frequency = value,
+                other => return Err(format!("line {}: unknown name {}, expected and, or, xor, register, voltage or frequency", index + 1, other)),
+            }
+        }
+
+        return Ok(technology);
+    }
+
+    pub fn capacitance(&self, operator: Operator) -> f64 {
+        match operator {
+            Operator::And => return self.and,
+            Operator::Or => return self.or,
+            Operator::Xor => return self.xor,
+        }
+    }
+
+    // charging and discharging a capacitance once each takes C * V^2, so a single toggle takes half of it
+    pub fn toggle_energy(&self, capacitance: f64) -> f64 {
+        return 0.5 * capacitance * self.voltage * self.voltage;
+    }
+
+    // the energy of a tick from the number of and, or and xor gate outputs and registers that toggled
+    fn tick_energy(&self, counts: &[usize; 4]) -> f64 {
+        let capacitances = [self.and, self.or, self.xor, self.register];
+        return counts.iter().zip(capacitances.iter()).map(|(count, capacitance)| self.toggle_energy(*capacitance) * *count as f64).sum();
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PowerReport {
+    pub cycles: usize,
+    pub energy: f64,
+    pub average: f64,
+    pub peak: f64,
+    // energy by hierarchy group, largest first
+    pub groups: Vec<(String, f64)>,
+    // the power of every cycle
+    pub trace: Vec<f64>,
+}
+
+impl fmt::Display for PowerReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        write!(f, "energy {:.3} over {} cycles, average power {:.3}, peak {:.3}", self.energy, self.cycles, self.average, self.peak)?;
+
+        let width = self.groups.iter().map(|(name, _energy)| name.len()).max().unwrap_or(0);
+        for (name, energy) in &self.groups {
+            let share = match self.energy > 0.0 {
+                true => energy * 100.0 / self.energy,
+                false => 0.0,
+            };
+            write!(f, "\n  {:width$}  {:>10.3}  {:>5.1}%", name, energy, share, width = width)?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl Netlist {
+
+    // the group of a net is the path of its signal without the last part, unnamed logic belongs to whatever it feeds
+    fn net_group(&self, net: Net, sinks: &[Vec<Sink>], groups: &mut Vec<Option<String>>) -> String {
+
+        if let Some(group) = &groups[net] {
+            return group.clone();
+        }
+
+        // marks the net so that a loop through unnamed logic ends up in the root group
+        groups[net] = Some(self.name.clone());
+
+        // flattened netlists keep the hierarchy in names like instance.signal
+        let group = match self.signal_name(net) {
+            Some(name) => match name.split('[').next().unwrap().rfind(['/', '.']) {
+                Some(position) => format!("{}/{}", self.name, &name[..position]),
+                None => self.name.clone(),
+            },
+            None => match sinks[net].first() {
+                Some(Sink::Gate(index, _right)) => self.net_group(self.gates[*index].output, sinks, groups),
+                Some(Sink::Register(index)) => self.net_group(self.registers[*index].output, sinks, groups),
+                _ => self.name.clone(),
+            },
+        };
+
+        groups[net] = Some(group.clone());
+        return group;
+    }
+
+    // the groups of every gate followed by the ones of every register
+    pub(super) fn element_groups(&self) -> Result<(Vec<String>, Vec<String>), String> {
+        let sinks = self.sinks()?;
+        let mut groups = vec![None; self.net_count];
+        let gates = self.gates.iter().map(|gate| self.net_group(gate.output, &sinks, &mut groups)).collect();
+        let registers = self.registers.iter().map(|register| self.net_group(register.output, &sinks, &mut groups)).collect();
+        return Ok((gates, registers));
+    }
+}
+
+impl Core {
+
+    // power is estimated from the toggles collected from this point on, and shown in the inspector while debugging
+    pub fn estimate_power(&mut self, technology: Technology) {
+        if self.activity.is_none() {
+            self.track_activity();
+        }
+        self.technology = Some(technology);
+    }
+
+    pub(super) fn sample_power(&mut self) {
+        if let (Some(technology), Some(activity), Some(inspector)) = (&self.technology, &self.activity, &mut self.inspector) {
+            if let Some((_rising, counts)) = activity.recorded().last() {
+                // a tick takes half a cycle
+                inspector.push_analog("power", technology.tick_energy(counts) * 2.0 * technology.frequency);
+            }
+        }
+    }
+
+    pub fn power(&self) -> Result<Option<PowerReport>, String> {
+
+        let (technology, activity) = match (&self.technology, &self.activity) {
+            (Some(technology), Some(activity)) => (technology, activity),
+            _ => return Ok(None),
+        };
+
+        let (gate_groups, register_groups) = self.netlist().element_groups()?;
+
+        let mut groups: Vec<(String, f64)> = Vec::new();
+        let mut add = |group: &String, energy: f64| match groups.iter_mut().find(|(name, _energy)| name == group) {
+            Some((_name, total)) => *total += energy,
+            None => groups.push((group.clone(), energy)),
+        };
+
+        for ((toggles, gate), group) in activity.gates.iter().zip(self.gates.iter()).zip(gate_groups.iter()) {
+            add(group, toggles[2].toggles() as f64 * technology.toggle_energy(technology.capacitance(gate.operator)));
+        }
+
+        for (toggles, group) in activity.registers.iter().zip(register_groups.iter()) {
+            add(group, toggles.toggles() as f64 * technology.toggle_energy(technology.register));
+        }
+
+        groups.sort_by(|first, second| second.1.total_cmp(&first.1).then_with(|| first.0.cmp(&second.0)));
+
+        // a cycle starts with a rising tick, a falling tick before the first rising one is a cycle of its own
+        let mut trace: Vec<f64> = Vec::new();
+        for (rising, counts) in activity.recorded() {
+            let energy = technology.tick_energy(counts) * technology.frequency;
+            match trace.last_mut() {
+                Some(last) if !*rising => *last += energy,
+                _ => trace.push(energy),
+            }
+        }
+        let energy = groups.iter().map(|(_name, energy)| energy).sum();
+        let average = match trace.len() {
+            0 => 0.0,
+            cycles => energy / cycles as f64 * technology.frequency,
+        };
+
+        return Ok(Some(PowerReport {
+            cycles: trace.len(),
+            energy: energy,
+            average: average,
+            peak: trace.iter().cloned().fold(0.0, f64::max),
+            groups: groups,
+            trace: trace,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Technology;
+    use super::super::{ Core, Netlist, LogicState };
+    use crate::types::FloatVector;
+
+    const TOGGLE: &str = "
+        module toggle(input clk, input en, output q);
+            reg master = 1'b0;
+            reg q = 1'b0;
+            always @(negedge clk) master <= q ^ en;
+            always @(posedge clk) q <= master;
+        endmodule
+    ";
+
+    // the power trace along with the energy of every tick
+    fn run(edges: &[bool]) -> (Vec<f64>, Vec<f64>) {
+
+        let netlist = Netlist::from_verilog(TOGGLE).unwrap();
+        let mut core = Core::from_netlist(String::from("toggle"), &netlist, FloatVector::new(), false).unwrap();
+        let technology = Technology::unit();
+        core.estimate_power(technology.clone());
+        core.set_port_state(core.find_port("en").unwrap(), LogicState::High);
+
+        edges.iter().for_each(|rising| core.tick(*rising));
+        let ticks = core.activity.as_ref().unwrap().recorded().iter().map(|(_rising, counts)| technology.tick_energy(counts)).collect();
+        return (core.power().unwrap().unwrap().trace, ticks);
+    }
+
+    #[test]
+    fn cycles_follow_the_tick_edges() {
+
+        let (trace, ticks) = run(&[true, false, true, false]);
+        assert_eq!(trace, vec![ticks[0] + ticks[1], ticks[2] + ticks[3]]);
+
+        // the leading falling tick is a cycle of its own instead of shifting every pair
+        let (trace, ticks) = run(&[false, true, false, true, false]);
+        assert!(ticks.iter().any(|energy| *energy > 0.0));
+        assert_eq!(trace, vec![ticks[0], ticks[1] + ticks[2], ticks[3] + ticks[4]]);
+    }
+}
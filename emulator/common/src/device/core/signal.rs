@@ -0,0 +1,30 @@
+use super::{ LogicState, ValueState, Register, Gate, Port, LabelSource };
+
+#[derive(Clone, Debug)]
+pub struct Signal {
+    pub name: String,
+    pub sources: Vec<LabelSource>,
+}
+
+impl Signal {
+
+    pub fn new(name: String, sources: Vec<LabelSource>) -> Self {
+        return Self {
+            name: name,
+            sources: sources,
+        };
+    }
+
+    pub fn is_bus(&self) -> bool {
+        return self.sources.len() > 1;
+    }
+
+    pub fn logic_state(&self, registers: &[Register], gates: &[Gate], ports: &[Port]) -> LogicState {
+        return self.sources[0].get_state(registers, gates, ports);
+    }
+
+    // buses are read starting with the most significant bit
+    pub fn value_state(&self, registers: &[Register], gates: &[Gate], ports: &[Port]) -> ValueState {
+        return ValueState::from_logic_states(self.sources.iter().map(|source| source.get_state(registers, gates, ports)));
+    }
+}
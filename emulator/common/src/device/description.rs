@@ -0,0 +1,134 @@
+// a device description lists the cores of a device and the wires between their ports
+//
+//   # comment
+//   core cpu cpu.v
+//   core uart uart.json
+//   wire cpu.tx -> uart.rx
+
+use super::Core;
+
+#[derive(Clone, Debug)]
+pub struct CoreDescription {
+    pub name: String,
+    pub filename: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct WireDescription {
+    pub source: (String, String),
+    pub target: (String, String),
+}
+
+// a wire with its cores and ports resolved to indices
+#[derive(Copy, Clone, Debug)]
+pub struct Wire {
+    pub source: (usize, usize),
+    pub target: (usize, usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct DeviceDescription {
+    pub cores: Vec<CoreDescription>,
+    pub wires: Vec<WireDescription>,
+}
+
+impl DeviceDescription {
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+
+        let mut cores: Vec<CoreDescription> = Vec::new();
+        let mut wires = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap();
+            let words: Vec<&str> = line.split_whitespace().collect();
+
+            match words.as_slice() {
+
+                [] => { },
+
+                ["core", name, filename] => {
+
+                    if name.contains(['.', ':']) {
+                        return Err(format!("line {}: core name {} may not contain '.' or ':'", line_number, name));
+                    }
+
+                    if cores.iter().any(|core| core.name == *name) {
+                        return Err(format!("line {}: core {} is declared twice", line_number, name));
+                    }
+
+                    cores.push(CoreDescription {
+                        name: String::from(*name),
+                        filename: String::from(*filename),
+                    });
+                },
+
+                ["wire", source, "->", target] => {
+                    wires.push(WireDescription {
+                        source: parse_port(source, line_number)?,
+                        target: parse_port(target, line_number)?,
+                    });
+                },
+
+                [keyword, ..] if *keyword == "core" || *keyword == "wire" => return Err(format!("line {}: malformed {} declaration", line_number, keyword)),
+
+                [other, ..] => return Err(format!("line {}: unknown declaration {}", line_number, other)),
+            }
+        }
+
+        if cores.is_empty() {
+            return Err(String::from("device does not declare any cores"));
+        }
+
+        return Ok(Self {
+            cores: cores,
+            wires: wires,
+        });
+    }
+
+    // cores have to be loaded in the order they are declared in
+    pub fn resolve_wires(&self, cores: &[Core]) -> Result<Vec<Wire>, String> {
+
+        let mut wires: Vec<Wire> = Vec::new();
+
+        for wire in &self.wires {
+
+            let source = resolve_port(&wire.source, cores)?;
+            let target = resolve_port(&wire.target, cores)?;
+
+            if cores[source.0].ports()[source.1].is_input() {
+                return Err(format!("{}.{} is an input port and can not drive a wire", wire.source.0, wire.source.1));
+            }
+
+            if !cores[target.0].ports()[target.1].is_input() {
+                return Err(format!("{}.{} is an output port and can not be driven by a wire", wire.target.0, wire.target.1));
+            }
+
+            if wires.iter().any(|other| other.target == target) {
+                return Err(format!("{}.{} is driven by multiple wires", wire.target.0, wire.target.1));
+            }
+
+            wires.push(Wire {
+                source: source,
+                target: target,
+            });
+        }
+
+        return Ok(wires);
+    }
+}
+
+fn resolve_port((core_name, port_name): &(String, String), cores: &[Core]) -> Result<(usize, usize), String> {
+    let core = cores.iter().position(|core| core.name() == core_name).ok_or(format!("no core named {}", core_name))?;
+    let port = cores[core].find_port(port_name)?;
+    return Ok((core, port));
+}
+
+fn parse_port(source: &str, line_number: usize) -> Result<(String, String), String> {
+    match source.find('.') {
+        Some(separator) => return Ok((String::from(&source[..separator]), String::from(&source[separator + 1..]))),
+        None => return Err(format!("line {}: expected core.port, found {}", line_number, source)),
+    }
+}
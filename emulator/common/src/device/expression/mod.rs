@@ -0,0 +1,356 @@
+mod token;
+mod parser;
+
+use super::core::{ LogicState, ValueState, Operator };
+use self::parser::Parser;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Operation {
+    And,
+    Or,
+    Xor,
+    Add,
+    Subtract,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Function {
+    Rose,
+    Fell,
+    Changed,
+    Unknown,
+}
+
+impl Function {
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rose" => return Some(Function::Rose),
+            "fell" => return Some(Function::Fell),
+            "changed" => return Some(Function::Changed),
+            "unknown" => return Some(Function::Unknown),
+            _other => return None,
+        }
+    }
+}
+
+// single bit signals evaluate to logic states and buses to values, the two convert into each other as needed
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Value {
+    Logic(LogicState),
+    Number(ValueState),
+}
+
+impl Value {
+
+    pub fn from_signal(state: ValueState, bus: bool) -> Self {
+        match bus {
+            true => return Value::Number(state),
+            false => return Value::Logic(Value::Number(state).to_logic()),
+        }
+    }
+
+    pub fn to_logic(&self) -> LogicState {
+        match self {
+            Value::Logic(state) => return *state,
+            Value::Number(ValueState::Stable(value)) => return LogicState::from_boolean(*value != 0),
+            Value::Number(ValueState::Floating) => return LogicState::Floating,
+            Value::Number(ValueState::Metastable) => return LogicState::Metastable,
+        }
+    }
+
+    pub fn to_number(&self) -> ValueState {
+        match self {
+            Value::Number(state) => return *state,
+            Value::Logic(LogicState::High) => return ValueState::Stable(1),
+            Value::Logic(LogicState::Low) => return ValueState::Stable(0),
+            Value::Logic(LogicState::Floating) => return ValueState::Floating,
+            Value::Logic(LogicState::Metastable) => return ValueState::Metastable,
+        }
+    }
+
+    // unknown values are never true
+    pub fn is_true(&self) -> bool {
+        return self.to_logic() == LogicState::High;
+    }
+
+    fn not(&self) -> Self {
+        match self.to_logic() {
+            LogicState::High => return Value::Logic(LogicState::Low),
+            LogicState::Low => return Value::Logic(LogicState::High),
+            unknown => return Value::Logic(unknown),
+        }
+    }
+
+    fn binary(operation: Operation, left: Self, right: Self) -> Self {
+        match operation {
+
+            Operation::And | Operation::Or | Operation::Xor => {
+
+                // a number combined with a single bit is used as a truth value, so 0 & x stays low even if x is unknown
+                if let (Value::Logic(..), _) | (_, Value::Logic(..)) = (left, right) {
+                    return Value::Logic(logic_operation(operation, left.to_logic(), right.to_logic()));
+                }
+
+                // a known zero decides an and of two numbers
+                if operation == Operation::And && (left.to_number() == ValueState::Stable(0) || right.to_number() == ValueState::Stable(0)) {
+                    return Value::Number(ValueState::Stable(0));
+                }
+
+                return Value::Number(number_operation(left.to_number(), right.to_number(), |left, right| match operation {
+                    Operation::And => left & right,
+                    Operation::Or => left | right,
+                    _other => left ^ right,
+                }));
+            },
+
+            Operation::Add => return Value::Number(number_operation(left.to_number(), right.to_number(), u64::wrapping_add)),
+
+            Operation::Subtract => return Value::Number(number_operation(left.to_number(), right.to_number(), u64::wrapping_sub)),
+
+            comparison => {
+                let result = number_operation(left.to_number(), right.to_number(), |left, right| {
+                    let result = match comparison {
+                        Operation::Equal => left == right,
+                        Operation::NotEqual => left != right,
+                        Operation::Less => left < right,
+                        Operation::LessEqual => left <= right,
+                        Operation::Greater => left > right,
+                        _other => left >= right,
+                    };
+                    result as u64
+                });
+                return Value::Logic(Value::Number(result).to_logic());
+            },
+        }
+    }
+}
+
+// floating wins over metastable, the same way it does when gates are evaluated
+fn unknown_state(left: LogicState, right: LogicState) -> LogicState {
+    match left.is_floating() || right.is_floating() {
+        true => return LogicState::Floating,
+        false => return LogicState::Metastable,
+    }
+}
+
+// a known low input decides an and, a known high input decides an or, even if the other input is unknown
+fn logic_operation(operation: Operation, left: LogicState, right: LogicState) -> LogicState {
+    let known = |state: LogicState| !state.is_floating() && !state.is_metastable();
+
+    match operation {
+        Operation::And if left == LogicState::Low || right == LogicState::Low => return LogicState::Low,
+        Operation::Or if left == LogicState::High || right == LogicState::High => return LogicState::High,
+        _other if !known(left) || !known(right) => return unknown_state(left, right),
+        _other => {
+            let operator = match operation {
+                Operation::And => Operator::And,
+                Operation::Or => Operator::Or,
+                _other => Operator::Xor,
+            };
+            return left.operator(right, operator);
+        },
+    }
+}
+
+fn number_operation<F: Fn(u64, u64) -> u64>(left: ValueState, right: ValueState, function: F) -> ValueState {
+    match (left, right) {
+        (ValueState::Stable(left), ValueState::Stable(right)) => return ValueState::Stable(function(left, right)),
+        (ValueState::Floating, _) | (_, ValueState::Floating) => return ValueState::Floating,
+        _other => return ValueState::Metastable,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Expression {
+    Number(u64),
+    Signal(usize),
+    Not(Box<Expression>),
+    Binary(Operation, Box<Expression>, Box<Expression>),
+    Function(Function, Box<Expression>),
+}
+
+impl Expression {
+
+    // every signal name is passed to resolve, which turns it into an index that is later passed to the evaluator
+    pub fn parse(source: &str, resolve: &mut dyn FnMut(&str) -> Result<usize, String>) -> Result<Self, String> {
+        return Parser::parse(source, resolve);
+    }
+
+    pub fn signals(&self) -> Vec<usize> {
+        match self {
+            Expression::Number(..) => return Vec::new(),
+            Expression::Signal(index) => return vec![*index],
+            Expression::Not(operand) => return operand.signals(),
+            Expression::Function(_function, argument) => return argument.signals(),
+
+            Expression::Binary(_operation, left, right) => {
+                let mut signals = left.signals();
+                signals.extend(right.signals());
+                return signals;
+            },
+        }
+    }
+
+    // current and previous return the value of a signal in the current and the previous half cycle
+    pub fn evaluate(&self, current: &dyn Fn(usize) -> Value, previous: &dyn Fn(usize) -> Value) -> Value {
+        match self {
+
+            Expression::Number(value) => return Value::Number(ValueState::Stable(*value)),
+
+            Expression::Signal(index) => return current(*index),
+
+            Expression::Not(operand) => return operand.evaluate(current, previous).not(),
+
+            Expression::Binary(operation, left, right) => {
+                let left = left.evaluate(current, previous);
+                let right = right.evaluate(current, previous);
+                return Value::binary(*operation, left, right);
+            },
+
+            Expression::Function(function, argument) => {
+                let now = argument.evaluate(current, previous);
+
+                match function {
+
+                    Function::Unknown => {
+                        let unknown = now.to_logic().is_floating() || now.to_logic().is_metastable();
+                        return Value::Logic(LogicState::from_boolean(unknown));
+                    },
+
+                    _other => {
+                        let before = argument.evaluate(previous, previous);
+
+                        match function {
+                            Function::Rose => return Value::binary(Operation::And, before.not(), Value::Logic(now.to_logic())),
+                            Function::Fell => return Value::binary(Operation::And, Value::Logic(before.to_logic()), now.not()),
+                            _other => return Value::binary(Operation::NotEqual, before, now),
+                        }
+                    },
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ Expression, Value };
+    use crate::device::core::{ LogicState, ValueState };
+
+    const NAMES: [&str; 6] = ["a", "b", "c", "x", "m", "bus"];
+
+    // a is high, b and c are low, x is floating, m is metastable and bus is five
+    fn current(index: usize) -> Value {
+        match index {
+            0 => return Value::Logic(LogicState::High),
+            1 | 2 => return Value::Logic(LogicState::Low),
+            3 => return Value::Logic(LogicState::Floating),
+            4 => return Value::Logic(LogicState::Metastable),
+            _other => return Value::Number(ValueState::Stable(5)),
+        }
+    }
+
+    // a was low, b was high and everything else was the same as now
+    fn previous(index: usize) -> Value {
+        match index {
+            0 => return Value::Logic(LogicState::Low),
+            1 => return Value::Logic(LogicState::High),
+            other => return current(other),
+        }
+    }
+
+    fn parse(source: &str) -> Result<Expression, String> {
+        let mut resolve = |name: &str| NAMES.iter().position(|other| *other == name).ok_or_else(|| format!("no signal named {}", name));
+        return Expression::parse(source, &mut resolve);
+    }
+
+    fn evaluate(source: &str) -> Value {
+        return parse(source).unwrap().evaluate(&current, &previous);
+    }
+
+    fn logic(source: &str) -> LogicState {
+        return evaluate(source).to_logic();
+    }
+
+    fn error(source: &str) -> String {
+        return parse(source).unwrap_err();
+    }
+
+    #[test]
+    fn precedence() {
+        // and binds tighter than xor, which binds tighter than or
+        assert_eq!(logic("a | b & c"), LogicState::High);
+        assert_eq!(logic("(a | b) & c"), LogicState::Low);
+        assert_eq!(logic("a ^ a & b"), LogicState::High);
+        assert_eq!(logic("b | a ^ a"), LogicState::Low);
+        assert_eq!(logic("!a & b"), LogicState::Low);
+        assert_eq!(logic("!(a & b)"), LogicState::High);
+        // arithmetic binds tighter than comparisons, which bind tighter than and
+        assert_eq!(logic("bus + 1 == 6"), LogicState::High);
+        assert_eq!(logic("bus - 2 > 3 & a"), LogicState::Low);
+        assert_eq!(logic("bus >= 5 && a || b"), LogicState::High);
+    }
+
+    #[test]
+    fn edges() {
+        assert_eq!(logic("rose(a)"), LogicState::High);
+        assert_eq!(logic("fell(a)"), LogicState::Low);
+        assert_eq!(logic("rose(b)"), LogicState::Low);
+        assert_eq!(logic("fell(b)"), LogicState::High);
+        assert_eq!(logic("changed(a | b)"), LogicState::Low);
+        assert_eq!(logic("changed(bus)"), LogicState::Low);
+        assert_eq!(logic("rose(c)"), LogicState::Low);
+        assert_eq!(logic("unknown(x) & unknown(m) & !unknown(a)"), LogicState::High);
+    }
+
+    #[test]
+    fn bus_literals() {
+        assert_eq!(evaluate("bus"), Value::Number(ValueState::Stable(5)));
+        assert_eq!(logic("bus == 0b101"), LogicState::High);
+        assert_eq!(logic("bus == 0x5"), LogicState::High);
+        assert_eq!(logic("bus != 0x1f"), LogicState::High);
+        assert_eq!(evaluate("0x1_f + bus"), Value::Number(ValueState::Stable(36)));
+        assert_eq!(evaluate("bus & 0b100"), Value::Number(ValueState::Stable(4)));
+        assert_eq!(evaluate("bus ^ 0xff"), Value::Number(ValueState::Stable(250)));
+    }
+
+    #[test]
+    fn unknown_values() {
+        // a known input decides and and or even if the other one is unknown
+        assert_eq!(logic("0 & x"), LogicState::Low);
+        assert_eq!(logic("c & m"), LogicState::Low);
+        assert_eq!(logic("1 | x"), LogicState::High);
+        assert_eq!(logic("m | a"), LogicState::High);
+        assert_eq!(logic("1 & x"), LogicState::Floating);
+        assert_eq!(logic("x ^ 1"), LogicState::Floating);
+        assert_eq!(logic("m ^ 1"), LogicState::Metastable);
+        // floating wins over metastable
+        assert_eq!(logic("x ^ m"), LogicState::Floating);
+        assert_eq!(logic("!x"), LogicState::Floating);
+        assert_eq!(logic("x + bus > 1"), LogicState::Floating);
+        assert!(!evaluate("x").is_true());
+        assert!(!evaluate("m").is_true());
+    }
+
+    #[test]
+    fn error_positions() {
+        assert_eq!(error(""), "empty expression");
+        assert_eq!(error("a &"), "column 4: unexpected end of expression");
+        assert_eq!(error("a & (b"), "column 7: expected ')', found end of expression");
+        assert_eq!(error("a $ b"), "column 3: unexpected character '$'");
+        assert_eq!(error("a b"), "column 3: expected an operator, found signal b");
+        assert_eq!(error("a == b == c"), "column 8: expected an operator, found '=='");
+        assert_eq!(error("a & q"), "column 5: no signal named q");
+        assert_eq!(error("peak(a)"), "column 1: unknown function peak");
+        assert_eq!(error("bus == 0x1g"), "column 8: invalid number 0x1g");
+        assert_eq!(error("a & )"), "column 5: expected a signal, number or '(', found ')'");
+    }
+}
@@ -0,0 +1,145 @@
+use super::token::{ Token, tokenize };
+use super::{ Expression, Operation, Function };
+
+pub struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    end_column: usize,
+    resolve: &'a mut dyn FnMut(&str) -> Result<usize, String>,
+}
+
+impl<'a> Parser<'a> {
+
+    pub fn parse(source: &str, resolve: &'a mut dyn FnMut(&str) -> Result<usize, String>) -> Result<Expression, String> {
+
+        let mut parser = Self {
+            tokens: tokenize(source)?,
+            position: 0,
+            end_column: source.chars().count() + 1,
+            resolve: resolve,
+        };
+
+        if parser.tokens.is_empty() {
+            return Err(String::from("empty expression"));
+        }
+
+        let expression = parser.parse_or()?;
+
+        if let Some((column, token)) = parser.tokens.get(parser.position) {
+            return Err(format!("column {}: expected an operator, found {}", column, token.describe()));
+        }
+
+        return Ok(expression);
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        return self.tokens.get(self.position).map(|(_column, token)| token);
+    }
+
+    fn next(&mut self) -> Option<(usize, Token)> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        return token;
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some((_column, token)) if token == expected => return Ok(()),
+            Some((column, token)) => return Err(format!("column {}: expected {}, found {}", column, expected.describe(), token.describe())),
+            None => return Err(format!("column {}: expected {}, found end of expression", self.end_column, expected.describe())),
+        }
+    }
+
+    fn parse_binary(&mut self, operators: &[(Token, Operation)], operand: fn(&mut Self) -> Result<Expression, String>) -> Result<Expression, String> {
+        let mut left = operand(self)?;
+
+        while let Some(operation) = self.peek().and_then(|token| operators.iter().find(|(operator, _operation)| operator == token)).map(|(_operator, operation)| *operation) {
+            self.position += 1;
+            let right = operand(self)?;
+            left = Expression::Binary(operation, Box::new(left), Box::new(right));
+        }
+
+        return Ok(left);
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, String> {
+        return self.parse_binary(&[(Token::Or, Operation::Or)], Self::parse_xor);
+    }
+
+    fn parse_xor(&mut self) -> Result<Expression, String> {
+        return self.parse_binary(&[(Token::Xor, Operation::Xor)], Self::parse_and);
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, String> {
+        return self.parse_binary(&[(Token::And, Operation::And)], Self::parse_comparison);
+    }
+
+    // comparisons do not chain, so a == b == c is rejected
+    fn parse_comparison(&mut self) -> Result<Expression, String> {
+
+        let operators = [
+            (Token::Equal, Operation::Equal),
+            (Token::NotEqual, Operation::NotEqual),
+            (Token::Less, Operation::Less),
+            (Token::LessEqual, Operation::LessEqual),
+            (Token::Greater, Operation::Greater),
+            (Token::GreaterEqual, Operation::GreaterEqual),
+        ];
+
+        let left = self.parse_additive()?;
+
+        if let Some(operation) = self.peek().and_then(|token| operators.iter().find(|(operator, _operation)| operator == token)).map(|(_operator, operation)| *operation) {
+            self.position += 1;
+            let right = self.parse_additive()?;
+            return Ok(Expression::Binary(operation, Box::new(left), Box::new(right)));
+        }
+
+        return Ok(left);
+    }
+
+    fn parse_additive(&mut self) -> Result<Expression, String> {
+        return self.parse_binary(&[(Token::Plus, Operation::Add), (Token::Minus, Operation::Subtract)], Self::parse_unary);
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, String> {
+
+        if self.peek() == Some(&Token::Not) {
+            self.position += 1;
+            let operand = self.parse_unary()?;
+            return Ok(Expression::Not(Box::new(operand)));
+        }
+
+        return self.parse_primary();
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, String> {
+        match self.next() {
+
+            Some((_column, Token::Number(value))) => return Ok(Expression::Number(value)),
+
+            Some((_column, Token::OpenParenthesis)) => {
+                let expression = self.parse_or()?;
+                self.expect(Token::CloseParenthesis)?;
+                return Ok(expression);
+            },
+
+            Some((column, Token::Identifier(name))) => {
+
+                if self.peek() == Some(&Token::OpenParenthesis) {
+                    let function = Function::from_name(&name).ok_or_else(|| format!("column {}: unknown function {}", column, name))?;
+                    self.position += 1;
+                    let argument = self.parse_or()?;
+                    self.expect(Token::CloseParenthesis)?;
+                    return Ok(Expression::Function(function, Box::new(argument)));
+                }
+
+                let index = (self.resolve)(&name).map_err(|message| format!("column {}: {}", column, message))?;
+                return Ok(Expression::Signal(index));
+            },
+
+            Some((column, token)) => return Err(format!("column {}: expected a signal, number or '(', found {}", column, token.describe())),
+
+            None => return Err(format!("column {}: unexpected end of expression", self.end_column)),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Identifier(String),
+    Number(u64),
+    And,
+    Or,
+    Xor,
+    Not,
+    Plus,
+    Minus,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    OpenParenthesis,
+    CloseParenthesis,
+}
+
+impl Token {
+
+    pub fn describe(&self) -> String {
+        match self {
+            Token::Identifier(name) => return format!("signal {}", name),
+            Token::Number(value) => return format!("number {}", value),
+            Token::And => return String::from("'&'"),
+            Token::Or => return String::from("'|'"),
+            Token::Xor => return String::from("'^'"),
+            Token::Not => return String::from("'!'"),
+            Token::Plus => return String::from("'+'"),
+            Token::Minus => return String::from("'-'"),
+            Token::Equal => return String::from("'=='"),
+            Token::NotEqual => return String::from("'!='"),
+            Token::Less => return String::from("'<'"),
+            Token::LessEqual => return String::from("'<='"),
+            Token::Greater => return String::from("'>'"),
+            Token::GreaterEqual => return String::from("'>='"),
+            Token::OpenParenthesis => return String::from("'('"),
+            Token::CloseParenthesis => return String::from("')'"),
+        }
+    }
+}
+
+// signal paths may contain slashes, dots, brackets and a core prefix separated by a colon
+fn is_identifier_character(character: char) -> bool {
+    return character.is_alphanumeric() || "_/.:[]".contains(character);
+}
+
+// returns every token together with the column it starts at
+pub fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, String> {
+
+    let characters: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < characters.len() {
+
+        let character = characters[index];
+        let column = index + 1;
+        let next = characters.get(index + 1).cloned();
+
+        if character.is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        if character.is_ascii_digit() {
+            let start = index;
+            while index < characters.len() && (characters[index].is_alphanumeric() || characters[index] == '_') {
+                index += 1;
+            }
+
+            let text: String = characters[start..index].iter().collect();
+            let value = parse_number(&text).map_err(|_| format!("column {}: invalid number {}", column, text))?;
+            tokens.push((column, Token::Number(value)));
+            continue;
+        }
+
+        if is_identifier_character(character) {
+            let start = index;
+            while index < characters.len() && is_identifier_character(characters[index]) {
+                index += 1;
+            }
+
+            tokens.push((column, Token::Identifier(characters[start..index].iter().collect())));
+            continue;
+        }
+
+        let (token, length) = match (character, next) {
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Equal, 2),
+            ('!', Some('=')) => (Token::NotEqual, 2),
+            ('<', Some('=')) => (Token::LessEqual, 2),
+            ('>', Some('=')) => (Token::GreaterEqual, 2),
+            ('&', _) => (Token::And, 1),
+            ('|', _) => (Token::Or, 1),
+            ('^', _) => (Token::Xor, 1),
+            ('!', _) | ('~', _) => (Token::Not, 1),
+            ('+', _) => (Token::Plus, 1),
+            ('-', _) => (Token::Minus, 1),
+            ('<', _) => (Token::Less, 1),
+            ('>', _) => (Token::Greater, 1),
+            ('(', _) => (Token::OpenParenthesis, 1),
+            (')', _) => (Token::CloseParenthesis, 1),
+            (other, _) => return Err(format!("column {}: unexpected character '{}'", column, other)),
+        };
+
+        tokens.push((column, token));
+        index += length;
+    }
+
+    return Ok(tokens);
+}
+
+pub fn parse_number(text: &str) -> Result<u64, ()> {
+
+    let text = text.replace('_', "");
+    let result = if let Some(digits) = text.strip_prefix("0x") {
+        u64::from_str_radix(digits, 16)
+    } else if let Some(digits) = text.strip_prefix("0b") {
+        u64::from_str_radix(digits, 2)
+    } else {
+        text.parse()
+    };
+
+    return result.map_err(|_| ());
+}
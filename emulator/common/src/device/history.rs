@@ -0,0 +1,68 @@
+use std::collections::VecDeque;
+use super::CoreSnapshot;
+
+// number of half cycles that can be stepped back
+const HISTORY_LENGTH: usize = 1024;
+
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub rising: bool,
+    pub cores: Vec<(CoreSnapshot, usize)>,
+}
+
+pub struct History {
+    entries: VecDeque<HistoryEntry>,
+    position: usize,
+}
+
+impl History {
+
+    pub fn new() -> Self {
+        return Self {
+            entries: VecDeque::new(),
+            position: 0,
+        };
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.position = 0;
+    }
+
+    pub fn is_latest(&self) -> bool {
+        return self.position + 1 >= self.entries.len();
+    }
+
+    // recording while stepped back discards every entry after the current one
+    pub fn record(&mut self, entry: HistoryEntry) {
+
+        self.entries.truncate(self.position + 1);
+        self.entries.push_back(entry);
+
+        if self.entries.len() > HISTORY_LENGTH {
+            self.entries.pop_front();
+        }
+
+        self.position = self.entries.len() - 1;
+    }
+
+    pub fn back(&mut self) -> Option<&HistoryEntry> {
+
+        if self.position == 0 || self.entries.is_empty() {
+            return None;
+        }
+
+        self.position -= 1;
+        return self.entries.get(self.position);
+    }
+
+    pub fn forward(&mut self) -> Option<&HistoryEntry> {
+
+        if self.is_latest() {
+            return None;
+        }
+
+        self.position += 1;
+        return self.entries.get(self.position);
+    }
+}
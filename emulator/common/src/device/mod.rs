@@ -1,62 +1,527 @@
 mod core;
+mod snapshot;
+mod history;
+mod breakpoint;
+mod expression;
+mod clock;
+mod description;
+mod overview;
+mod top_bar;
+mod colors;
 
-use self::core::*;
+use self::core::{ Inspector, VcdWriter, INTERFACE_BORDER };
+use self::history::{ History, HistoryEntry };
+use self::clock::Clock;
+use self::description::{ DeviceDescription, Wire };
+use self::top_bar::TOP_BAR_HEIGHT;
 use crate::types::*;
+use std::fs::{ read_to_string, write };
+use std::path::Path;
+
+pub use self::core::{ Core, Port, LogicState, Operator, Netlist, Net, PortDirection, NetlistGate, NetlistRegister, NetlistConstant, NetlistPort, NetlistSignal, Pass, PassReport, Toggles, ActivityReport, ActivitySnapshot, Technology, PowerReport, fuzz, FuzzReport, Divergence, CoreBuilder, GateHandle, RegisterHandle, ConstantHandle, PortHandle, GroupHandle, Source, Pin, Formatting, EquivalenceReport, Comparison, Counterexample, Method, Delays, TimingPath, TimingReport, Stimulus, Fault, FaultSite, FaultReport, TruthTable, TableFormat };
+pub use self::snapshot::{ Snapshot, CoreSnapshot };
+pub use self::breakpoint::{ Breakpoint, BreakpointHit, Condition };
+pub use self::expression::{ Expression, Value, Operation, Function };
+pub use self::clock::Speed;
 
 pub struct Device {
+    name: String,
     cores: Vec<Core>,
+    wires: Vec<Wire>,
+    recording: Option<Inspector>,
+    history: History,
+    breakpoints: Vec<Breakpoint>,
+    hits: Vec<BreakpointHit>,
+    clock: Clock,
     focused_core: Option<usize>,
+    interface_size: FloatVector,
     rising: bool,
     show_top_bar: bool,
+    status: Option<String>,
 }
 
 impl Device {
 
-    pub fn new(interface_size: FloatVector) -> Self {
+    // the built in sample circuit, for when no circuit is given
+    pub fn new(interface_size: FloatVector) -> Result<Self, String> {
+        let core = Core::sample(String::from("sample"), interface_size, true)?;
+        return Ok(Self::from_cores(String::from("sample"), vec![core], Vec::new(), interface_size));
+    }
+
+    pub fn load(filename: String, interface_size: FloatVector, debugging: bool) -> Result<Self, String> {
+
+        let core = Core::load(filename, interface_size, debugging)?;
+        let name = String::from(core.name());
+        return Ok(Self::from_cores(name, vec![core], Vec::new(), interface_size));
+    }
+
+    // loads all cores of a device description, core files are relative to the description
+    pub fn from_description(filename: String, interface_size: FloatVector, debugging: bool) -> Result<Self, String> {
+
+        let source = read_to_string(&filename).map_err(|error| format!("failed to read {}: {}", filename, error))?;
+        let description = DeviceDescription::parse(&source).map_err(|error| format!("{}: {}", filename, error))?;
+        let directory = Path::new(&filename).parent().unwrap_or(Path::new(""));
 
-        // TEMP
         let mut cores = Vec::new();
-        cores.push(Core::load(String::from("test.logic"), interface_size, true));
+        for core_description in &description.cores {
+            let core_filename = directory.join(&core_description.filename).to_string_lossy().into_owned();
+            let mut core = Core::load(core_filename, interface_size, debugging)?;
+            core.set_name(core_description.name.clone());
+            cores.push(core);
+        }
+
+        let wires = description.resolve_wires(&cores).map_err(|error| format!("{}: {}", filename, error))?;
+        let name = file_name(&filename);
+        return Ok(Self::from_cores(name, cores, wires, interface_size));
+    }
+
+    fn from_cores(name: String, cores: Vec<Core>, wires: Vec<Wire>, interface_size: FloatVector) -> Self {
 
-        return Self {
+        // devices with multiple cores start out in the overview
+        let focused_core = (cores.len() == 1).then_some(0);
+
+        let mut device = Self {
+            name: name,
             cores: cores,
-            focused_core: Some(0), // TODO: None
+            wires: wires,
+            recording: None,
+            history: History::new(),
+            breakpoints: Vec::new(),
+            hits: Vec::new(),
+            clock: Clock::new(),
+            focused_core: focused_core,
+            interface_size: interface_size,
+            rising: true,
+            show_top_bar: true,
+            status: None,
+        };
+
+        device.resize(interface_size);
+        device.record_history();
+        return device;
+    }
+
+    // replays a recorded vcd file without simulating anything
+    pub fn replay(filename: String, interface_size: FloatVector) -> Result<Self, String> {
+
+        let source = read_to_string(&filename).map_err(|error| format!("failed to read {}: {}", filename, error))?;
+        let name = file_name(&filename);
+        let recording = Inspector::from_vcd(&name, interface_size - FloatVector::with(INTERFACE_BORDER * 2.0), &source)?;
+
+        let mut device = Self {
+            name: name,
+            cores: Vec::new(),
+            wires: Vec::new(),
+            recording: Some(recording),
+            history: History::new(),
+            breakpoints: Vec::new(),
+            hits: Vec::new(),
+            clock: Clock::new(),
+            focused_core: None,
+            interface_size: interface_size,
             rising: true,
             show_top_bar: true,
+            status: None,
         };
+
+        device.resize(interface_size);
+        return Ok(device);
     }
 
     pub fn handle_key_input(&mut self, key: Key) {
+
+        if key == Key::B {
+            self.show_top_bar = !self.show_top_bar;
+            self.resize(self.interface_size);
+            return;
+        }
+
+        if let Some(recording) = &mut self.recording {
+            recording.handle_key_input(key);
+            return;
+        }
+
         match key {
 
-            Key::Space => {
-                let rising = self.rising;
-                self.cores.iter_mut().for_each(|core| core.tick(rising));
-                self.rising = !self.rising;
+            Key::Space => self.step(),
+
+            Key::R => self.set_running(!self.is_running()),
+
+            Key::Up => self.clock.faster(),
+
+            Key::Down => self.clock.slower(),
+
+            Key::Left => {
+                self.set_running(false);
+                self.step_back();
+            },
+
+            Key::Right => {
+                self.set_running(false);
+                self.step_forward();
             },
 
+            Key::S => {
+                let filename = self.snapshot_filename();
+                match self.save_snapshot(&filename) {
+                    Ok(()) => self.status = Some(format!("saved snapshot {}", filename)),
+                    Err(message) => self.status = Some(message),
+                }
+            },
+
+            Key::L => {
+                let filename = self.snapshot_filename();
+                match self.load_snapshot(&filename) {
+                    Ok(()) => self.status = Some(format!("loaded snapshot {}", filename)),
+                    Err(message) => self.status = Some(message),
+                }
+            },
+
+            Key::Tab => self.focus_next(),
+
+            Key::Escape => self.focus(None),
+
             other => {
-                self.cores[0].handle_key_input(other);
+                if let Some(index) = self.focused_core {
+                    self.cores[index].handle_key_input(other);
+                }
+            },
+        }
+    }
+
+    pub fn step(&mut self) {
+
+        // simulating from a past state discards everything that happened after it
+        if !self.history.is_latest() {
+            self.cores.iter_mut().for_each(|core| core.discard_future());
+        }
+
+        self.propagate_wires();
+
+        let rising = self.rising;
+        self.cores.iter_mut().for_each(|core| core.tick(rising));
+        self.rising = !self.rising;
+        self.record_history();
+        self.check_breakpoints();
+    }
+
+    // wires carry the state of the previous half cycle, so the order in which cores are ticked does not matter
+    fn propagate_wires(&mut self) {
+
+        let states: Vec<LogicState> = self.wires.iter().map(|wire| self.cores[wire.source.0].port_state(wire.source.1)).collect();
+
+        for (wire, state) in self.wires.iter().zip(states) {
+            self.cores[wire.target.0].set_port_state(wire.target.1, state);
+        }
+    }
+
+    // runs until the given number of cycles passed or a breakpoint was hit
+    pub fn run(&mut self, cycles: usize) -> bool {
+
+        for _cycle in 0..cycles {
+            for _edge in 0..2 {
+                self.step();
+
+                if !self.hits.is_empty() {
+                    return true;
+                }
+            }
+        }
+
+        return false;
+    }
+
+    // advances a running clock by the time that passed since the last update, returns true if anything was simulated
+    pub fn update(&mut self, elapsed: f32) -> bool {
+
+        if self.recording.is_some() {
+            return false;
+        }
+
+        let steps = self.clock.steps(elapsed);
+
+        for _step in 0..steps {
+            self.step();
+
+            if !self.hits.is_empty() {
+                self.set_running(false);
+                break;
+            }
+        }
+
+        return steps > 0;
+    }
+
+    pub fn is_running(&self) -> bool {
+        return self.clock.is_running();
+    }
+
+    pub fn set_running(&mut self, running: bool) {
+        self.clock.set_running(running);
+    }
+
+    pub fn speed(&self) -> Speed {
+        return self.clock.speed();
+    }
+
+    pub fn set_speed(&mut self, speed: Speed) {
+        self.clock.set_speed(speed);
+    }
+
+    // the signal is shown in the inspector of the core whose signals the expression refers to
+    pub fn add_derived_signal(&mut self, name: &str, source: &str) -> Result<(), String> {
+        let (core, expression) = breakpoint::compile_expression(source, &self.cores)?;
+        return self.cores[core].add_derived_signal(name, expression);
+    }
+
+    pub fn add_breakpoint(&mut self, condition: &str) -> Result<usize, String> {
+        let condition = Condition::parse(condition)?;
+        self.breakpoints.push(Breakpoint::new(condition, &self.cores)?);
+        return Ok(self.breakpoints.len() - 1);
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+        self.hits.clear();
+    }
+
+    pub fn breakpoints(&self) -> &Vec<Breakpoint> {
+        return &self.breakpoints;
+    }
+
+    pub fn hits(&self) -> &Vec<BreakpointHit> {
+        return &self.hits;
+    }
+
+    fn check_breakpoints(&mut self) {
+        self.hits.clear();
+
+        for (index, breakpoint) in self.breakpoints.iter_mut().enumerate() {
+            if let Some((core, description)) = breakpoint.check(&self.cores) {
+                self.hits.push(BreakpointHit {
+                    breakpoint: index,
+                    core: String::from(self.cores[core].name()),
+                    cycle: self.cores[core].cycle_count(),
+                    description: description,
+                });
+            }
+        }
+    }
+
+    fn synchronize_breakpoints(&mut self) {
+        self.hits.clear();
+        let cores = &self.cores;
+        self.breakpoints.iter_mut().for_each(|breakpoint| breakpoint.synchronize(cores));
+    }
+
+    pub fn step_back(&mut self) {
+        if let Some(entry) = self.history.back().cloned() {
+            self.apply_history(&entry);
+        }
+    }
+
+    pub fn step_forward(&mut self) {
+        match self.history.forward().cloned() {
+            Some(entry) => self.apply_history(&entry),
+            None => self.step(),
+        }
+    }
+
+    fn record_history(&mut self) {
+        let entry = HistoryEntry {
+            rising: self.rising,
+            cores: self.cores.iter().map(|core| (core.state(), core.samples())).collect(),
+        };
+        self.history.record(entry);
+    }
+
+    fn apply_history(&mut self, entry: &HistoryEntry) {
+        self.rising = entry.rising;
+        self.cores.iter_mut().zip(entry.cores.iter()).for_each(|(core, (state, samples))| core.restore_state(state, *samples));
+        self.synchronize_breakpoints();
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        return Snapshot {
+            rising: self.rising,
+            cores: self.cores.iter().map(|core| core.snapshot()).collect(),
+        };
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), String> {
+
+        if snapshot.cores.len() != self.cores.len() {
+            return Err(format!("snapshot contains {} cores but {} are loaded", snapshot.cores.len(), self.cores.len()));
+        }
+
+        // validate everything first so a bad snapshot never leaves the device half restored
+        for (core, core_snapshot) in self.cores.iter().zip(snapshot.cores.iter()) {
+            if core.name() != core_snapshot.name {
+                return Err(format!("snapshot contains core {} but {} is loaded", core_snapshot.name, core.name()));
             }
+            core.validate_snapshot(core_snapshot)?;
+        }
+
+        for (core, core_snapshot) in self.cores.iter_mut().zip(snapshot.cores.iter()) {
+            core.restore(core_snapshot)?;
+        }
+
+        self.rising = snapshot.rising;
+        self.history.clear();
+        self.record_history();
+        self.synchronize_breakpoints();
+        return Ok(());
+    }
+
+    pub fn save_snapshot(&self, filename: &str) -> Result<(), String> {
+        return write(filename, self.snapshot().serialize()).map_err(|error| format!("failed to write snapshot {}: {}", filename, error));
+    }
+
+    pub fn load_snapshot(&mut self, filename: &str) -> Result<(), String> {
+        let source = read_to_string(filename).map_err(|error| format!("failed to read snapshot {}: {}", filename, error))?;
+        let snapshot = Snapshot::parse(&source).map_err(|error| format!("{}: {}", filename, error))?;
+        return self.restore(&snapshot);
+    }
+
+    fn snapshot_filename(&self) -> String {
+        match self.cores.first() {
+            Some(core) => return format!("{}.snapshot", core.name()),
+            None => return String::from("device.snapshot"),
+        }
+    }
+
+    // the state of an input port is pushed into the core on the next rising edge
+    pub fn set_port_state(&mut self, core: usize, port: usize, state: LogicState) {
+        self.cores[core].set_port_state(port, state);
+    }
+
+    pub fn cores(&self) -> &Vec<Core> {
+        return &self.cores;
+    }
+
+    pub fn track_all(&mut self) {
+        self.cores.iter_mut().for_each(|core| core.track_all());
+    }
+
+    pub fn track_activity(&mut self) {
+        self.cores.iter_mut().for_each(|core| core.track_activity());
+    }
+
+    pub fn estimate_power(&mut self, technology: &Technology) {
+        self.cores.iter_mut().for_each(|core| core.estimate_power(technology.clone()));
+    }
+
+    pub fn vcd(&self) -> String {
+        let mut writer = VcdWriter::new();
+        self.cores.iter().for_each(|core| core.write_vcd(&mut writer));
+        return writer.finish();
+    }
+
+    // every core becomes its own module
+    pub fn verilog(&self) -> Result<String, String> {
+        let modules: Result<Vec<String>, String> = self.cores.iter().map(|core| core.netlist().to_verilog().map_err(|error| format!("{}: {}", core.name(), error))).collect();
+        return Ok(modules?.join("\n"));
+    }
+
+    // optimizing changes the gates of a core, so snapshots taken before can not be restored anymore
+    pub fn optimize(&mut self, passes: &[Pass]) -> Vec<(String, PassReport)> {
+        let mut reports = Vec::new();
+
+        for core in &mut self.cores {
+            let name = String::from(core.name());
+            reports.extend(core.optimize(passes).into_iter().map(|report| (name.clone(), report)));
+        }
+
+        self.history.clear();
+        self.record_history();
+        return reports;
+    }
+
+    // every core is drawn as a cluster and wires connect the ports of different cores
+    pub fn dot(&self, colored: bool) -> String {
+        let mut dot = format!("digraph \"{}\" {{\n    rankdir=LR;\n", Core::dot_escape(&self.name));
+
+        for (index, core) in self.cores.iter().enumerate() {
+            dot.push_str(&format!("    subgraph cluster_{} {{\n        label=\"{} cycle {}\";\n", index, Core::dot_escape(core.name()), core.cycle_count()));
+            core.write_dot(&format!("core_{}_", index), colored).iter().for_each(|line| dot.push_str(&format!("        {}\n", line)));
+            dot.push_str("    }\n");
+        }
+
+        for wire in &self.wires {
+            let source = Core::dot_port_node(&format!("core_{}_", wire.source.0), wire.source.1);
+            let target = Core::dot_port_node(&format!("core_{}_", wire.target.0), wire.target.1);
+            dot.push_str(&format!("    {} -> {} [style=dashed];\n", source, target));
+        }
+
+        dot.push_str("}\n");
+        return dot;
+    }
+
+    pub fn focused_core(&self) -> Option<usize> {
+        return self.focused_core;
+    }
+
+    pub fn focus(&mut self, core: Option<usize>) {
+        self.focused_core = core.filter(|index| *index < self.cores.len());
+    }
+
+    // cycles through all cores and the overview
+    pub fn focus_next(&mut self) {
+        match self.focused_core {
+            Some(index) if index + 1 < self.cores.len() => self.focused_core = Some(index + 1),
+            Some(_index) => self.focused_core = None,
+            None => self.focus(Some(0)),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        return self.status.as_deref();
+    }
+
+    // the area below the top bar
+    fn content_offset(&self) -> FloatVector {
+        match self.show_top_bar {
+            true => return FloatVector::with_y(TOP_BAR_HEIGHT),
+            false => return FloatVector::new(),
         }
     }
 
     pub fn resize(&mut self, size: FloatVector) {
-        self.cores.iter_mut().for_each(|core| core.resize(size));
+        self.interface_size = size;
+        let content_size = size - self.content_offset();
+        self.cores.iter_mut().for_each(|core| core.resize(content_size));
+
+        if let Some(recording) = &mut self.recording {
+            recording.resize(content_size - FloatVector::with(INTERFACE_BORDER * 2.0));
+        }
     }
 
     pub fn render<T: Renderer>(&self, renderer: &mut T) {
         renderer.clear(Color::monochrome(25));
-        let position = FloatVector::new();
+        let position = self.content_offset();
 
         if self.show_top_bar {
-            // draw top bar
+            self.draw_top_bar(renderer);
+        }
+
+        match self.focused_core {
+            Some(index) => self.cores[index].draw(renderer, position),
+            None if self.recording.is_none() => self.draw_overview(renderer, position),
+            None => { },
         }
 
-        if let Some(index) = self.focused_core {
-            self.cores[index].draw(renderer, position);
+        if let Some(recording) = &self.recording {
+            recording.draw(renderer, position + FloatVector::with(INTERFACE_BORDER));
         }
 
         renderer.display();
     }
 }
+
+fn file_name(filename: &str) -> String {
+    return Path::new(filename).file_name().and_then(|name| name.to_str()).map(String::from).unwrap_or_else(|| String::from(filename));
+}
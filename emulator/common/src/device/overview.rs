@@ -0,0 +1,76 @@
+use super::core::INTERFACE_BORDER;
+use super::Device;
+use super::colors::*;
+use crate::types::*;
+
+const ROW_HEIGHT: f32 = 20.0;
+const ROW_GAP: f32 = 2.0;
+const SECTION_GAP: f32 = 12.0;
+const TEXT_OFFSET: f32 = 10.0;
+const TEXT_PADDING: f32 = 1.0;
+const TEXT_SIZE: u32 = 12;
+
+const HEADER_COLOR: Color = Color::from(40, 40, 40);
+const ROW_COLOR: Color = Color::from(55, 55, 55);
+const METASTABLE_COLOR: Color = Color::from(150, 100, 100);
+
+impl Device {
+
+    fn draw_row<T: Renderer>(&self, renderer: &mut T, position: &mut FloatVector, color: Color, left: &str, right: Option<(&str, Color)>) {
+
+        let width = self.interface_size.x - INTERFACE_BORDER * 2.0;
+        renderer.draw_rectangle(*position, FloatVector::from(width, ROW_HEIGHT), color);
+        renderer.draw_text(left, *position + FloatVector::from(TEXT_OFFSET, TEXT_PADDING), TEXT_COLOR, TEXT_SIZE);
+
+        if let Some((text, text_color)) = right {
+            renderer.draw_text_right(text, *position + FloatVector::from(width - TEXT_OFFSET, TEXT_PADDING), text_color, TEXT_SIZE);
+        }
+
+        position.y += ROW_HEIGHT + ROW_GAP;
+    }
+
+    fn core_status(&self, index: usize) -> (&'static str, Color) {
+
+        if self.hits.iter().any(|hit| hit.core == self.cores[index].name()) {
+            return ("stopped at breakpoint", BREAKPOINT_COLOR);
+        }
+
+        if !self.cores[index].metastable_sources().is_empty() {
+            return ("metastable", METASTABLE_COLOR);
+        }
+
+        match self.clock.is_running() {
+            true => return ("running", RUNNING_COLOR),
+            false => return ("paused", PAUSED_COLOR),
+        }
+    }
+
+    // lists every core and wire of the device, shown when no core is focused
+    pub(super) fn draw_overview<T: Renderer>(&self, renderer: &mut T, position: FloatVector) {
+
+        let mut position = position + FloatVector::with(INTERFACE_BORDER);
+        self.draw_row(renderer, &mut position, HEADER_COLOR, "cores (tab to focus)", None);
+
+        for (index, core) in self.cores.iter().enumerate() {
+            let (status, color) = self.core_status(index);
+            let text = format!("{}  {}", index, core.name());
+            let right = format!("{} cycles  {}", core.cycle_count(), status);
+            self.draw_row(renderer, &mut position, ROW_COLOR, &text, Some((&right, color)));
+        }
+
+        if self.wires.is_empty() {
+            return;
+        }
+
+        position.y += SECTION_GAP;
+        self.draw_row(renderer, &mut position, HEADER_COLOR, "wires", None);
+
+        for wire in &self.wires {
+            let (source_core, source_port) = (&self.cores[wire.source.0], wire.source.1);
+            let (target_core, target_port) = (&self.cores[wire.target.0], wire.target.1);
+            let text = format!("{}.{} -> {}.{}", source_core.name(), source_core.ports()[source_port].name, target_core.name(), target_core.ports()[target_port].name);
+            let state = format!("{:?}", source_core.port_state(source_port)).to_lowercase();
+            self.draw_row(renderer, &mut position, ROW_COLOR, &text, Some((&state, TEXT_COLOR)));
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use super::core::{ LogicState, ValueState, ActivitySnapshot };
+
+const SNAPSHOT_HEADER: &str = "mimicry snapshot";
+const SNAPSHOT_VERSION: usize = 1;
+
+#[derive(Clone, Debug)]
+pub struct CoreSnapshot {
+    pub name: String,
+    pub cycle_count: usize,
+    pub registers: Vec<(LogicState, LogicState)>,
+    pub gates: Vec<(LogicState, LogicState, LogicState)>,
+    pub ports: Vec<LogicState>,
+    pub logic_trackers: Vec<Vec<LogicState>>,
+    pub value_trackers: Vec<Vec<ValueState>>,
+    // only kept in the history, it is not written to snapshot files
+    pub activity: Option<ActivitySnapshot>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub rising: bool,
+    pub cores: Vec<CoreSnapshot>,
+}
+
+impl Snapshot {
+
+    pub fn serialize(&self) -> String {
+
+        let mut output = format!("{} {}\n", SNAPSHOT_HEADER, SNAPSHOT_VERSION);
+        output.push_str(&format!("rising {}\n", self.rising as usize));
+
+        for core in &self.cores {
+            output.push_str(&format!("core {}\n", core.name));
+            output.push_str(&format!("cycles {}\n", core.cycle_count));
+
+            output.push_str(&format!("registers {}\n", core.registers.len()));
+            for (input, state) in &core.registers {
+                output.push_str(&format!("{} {}\n", logic_character(input), logic_character(state)));
+            }
+
+            output.push_str(&format!("gates {}\n", core.gates.len()));
+            for (left_input, right_input, state) in &core.gates {
+                output.push_str(&format!("{} {} {}\n", logic_character(left_input), logic_character(right_input), logic_character(state)));
+            }
+
+            output.push_str(&format!("ports {}\n", core.ports.len()));
+            for state in &core.ports {
+                output.push_str(&format!("{}\n", logic_character(state)));
+            }
+
+            output.push_str(&format!("logic trackers {}\n", core.logic_trackers.len()));
+            for states in &core.logic_trackers {
+                output.push_str(&format!("{}\n", states.iter().map(logic_character).collect::<String>()));
+            }
+
+            output.push_str(&format!("value trackers {}\n", core.value_trackers.len()));
+            for states in &core.value_trackers {
+                output.push_str(&format!("{}\n", states.iter().map(value_text).collect::<Vec<String>>().join(" ")));
+            }
+        }
+
+        return output;
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+
+        let mut lines = SnapshotLines::new(source);
+        let header = lines.expect("header")?;
+
+        let version = match header.strip_prefix(SNAPSHOT_HEADER) {
+            Some(version) => lines.parse_number(version.trim())?,
+            None => return Err(String::from("file is not a snapshot")),
+        };
+
+        if version != SNAPSHOT_VERSION {
+            return Err(format!("snapshot version {} is not supported", version));
+        }
+
+        let rising = lines.expect_field("rising")? != "0";
+        let mut cores = Vec::new();
+
+        while lines.peek().is_some() {
+
+            let name = String::from(lines.expect_field("core")?);
+            let cycle_count = lines.expect_count("cycles")?;
+
+            let mut registers = Vec::new();
+            for _index in 0..lines.expect_count("registers")? {
+                let states = lines.expect_states(2)?;
+                registers.push((states[0], states[1]));
+            }
+
+            let mut gates = Vec::new();
+            for _index in 0..lines.expect_count("gates")? {
+                let states = lines.expect_states(3)?;
+                gates.push((states[0], states[1], states[2]));
+            }
+
+            let mut ports = Vec::new();
+            for _index in 0..lines.expect_count("ports")? {
+                ports.push(lines.expect_states(1)?[0]);
+            }
+
+            let mut logic_trackers = Vec::new();
+            for _index in 0..lines.expect_count("logic trackers")? {
+                let line = lines.expect("logic tracker")?;
+                let states = line.chars().map(|character| lines.parse_logic(character)).collect::<Result<Vec<LogicState>, String>>()?;
+                logic_trackers.push(states);
+            }
+
+            let mut value_trackers = Vec::new();
+            for _index in 0..lines.expect_count("value trackers")? {
+                let line = lines.expect("value tracker")?;
+                let states = line.split_whitespace().map(|value| lines.parse_value(value)).collect::<Result<Vec<ValueState>, String>>()?;
+                value_trackers.push(states);
+            }
+
+            cores.push(CoreSnapshot {
+                name: name,
+                cycle_count: cycle_count,
+                registers: registers,
+                gates: gates,
+                ports: ports,
+                logic_trackers: logic_trackers,
+                value_trackers: value_trackers,
+                activity: None,
+            });
+        }
+
+        return Ok(Self {
+            rising: rising,
+            cores: cores,
+        });
+    }
+}
+
+struct SnapshotLines<'a> {
+    lines: std::iter::Peekable<std::iter::Enumerate<std::str::Lines<'a>>>,
+    line: usize,
+}
+
+impl<'a> SnapshotLines<'a> {
+
+    fn new(source: &'a str) -> Self {
+        return Self {
+            lines: source.lines().enumerate().peekable(),
+            line: 0,
+        };
+    }
+
+    fn peek(&mut self) -> Option<&str> {
+        return self.lines.peek().map(|(_index, line)| *line);
+    }
+
+    fn expect(&mut self, context: &str) -> Result<&'a str, String> {
+        match self.lines.next() {
+
+            Some((index, line)) => {
+                self.line = index + 1;
+                return Ok(line);
+            },
+
+            None => return Err(format!("unexpected end of snapshot, expected {}", context)),
+        }
+    }
+
+    fn expect_field(&mut self, field: &str) -> Result<&'a str, String> {
+        let line = self.expect(field)?;
+
+        match line.strip_prefix(field).and_then(|value| value.strip_prefix(' ')) {
+            Some(value) => return Ok(value),
+            None => return Err(format!("line {}: expected {}", self.line, field)),
+        }
+    }
+
+    fn expect_count(&mut self, field: &str) -> Result<usize, String> {
+        let value = self.expect_field(field)?;
+        return self.parse_number(value);
+    }
+
+    fn expect_states(&mut self, count: usize) -> Result<Vec<LogicState>, String> {
+        let line = self.expect("states")?;
+        let states = line.split_whitespace().map(|state| {
+            let mut characters = state.chars();
+            match (characters.next(), characters.next()) {
+                (Some(character), None) => self.parse_logic(character),
+                _other => Err(format!("line {}: invalid state {}", self.line, state)),
+            }
+        }).collect::<Result<Vec<LogicState>, String>>()?;
+
+        if states.len() != count {
+            return Err(format!("line {}: expected {} states, found {}", self.line, count, states.len()));
+        }
+
+        return Ok(states);
+    }
+
+    fn parse_number(&self, value: &str) -> Result<usize, String> {
+        return value.parse().map_err(|_| format!("line {}: invalid number {}", self.line, value));
+    }
+
+    fn parse_logic(&self, character: char) -> Result<LogicState, String> {
+        match character {
+            '1' => return Ok(LogicState::High),
+            '0' => return Ok(LogicState::Low),
+            'z' => return Ok(LogicState::Floating),
+            'x' => return Ok(LogicState::Metastable),
+            other => return Err(format!("line {}: invalid logic state {}", self.line, other)),
+        }
+    }
+
+    fn parse_value(&self, value: &str) -> Result<ValueState, String> {
+        match value {
+            "z" => return Ok(ValueState::Floating),
+            "x" => return Ok(ValueState::Metastable),
+            number => return number.parse().map(ValueState::Stable).map_err(|_| format!("line {}: invalid value {}", self.line, number)),
+        }
+    }
+}
+
+fn logic_character(state: &LogicState) -> char {
+    match state {
+        LogicState::High => return '1',
+        LogicState::Low => return '0',
+        LogicState::Floating => return 'z',
+        LogicState::Metastable => return 'x',
+    }
+}
+
+fn value_text(state: &ValueState) -> String {
+    match state {
+        ValueState::Stable(value) => return value.to_string(),
+        ValueState::Floating => return String::from("z"),
+        ValueState::Metastable => return String::from("x"),
+    }
+}
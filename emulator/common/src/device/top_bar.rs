@@ -0,0 +1,139 @@
+use super::core::INTERFACE_BORDER;
+use super::{ Device, Speed };
+use super::colors::*;
+use crate::types::*;
+
+pub const TOP_BAR_HEIGHT: f32 = 24.0;
+
+const TEXT_SIZE: u32 = 12;
+const TEXT_PADDING: f32 = 3.0;
+const ITEM_GAP: f32 = 20.0;
+const BREAKPOINT_GAP: f32 = 10.0;
+
+// used to estimate the width of text, the renderer can not measure it
+const CHARACTER_WIDTH: f32 = 7.5;
+
+const BAR_COLOR: Color = Color::from(40, 40, 40);
+
+impl Device {
+
+    fn top_bar_items(&self) -> Vec<(String, Color)> {
+
+        let mut items = vec![(self.name.clone(), TEXT_COLOR)];
+
+        if self.recording.is_some() {
+            items.push((String::from("replay"), PAUSED_COLOR));
+            return items;
+        }
+
+        let cycle_count = self.cores.iter().map(|core| core.cycle_count()).max().unwrap_or(0);
+        items.push((format!("cycle {}", cycle_count), TEXT_COLOR));
+
+        // rising is the edge of the next tick, so the clock is currently low
+        match self.rising {
+            true => items.push((String::from("clock low"), TEXT_COLOR)),
+            false => items.push((String::from("clock high"), TEXT_COLOR)),
+        }
+
+        match self.clock.is_running() {
+            true => items.push((String::from("running"), RUNNING_COLOR)),
+            false => items.push((String::from("paused"), PAUSED_COLOR)),
+        }
+
+        match self.clock.speed() {
+            Speed::CyclesPerSecond(speed) => items.push((format!("{} cycles/s", speed), TEXT_COLOR)),
+            Speed::Unlimited => items.push((String::from("unlimited"), TEXT_COLOR)),
+        }
+
+        if let Some(index) = self.focused_core {
+            items.push((format!("core {}", self.cores[index].name()), TEXT_COLOR));
+        }
+
+        return items;
+    }
+
+    // the conditions of every breakpoint with the ones that were hit highlighted, cut off where the space runs out
+    fn breakpoint_items(&self, width: f32) -> Vec<(String, Color)> {
+
+        let mut items = Vec::new();
+        let mut remaining = (width / CHARACTER_WIDTH) as usize;
+        let gap = (BREAKPOINT_GAP / CHARACTER_WIDTH).ceil() as usize;
+
+        for (index, breakpoint) in self.breakpoints.iter().enumerate() {
+
+            let color = match self.hits.iter().any(|hit| hit.breakpoint == index) {
+                true => BREAKPOINT_COLOR,
+                false => TEXT_COLOR,
+            };
+
+            let text = format!("[{}]", breakpoint.condition);
+            let length = text.chars().count();
+
+            if length > remaining {
+                if remaining > 3 {
+                    let truncated: String = text.chars().take(remaining - 3).collect();
+                    items.push((format!("{}...", truncated), color));
+                }
+                break;
+            }
+
+            items.push((text, color));
+            remaining = remaining.saturating_sub(length + gap);
+        }
+
+        return items;
+    }
+
+    pub(super) fn draw_top_bar<T: Renderer>(&self, renderer: &mut T) {
+
+        renderer.draw_rectangle(FloatVector::new(), FloatVector::from(self.interface_size.x, TOP_BAR_HEIGHT), BAR_COLOR);
+
+        let mut position = FloatVector::from(INTERFACE_BORDER, TEXT_PADDING);
+        for (text, color) in self.top_bar_items() {
+            renderer.draw_text(&text, position, color, TEXT_SIZE);
+            position.x += text.len() as f32 * CHARACTER_WIDTH + ITEM_GAP;
+        }
+
+        // breakpoint hits are more important than any other message
+        let message = match (self.hits.first(), &self.status) {
+            (Some(hit), _status) => Some((hit.to_string(), BREAKPOINT_COLOR)),
+            (None, Some(status)) => Some((status.clone(), TEXT_COLOR)),
+            (None, None) => None,
+        };
+
+        let mut right_position = FloatVector::from(self.interface_size.x - INTERFACE_BORDER, TEXT_PADDING);
+        if let Some((text, color)) = message {
+            renderer.draw_text_right(&text, right_position, color, TEXT_SIZE);
+            right_position.x -= text.len() as f32 * CHARACTER_WIDTH + ITEM_GAP;
+        }
+
+        for (text, color) in self.breakpoint_items(right_position.x - position.x) {
+            renderer.draw_text(&text, position, color, TEXT_SIZE);
+            position.x += text.chars().count() as f32 * CHARACTER_WIDTH + BREAKPOINT_GAP;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ CHARACTER_WIDTH, TEXT_COLOR, BREAKPOINT_COLOR };
+    use super::super::Device;
+    use crate::types::FloatVector;
+
+    #[test]
+    fn breakpoints_are_cut_off() {
+
+        let mut device = Device::new(FloatVector::from(800.0, 600.0)).unwrap();
+        device.add_breakpoint("cycles 2").unwrap();
+        device.add_breakpoint("metastable").unwrap();
+        device.add_breakpoint("cycles 1000").unwrap();
+        assert!(device.run(10));
+
+        let texts = |characters: usize| device.breakpoint_items(characters as f32 * CHARACTER_WIDTH);
+        assert_eq!(texts(80), vec![(String::from("[cycles 2]"), BREAKPOINT_COLOR), (String::from("[metastable]"), TEXT_COLOR), (String::from("[cycles 1000]"), TEXT_COLOR)]);
+        assert_eq!(texts(32), vec![(String::from("[cycles 2]"), BREAKPOINT_COLOR), (String::from("[metastable]"), TEXT_COLOR), (String::from("[cy..."), TEXT_COLOR)]);
+        assert_eq!(texts(12), vec![(String::from("[cycles 2]"), BREAKPOINT_COLOR)]);
+        assert_eq!(texts(3), vec![]);
+    }
+}
@@ -1,15 +1,37 @@
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Key {
+    A,
+    B,
     T,
+    S,
+    L,
+    R,
     Space,
+    Left,
+    Right,
+    Up,
+    Down,
+    Tab,
+    Escape,
 }
 
 impl Key {
 
     pub fn from_code(code: usize) -> Option<Self> {
         match code {
+            9 => return Some(Key::Tab),
+            27 => return Some(Key::Escape),
             32 => return Some(Key::Space),
+            37 => return Some(Key::Left),
+            38 => return Some(Key::Up),
+            39 => return Some(Key::Right),
+            40 => return Some(Key::Down),
             84 => return Some(Key::T),
+            83 => return Some(Key::S),
+            65 => return Some(Key::A),
+            66 => return Some(Key::B),
+            76 => return Some(Key::L),
+            82 => return Some(Key::R),
             _other => return None,
         }
     }
@@ -0,0 +1,231 @@
+use common::{ Pass, Delays, TableFormat };
+
+pub const USAGE: &str = "usage: mimicry-emulator-headless <circuit|device|--sample|--fuzz <cases> [--seed <seed>]> [--cycles <count>] [--stimulus <file|random:<cycles>>] [--trace <file>] [--vcd <file>] [--verilog <file>] [--dot <file>] [--dot-states] [--all-signals] [--signal <name>=<expression>]... [--activity] [--power <technology|default>] [--power-trace <file>] [--optimize <all|constants,duplicates,dead,splitters>] [--equivalent <circuit>] [--timing] [--delays <and=1,or=1,xor=1,gate<index>=1>] [--clock-period <delay>] [--faults <stimulus|random:<cycles>>] [--truth-table <inputs>:<outputs>] [--table-format <text|csv|markdown>] [--table-unknown] [--restore <file>] [--snapshot <file>] [--break <condition>]... [--assert <expression>]...";
+
+pub const DEFAULT_CYCLES: usize = 100;
+const DEFAULT_SEED: u64 = 1;
+
+pub struct Arguments {
+    pub filename: String,
+    pub sample: bool,
+    // without a count a stimulus runs until it ends
+    pub cycles: Option<usize>,
+    pub stimulus: Option<String>,
+    // analysis reports without anything that needs a simulation skip it
+    pub simulate: bool,
+    pub trace: Option<String>,
+    pub vcd: Option<String>,
+    pub verilog: Option<String>,
+    pub dot: Option<String>,
+    pub dot_states: bool,
+    pub all_signals: bool,
+    pub activity: bool,
+    pub power: Option<String>,
+    pub power_trace: Option<String>,
+    pub passes: Vec<Pass>,
+    pub equivalent: Option<String>,
+    pub timing: bool,
+    pub delays: Delays,
+    pub clock_period: Option<f64>,
+    pub faults: Option<String>,
+    pub truth_table: Option<(Vec<String>, Vec<String>)>,
+    pub table_format: TableFormat,
+    pub table_unknown: bool,
+    pub restore: Option<String>,
+    pub snapshot: Option<String>,
+    pub breakpoints: Vec<String>,
+    pub derived_signals: Vec<(String, String)>,
+    pub fuzz: Option<usize>,
+    pub seed: u64,
+}
+
+impl Arguments {
+
+    pub fn parse<T: Iterator<Item = String>>(mut arguments: T) -> Result<Self, String> {
+
+        let mut filename = None;
+        let mut sample = false;
+        let mut cycles = None;
+        let mut stimulus = None;
+        let mut trace = None;
+        let mut vcd = None;
+        let mut verilog = None;
+        let mut dot = None;
+        let mut dot_states = false;
+        let mut all_signals = false;
+        let mut activity = false;
+        let mut power = None;
+        let mut power_trace = None;
+        let mut passes = Vec::new();
+        let mut equivalent = None;
+        let mut timing = false;
+        let mut delays = Delays::unit();
+        let mut clock_period = None;
+        let mut faults = None;
+        let mut truth_table = None;
+        let mut table_format = TableFormat::Text;
+        let mut table_unknown = false;
+        let mut restore = None;
+        let mut snapshot = None;
+        let mut breakpoints = Vec::new();
+        let mut derived_signals = Vec::new();
+        let mut fuzz = None;
+        let mut seed = DEFAULT_SEED;
+
+        while let Some(argument) = arguments.next() {
+            match argument.as_str() {
+
+                "--cycles" => {
+                    let value = Self::expect_value(&mut arguments, &argument)?;
+                    cycles = Some(value.parse().map_err(|_| format!("invalid cycle count {}", value))?);
+                },
+
+                "--stimulus" => stimulus = Some(Self::expect_value(&mut arguments, &argument)?),
+
+                "--trace" => trace = Some(Self::expect_value(&mut arguments, &argument)?),
+
+                "--vcd" => vcd = Some(Self::expect_value(&mut arguments, &argument)?),
+
+                "--verilog" => verilog = Some(Self::expect_value(&mut arguments, &argument)?),
+
+                "--dot" => dot = Some(Self::expect_value(&mut arguments, &argument)?),
+
+                "--dot-states" => dot_states = true,
+
+                "--all-signals" => all_signals = true,
+
+                "--activity" => activity = true,
+
+                "--power" => power = Some(Self::expect_value(&mut arguments, &argument)?),
+
+                "--power-trace" => power_trace = Some(Self::expect_value(&mut arguments, &argument)?),
+
+                "--optimize" => {
+                    let value = Self::expect_value(&mut arguments, &argument)?;
+                    passes = match value.as_str() {
+                        "all" => Pass::ALL.to_vec(),
+                        _other => value.split(',').map(Pass::from_name).collect::<Result<Vec<Pass>, String>>()?,
+                    };
+                },
+
+                "--equivalent" => equivalent = Some(Self::expect_value(&mut arguments, &argument)?),
+
+                "--timing" => timing = true,
+
+                // configuring delays or a clock period implies a timing report
+                "--delays" => {
+                    delays = Delays::parse(&Self::expect_value(&mut arguments, &argument)?)?;
+                    timing = true;
+                },
+
+                "--clock-period" => {
+                    let value = Self::expect_value(&mut arguments, &argument)?;
+                    clock_period = Some(value.parse().ok().filter(|period: &f64| period.is_finite() && *period > 0.0).ok_or_else(|| format!("invalid clock period {}", value))?);
+                    timing = true;
+                },
+
+                "--faults" => faults = Some(Self::expect_value(&mut arguments, &argument)?),
+
+                // comma separated names on either side, leaving a side empty selects all ports
+                "--truth-table" => {
+                    let value = Self::expect_value(&mut arguments, &argument)?;
+                    let (inputs, outputs) = value.rsplit_once(':').ok_or_else(|| format!("invalid truth table {}, expected <inputs>:<outputs>", value))?;
+                    let names = |names: &str| names.split(',').map(str::trim).filter(|name| !name.is_empty()).map(String::from).collect();
+                    truth_table = Some((names(inputs), names(outputs)));
+                },
+
+                "--table-format" => table_format = TableFormat::from_name(&Self::expect_value(&mut arguments, &argument)?)?,
+
+                "--table-unknown" => table_unknown = true,
+
+                "--restore" => restore = Some(Self::expect_value(&mut arguments, &argument)?),
+
+                "--snapshot" => snapshot = Some(Self::expect_value(&mut arguments, &argument)?),
+
+                "--break" => breakpoints.push(Self::expect_value(&mut arguments, &argument)?),
+
+                "--assert" => breakpoints.push(format!("assert {}", Self::expect_value(&mut arguments, &argument)?)),
+
+                "--signal" => {
+                    let value = Self::expect_value(&mut arguments, &argument)?;
+                    let (name, expression) = value.split_once('=').ok_or_else(|| format!("derived signal {} is not of the form <name>=<expression>", value))?;
+                    derived_signals.push((String::from(name.trim()), String::from(expression.trim())));
+                },
+
+                "--fuzz" => {
+                    let value = Self::expect_value(&mut arguments, &argument)?;
+                    fuzz = Some(value.parse().map_err(|_| format!("invalid case count {}", value))?);
+                },
+
+                "--seed" => {
+                    let value = Self::expect_value(&mut arguments, &argument)?;
+                    seed = value.parse().map_err(|_| format!("invalid seed {}", value))?;
+                },
+
+                "--sample" => sample = true,
+
+                flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+
+                _other => {
+                    match filename {
+                        Some(..) => return Err(format!("unexpected argument {}", argument)),
+                        None => filename = Some(argument),
+                    }
+                },
+            }
+        }
+
+        // fuzzing generates its own circuits and the sample circuit is built in
+        let filename = match (filename, sample || fuzz.is_some()) {
+            (Some(..), true) if sample => return Err(String::from("a circuit can not be combined with --sample")),
+            (Some(filename), _) => filename,
+            (None, true) => String::new(),
+            (None, false) => return Err(String::from("no circuit specified")),
+        };
+
+        // a power trace needs an estimate, which uses the default technology unless one was given
+        if power_trace.is_some() && power.is_none() {
+            power = Some(String::from("default"));
+        }
+
+        let analysis = equivalent.is_some() || timing || faults.is_some() || truth_table.is_some();
+        let simulation = cycles.is_some() || stimulus.is_some() || trace.is_some() || vcd.is_some() || dot_states || all_signals || activity
+            || power.is_some() || restore.is_some() || snapshot.is_some() || !breakpoints.is_empty() || !derived_signals.is_empty();
+
+        return Ok(Self {
+            filename: filename,
+            sample: sample,
+            cycles: cycles,
+            stimulus: stimulus,
+            simulate: simulation || !analysis,
+            trace: trace,
+            vcd: vcd,
+            verilog: verilog,
+            dot: dot,
+            dot_states: dot_states,
+            all_signals: all_signals,
+            activity: activity,
+            power: power,
+            power_trace: power_trace,
+            passes: passes,
+            equivalent: equivalent,
+            timing: timing,
+            delays: delays,
+            clock_period: clock_period,
+            faults: faults,
+            truth_table: truth_table,
+            table_format: table_format,
+            table_unknown: table_unknown,
+            restore: restore,
+            snapshot: snapshot,
+            breakpoints: breakpoints,
+            derived_signals: derived_signals,
+            fuzz: fuzz,
+            seed: seed,
+        });
+    }
+
+    fn expect_value<T: Iterator<Item = String>>(arguments: &mut T, flag: &str) -> Result<String, String> {
+        return arguments.next().ok_or_else(|| format!("missing value for {}", flag));
+    }
+}
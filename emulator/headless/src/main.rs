@@ -0,0 +1,232 @@
+extern crate common;
+
+mod arguments;
+
+use common::*;
+use self::arguments::{ Arguments, USAGE, DEFAULT_CYCLES };
+use std::process::exit;
+use std::fs::{ read_to_string, write };
+
+// the inspector still lays out its items, even if nothing is ever drawn
+const INTERFACE_SIZE: FloatVector = FloatVector::from(1800.0, 900.0);
+
+// random stimuli are reproducible between runs
+const RANDOM_SEED: u64 = 0x5eed;
+
+const EXIT_ERROR: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_BREAKPOINT: i32 = 3;
+const EXIT_MISMATCH: i32 = 4;
+
+// the stimulus is either a file or a number of cycles of random inputs
+fn load_stimulus(stimulus: &str, netlist: &Netlist) -> Result<Stimulus, String> {
+    match stimulus.strip_prefix("random:") {
+        Some(cycles) => return Ok(Stimulus::random(netlist, cycles.parse().map_err(|_| format!("invalid cycle count {}", cycles))?, RANDOM_SEED)),
+        None => return Stimulus::parse(&read_to_string(stimulus).map_err(|error| format!("failed to read stimulus {}: {}", stimulus, error))?).map_err(|error| format!("{}: {}", stimulus, error)),
+    }
+}
+
+// returns the exit code, which tells if the cores differ or the simulation was stopped by a breakpoint
+fn run(arguments: Arguments) -> Result<i32, String> {
+
+    if let Some(cases) = arguments.fuzz {
+        let report = fuzz(cases, arguments.seed)?;
+        println!("{}", report);
+        match report.divergence {
+            Some(..) => return Ok(EXIT_MISMATCH),
+            None => return Ok(0),
+        }
+    }
+
+    let mut device = match arguments.filename.ends_with(".device") {
+        _ if arguments.sample => Device::new(INTERFACE_SIZE)?,
+        true => Device::from_description(arguments.filename, INTERFACE_SIZE, true)?,
+        false => Device::load(arguments.filename, INTERFACE_SIZE, true)?,
+    };
+
+    if !arguments.passes.is_empty() {
+        device.optimize(&arguments.passes).iter().for_each(|(core, report)| println!("{}: {}", core, report));
+    }
+
+    // runs after the optimization so the optimized core can be checked against the original
+    let mut equivalent = true;
+    if let Some(filename) = arguments.equivalent {
+        let other = Core::load(filename, INTERFACE_SIZE, false)?;
+        let report = match device.cores().as_slice() {
+            [core] => core.check_equivalence(&other)?,
+            _cores => return Err(String::from("equivalence checking needs a single core")),
+        };
+        println!("{}", report);
+        equivalent = report.is_equivalent();
+    }
+
+    if arguments.timing {
+        for core in device.cores() {
+            println!("{}: {}", core.name(), core.timing(&arguments.delays, arguments.clock_period)?);
+        }
+    }
+
+    if let Some(faults) = &arguments.faults {
+        for core in device.cores() {
+            let stimulus = load_stimulus(faults, &core.netlist())?;
+            println!("{}: {}", core.name(), core.simulate_faults(&stimulus)?);
+        }
+    }
+
+    if let Some((inputs, outputs)) = &arguments.truth_table {
+        for core in device.cores() {
+            let table = core.truth_table(inputs, outputs, arguments.table_unknown)?;
+            println!("{}: {} rows", core.name(), table.rows.len());
+            println!("{}", table.format(arguments.table_format).trim_end());
+        }
+    }
+
+    // analysis reports on their own stop here
+    let mut stopped = false;
+    if arguments.simulate {
+
+        if arguments.all_signals {
+            device.track_all();
+        }
+
+        if arguments.activity {
+            device.track_activity();
+        }
+
+        if let Some(technology) = &arguments.power {
+            let technology = match technology.as_str() {
+                "default" => Technology::unit(),
+                filename => Technology::parse(&read_to_string(filename).map_err(|error| format!("failed to read technology {}: {}", filename, error))?).map_err(|error| format!("{}: {}", filename, error))?,
+            };
+            device.estimate_power(&technology);
+        }
+
+        if let Some(filename) = &arguments.restore {
+            device.load_snapshot(filename)?;
+        }
+
+        for (name, expression) in &arguments.derived_signals {
+            device.add_derived_signal(name, expression)?;
+        }
+
+        for condition in &arguments.breakpoints {
+            device.add_breakpoint(condition)?;
+        }
+
+        // the port states of every cycle for every core
+        let mut stimuli = Vec::new();
+        if let Some(stimulus) = &arguments.stimulus {
+            for core in device.cores() {
+                let netlist = core.netlist();
+                stimuli.push(load_stimulus(stimulus, &netlist)?.resolve(&netlist).map_err(|error| format!("{}: {}", core.name(), error))?);
+            }
+        }
+
+        // inputs keep their last state once the stimulus ends
+        let cycles = arguments.cycles.unwrap_or_else(|| stimuli.iter().map(Vec::len).max().unwrap_or(DEFAULT_CYCLES));
+        for cycle in 0..cycles {
+
+            for (core, states) in stimuli.iter().enumerate() {
+                if let Some(states) = states.get(cycle) {
+                    states.iter().for_each(|(port, state)| device.set_port_state(core, *port, *state));
+                }
+            }
+
+            if device.run(1) {
+                stopped = true;
+                break;
+            }
+        }
+
+        device.hits().iter().for_each(|hit| println!("{}", hit));
+
+        for core in device.cores() {
+            println!("{}: {} cycles", core.name(), core.cycle_count());
+            core.ports().iter().for_each(|port| println!("  port {} {}", port.name, format!("{:?}", port.state).to_lowercase()));
+
+            if let Some(states) = core.states() {
+                let width = states.iter().map(|(path, _state)| path.len()).max().unwrap_or(0);
+                states.iter().for_each(|(path, state)| println!("  {:width$}  {}", path, state, width = width));
+            }
+
+            if let Some(activity) = core.activity() {
+                println!("  activity: {}", activity.to_string().replace("\n", "\n  "));
+            }
+
+            if let Some(power) = core.power()? {
+                println!("  power: {}", power.to_string().replace("\n", "\n  "));
+            }
+        }
+
+        if let Some(filename) = &arguments.power_trace {
+            let mut trace = String::from("core,cycle,power\n");
+
+            for core in device.cores() {
+                if let Some(power) = core.power()? {
+                    power.trace.iter().enumerate().for_each(|(cycle, value)| trace.push_str(&format!("{},{},{}\n", core.name(), cycle, value)));
+                }
+            }
+
+            write(filename, trace).map_err(|error| format!("failed to write power trace {}: {}", filename, error))?;
+        }
+
+        if let Some(filename) = arguments.trace {
+            let mut trace = String::new();
+
+            for core in device.cores() {
+                if let Some(core_trace) = core.trace() {
+                    trace.push_str(&format!("# {}\n", core.name()));
+                    trace.push_str(&core_trace);
+                }
+            }
+
+            write(&filename, trace).map_err(|error| format!("failed to write trace {}: {}", filename, error))?;
+        }
+
+        if let Some(filename) = &arguments.snapshot {
+            device.save_snapshot(filename)?;
+        }
+
+        if let Some(filename) = arguments.vcd {
+            write(&filename, device.vcd()).map_err(|error| format!("failed to write vcd {}: {}", filename, error))?;
+        }
+    }
+
+    if let Some(filename) = arguments.verilog {
+        write(&filename, device.verilog()?).map_err(|error| format!("failed to write verilog {}: {}", filename, error))?;
+    }
+
+    if let Some(filename) = arguments.dot {
+        write(&filename, device.dot(arguments.dot_states)).map_err(|error| format!("failed to write dot {}: {}", filename, error))?;
+    }
+
+    match (equivalent, stopped) {
+        (false, _) => return Ok(EXIT_MISMATCH),
+        (true, true) => return Ok(EXIT_BREAKPOINT),
+        (true, false) => return Ok(0),
+    }
+}
+
+fn main() {
+
+    let arguments = match Arguments::parse(std::env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(message) => {
+            eprintln!("{}", message);
+            eprintln!("{}", USAGE);
+            exit(EXIT_USAGE);
+        },
+    };
+
+    match run(arguments) {
+
+        Ok(0) => { },
+
+        Ok(code) => exit(code),
+
+        Err(message) => {
+            eprintln!("error: {}", message);
+            exit(EXIT_ERROR);
+        },
+    }
+}
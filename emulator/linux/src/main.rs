@@ -6,11 +6,26 @@ mod renderer;
 use common::*;
 use self::renderer::SFMLRenderer;
 use sfml::window::Event;
+use std::time::Instant;
+
+const USAGE: &'static str = "usage: mimicry-emulator [circuit|device|recording] [--break <condition>]...";
+const EXIT_USAGE: i32 = 2;
 
 fn from_sfml_key(key: sfml::window::Key) -> Option<Key> {
     match key {
         sfml::window::Key::SPACE => return Some(Key::Space),
+        sfml::window::Key::LEFT => return Some(Key::Left),
+        sfml::window::Key::RIGHT => return Some(Key::Right),
+        sfml::window::Key::UP => return Some(Key::Up),
+        sfml::window::Key::DOWN => return Some(Key::Down),
         sfml::window::Key::T => return Some(Key::T),
+        sfml::window::Key::A => return Some(Key::A),
+        sfml::window::Key::B => return Some(Key::B),
+        sfml::window::Key::S => return Some(Key::S),
+        sfml::window::Key::L => return Some(Key::L),
+        sfml::window::Key::R => return Some(Key::R),
+        sfml::window::Key::TAB => return Some(Key::Tab),
+        sfml::window::Key::ESCAPE => return Some(Key::Escape),
         _other => return None,
     }
 }
@@ -22,9 +37,45 @@ fn main() {
     let window_size = FloatVector::from(1800.0, 900.0);
     let vertical_synch = true;
 
-    let mut device = Device::new(window_size);
+    let mut filename = None;
+    let mut breakpoints = Vec::new();
+    let mut arguments = std::env::args().skip(1);
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--break" => match arguments.next() {
+                Some(condition) => breakpoints.push(condition),
+                None => {
+                    eprintln!("missing value for --break");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(EXIT_USAGE);
+                },
+            },
+            _other => filename = Some(argument),
+        }
+    }
+
+    let device = match filename {
+        Some(filename) if filename.ends_with(".vcd") => Device::replay(filename, window_size),
+        Some(filename) if filename.ends_with(".device") => Device::from_description(filename, window_size, true),
+        Some(filename) => Device::load(filename, window_size, true),
+        None => Device::new(window_size),
+    };
+
+    let mut device = device.and_then(|mut device| {
+        for condition in &breakpoints {
+            device.add_breakpoint(condition)?;
+        }
+        Ok(device)
+    }).unwrap_or_else(|message| {
+        eprintln!("error: {}", message);
+        std::process::exit(1);
+    });
+
     let mut renderer = SFMLRenderer::new(window_title, window_size, vertical_synch, "/usr/share/tortoise/mimicry/emulator/assets/monaco.ttf");
 
+    let mut last_update = Instant::now();
+
     loop {
         while let Some(event) = renderer.event() {
             match event {
@@ -46,6 +97,11 @@ fn main() {
             }
         }
 
+        // the simulation runs at its own rate, independent of how often the window is redrawn
+        let now = Instant::now();
+        device.update(now.duration_since(last_update).as_secs_f32());
+        last_update = now;
+
         device.render(&mut renderer);
     }
 }